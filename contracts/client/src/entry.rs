@@ -1,13 +1,14 @@
 use cosmwasm_std::{
-    entry_point, to_json_binary, Deps, DepsMut, Empty, Env, IbcBasicResponse, IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcChannelOpenResponse, IbcMsg, IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse, IbcTimeout, MessageInfo, QueryResponse, Response
+    entry_point, Addr, Ibc3ChannelOpenResponse, to_json_binary, Deps, DepsMut, Empty, Env, IbcBasicResponse, IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcChannelOpenResponse, IbcMsg, IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse, IbcTimeout, MessageInfo, QueryResponse, Reply, Response
 };
 use cw2::{get_contract_version, set_contract_version};
+use cw_utils::Expiration;
 use shared::{
-    ibc::TIMEOUT_SECONDS, msg::{chat_message::{ChatMessage, ChatMessageWithIndex}, contract::client::{ChatMessagesResp, ExecuteMsg, InfoResp, InstantiateMsg, QueryMsg}, ibc::IbcExecuteMsg}, response::{QueryResponseExt, ResponseBuilder}
+    ibc::{channel_protocol_version, IbcAck, TIMEOUT_SECONDS}, msg::{chat_message::{validate_expiration, ChatMessage, ChatMessageKind, ChatMessageWithIndex}, command::ChatCommand, content::MessageContent, identity::identity_of_address, contract::client::{BlockedResp, ChatMessagesResp, ExecuteMsg, HooksResp, IdentityResp, InfoResp, InstantiateMsg, MentionsResp, NonceResp, PinsResp, OutboxResp, PollsResp, ProfileResp, QueryMsg, RoomAccessResp, RoomKeysResp, RoomsResp, SudoMsg, TipsResp, UnreadCountsResp, UnreadMentionsResp}, ibc::IbcExecuteMsg, poll::{validate_poll, Voter}, room::{RoomAccess, RoomId}}, response::{QueryResponseExt, ResponseBuilder}
 };
 use anyhow::{Context, Result};
 
-use crate::state::{State, StateContext};
+use crate::state::{hook::HOOK_REPLY_ID, tip::TIP_TRANSFER_REPLY_ID, State, StateContext};
 
 // version info for migration info
 const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
@@ -17,13 +18,18 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 pub fn instantiate(
     deps: DepsMut,
     env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     let (state, mut ctx) = StateContext::new(deps, env)?;
     state.set_network_id(&mut ctx, msg.network_id)?;
+    state.set_channel_order(&mut ctx, msg.channel_order.unwrap_or_default())?;
+    state.set_owner(&mut ctx, &info.sender)?;
+
+    let nois_proxy = msg.nois_proxy.map(|proxy| state.api.addr_validate(&proxy)).transpose()?;
+    state.set_nois_proxy(&mut ctx, nois_proxy.as_ref())?;
 
 
 
@@ -35,43 +41,226 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> R
     let (state, mut ctx) = StateContext::new(deps, env)?;
 
     match msg {
-        ExecuteMsg::SendMessage { message } => {
-            let network_id = state.get_network_id(ctx.store)?;
-            let message = ChatMessage {
+        ExecuteMsg::SendMessage { message, room, expires, content } => {
+            // We send it to the server for broadcasting
+            // along with anything that was buffered before it, to keep the order
+            // If there's no server channel yet, this lands in the outbox
+            if let Some(msg) = store_outgoing_message(&state, &mut ctx, &info, message, room, expires.unwrap_or_default(), content.unwrap_or_default())? {
+                state.buffer_for_server(&mut ctx, msg)?;
+            }
+            state.flush_send_buffer(&mut ctx)?;
+        },
+        ExecuteMsg::SendSignedMessage { payload, pubkey, signature } => {
+            let (signer, payload) = state.verify_signed_message(&mut ctx, &payload, &pubkey, &signature)?;
+
+            // whoever submitted it only paid for the gas, the funds aren't the signer's
+            let info = MessageInfo { sender: signer, funds: Vec::new() };
+            if let Some(msg) = store_outgoing_message(&state, &mut ctx, &info, payload.message, payload.room, Expiration::Never {}, payload.content)? {
+                state.buffer_for_server(&mut ctx, msg)?;
+            }
+            state.flush_send_buffer(&mut ctx)?;
+        },
+        ExecuteMsg::LinkIdentity { address, pubkey, signature } => {
+            let signature = state.check_identity_link(&info.sender, address, pubkey, signature)?;
+
+            // stored when the server sends it back, like everyone else
+            state.buffer_for_server(&mut ctx, IbcExecuteMsg::LinkIdentity { signature })?;
+            state.flush_send_buffer(&mut ctx)?;
+        },
+        ExecuteMsg::BufferMessage { message, room, expires, content } => {
+            if let Some(msg) = store_outgoing_message(&state, &mut ctx, &info, message, room, expires.unwrap_or_default(), content.unwrap_or_default())? {
+                state.buffer_for_server(&mut ctx, msg)?;
+            }
+        },
+        ExecuteMsg::CreateRoom { name, access } => {
+            let access = state.validate_room_access(access.unwrap_or(RoomAccess::Open))?;
+            let room = state.create_room(&mut ctx, &info.sender, name, access)?;
+
+            state.buffer_for_server(&mut ctx, IbcExecuteMsg::RoomCreated { room })?;
+            state.flush_send_buffer(&mut ctx)?;
+        },
+        ExecuteMsg::SetRoomAccess { room, access } => {
+            let access = state.validate_room_access(access)?;
+            let room = state.set_room_access(&mut ctx, &info.sender, &room, access)?;
+
+            state.buffer_for_server(&mut ctx, IbcExecuteMsg::RoomUpdated { room })?;
+            state.flush_send_buffer(&mut ctx)?;
+        },
+        ExecuteMsg::SetEncryptionKey { key } => {
+            state.set_encryption_key(&mut ctx, &info.sender, key)?;
+        },
+        ExecuteMsg::CreatePrivateRoom { name, keys } => {
+            let room = state.create_private_room(&mut ctx, &info.sender, name, keys)?;
+
+            state.buffer_for_server(&mut ctx, IbcExecuteMsg::RoomCreated { room })?;
+            state.flush_send_buffer(&mut ctx)?;
+        },
+        ExecuteMsg::AddRoomMember { room, key } => {
+            let room = state.add_room_member(&mut ctx, &info.sender, &room, key)?;
+
+            state.buffer_for_server(&mut ctx, IbcExecuteMsg::RoomUpdated { room })?;
+            state.flush_send_buffer(&mut ctx)?;
+        },
+        ExecuteMsg::RemoveRoomMember { room, member, keys } => {
+            let member = state.api.addr_validate(&member)?;
+            let room = state.remove_room_member(&mut ctx, &info.sender, &room, &member, keys)?;
+
+            state.buffer_for_server(&mut ctx, IbcExecuteMsg::RoomUpdated { room })?;
+            state.flush_send_buffer(&mut ctx)?;
+        },
+        ExecuteMsg::LeaveRoom { room } => {
+            let room = state.leave_room(&mut ctx, &info.sender, &room)?;
+
+            state.buffer_for_server(&mut ctx, IbcExecuteMsg::RoomUpdated { room })?;
+            state.flush_send_buffer(&mut ctx)?;
+        },
+        ExecuteMsg::RotateRoomKey { room, keys } => {
+            let room = state.set_room_key(&mut ctx, &info.sender, &room, keys)?;
+
+            state.buffer_for_server(&mut ctx, IbcExecuteMsg::RoomUpdated { room })?;
+            state.flush_send_buffer(&mut ctx)?;
+        },
+        ExecuteMsg::CreatePoll { question, options, closes_at } => {
+            validate_poll(&question, &options, closes_at, state.env.block.time)?;
+
+            let creator = Voter {
+                network_id: state.get_network_id(ctx.store)?,
                 user: info.sender.clone(),
-                network_id,
-                message: message.clone(),
             };
-            // First we store the message in our local state
-            let index = state.push_chat_message(&mut ctx, message.clone())?;
 
-            // Then we send it to the server for broadcasting
-            let msg = IbcExecuteMsg::SendMessageToServer { 
-                message: ChatMessageWithIndex {
-                    msg: message,
-                    index
-                }
+            state.buffer_for_server(&mut ctx, IbcExecuteMsg::CreatePoll { question, options, closes_at, creator })?;
+            state.flush_send_buffer(&mut ctx)?;
+        },
+        ExecuteMsg::Vote { poll_id, option } => {
+            state.check_vote(ctx.store, poll_id, option)?;
+
+            let voter = Voter {
+                network_id: state.get_network_id(ctx.store)?,
+                user: info.sender.clone(),
             };
-    
-            // outbound IBC message, where packet is then received on other chain
-            let channel_id = state
-                .get_server_channel(ctx.store)?
-                .context("server channel not set")?
-                .endpoint
-                .channel_id;
-    
-            ctx.response_mut().add_message(IbcMsg::SendPacket {
-                channel_id,
-                data: to_json_binary(&msg)?,
-                timeout: IbcTimeout::with_timestamp(state.env.block.time.plus_seconds(TIMEOUT_SECONDS)),
-            });
 
+            state.buffer_for_server(&mut ctx, IbcExecuteMsg::Vote { poll_id, option, voter })?;
+            state.flush_send_buffer(&mut ctx)?;
+        },
+        ExecuteMsg::FlushBuffer {  } => {
+            state.flush_send_buffer(&mut ctx)?;
+        },
+        ExecuteMsg::PurgeOutbox {  } => {
+            state.ensure_owner(ctx.store, &info.sender)?;
+            state.purge_outbox(&mut ctx)?;
+        },
+        ExecuteMsg::Tip { index } => {
+            let message = state.tip(&mut ctx, &info, index)?;
+            let index = state.push_chat_message(&mut ctx, message.clone())?;
+
+            state.buffer_for_server(&mut ctx, IbcExecuteMsg::SendMessageToServer {
+                message: ChatMessageWithIndex { msg: message, index },
+            })?;
+            state.flush_send_buffer(&mut ctx)?;
+        },
+        ExecuteMsg::SetTransferChannel { network_id, channel_id } => {
+            state.ensure_owner(ctx.store, &info.sender)?;
+            state.set_transfer_channel(&mut ctx, network_id, channel_id)?;
+        },
+        ExecuteMsg::SetNoisProxy { proxy } => {
+            state.ensure_owner(ctx.store, &info.sender)?;
+            let proxy = proxy.map(|proxy| state.api.addr_validate(&proxy)).transpose()?;
+            state.set_nois_proxy(&mut ctx, proxy.as_ref())?;
+        },
+        ExecuteMsg::NoisReceive { callback } => {
+            state.handle_nois_callback(&mut ctx, &info.sender, callback)?;
+        },
+        ExecuteMsg::Block { network_id, address } => {
+            state.block(&mut ctx, &info.sender, network_id, &address)?;
+        },
+        ExecuteMsg::Unblock { network_id, address } => {
+            state.unblock(&mut ctx, &info.sender, network_id, &address)?;
+        },
+        ExecuteMsg::Pin { index } => {
+            let pin = state.check_pin(ctx.store, &info.sender, index)?;
+
+            state.buffer_for_server(&mut ctx, IbcExecuteMsg::UpdatePin { pin, pinned: true })?;
+            state.flush_send_buffer(&mut ctx)?;
+        },
+        ExecuteMsg::Unpin { room, id } => {
+            let pin = state.check_unpin(ctx.store, &info.sender, room, id)?;
+
+            state.buffer_for_server(&mut ctx, IbcExecuteMsg::UpdatePin { pin, pinned: false })?;
+            state.flush_send_buffer(&mut ctx)?;
+        },
+        ExecuteMsg::SetReadCursor { room, index } => {
+            state.set_read_cursor(&mut ctx, &info.sender, room, index)?;
+        },
+        ExecuteMsg::MarkRead { up_to } => {
+            state.mark_mentions_read(&mut ctx, &info.sender, up_to)?;
+        },
+        ExecuteMsg::SetHook { contract, filter } => {
+            state.ensure_owner(ctx.store, &info.sender)?;
+            let contract = state.api.addr_validate(&contract)?;
+            state.set_hook(&mut ctx, &contract, filter.unwrap_or_default())?;
+        },
+        ExecuteMsg::RemoveHook { contract } => {
+            state.ensure_owner(ctx.store, &info.sender)?;
+            let contract = state.api.addr_validate(&contract)?;
+            state.remove_hook(&mut ctx, &contract)?;
         }
     }
 
     Ok(ctx.response.into_response())
 }
 
+// stores the message in our local state, and returns the packet for the server
+// commands are handled here instead, and only some of them have anything to send
+// only plain text can be a command
+fn store_outgoing_message(state: &State, ctx: &mut StateContext, info: &MessageInfo, message: String, room: Option<RoomId>, expires: Expiration, content: MessageContent) -> Result<Option<IbcExecuteMsg>> {
+    if expires.is_expired(&state.env.block) {
+        anyhow::bail!("the message would already be expired");
+    }
+
+    let command = match content {
+        MessageContent::Text => ChatCommand::parse(&message),
+        _ => None,
+    };
+
+    let mut message = match command {
+        Some(command) => match state.handle_chat_command(ctx, info, command)? {
+            Some(message) => message,
+            None => return Ok(None),
+        },
+        None => ChatMessage {
+            user: info.sender.clone(),
+            network_id: state.get_network_id(ctx.store)?,
+            message,
+            kind: ChatMessageKind::Text,
+            content,
+            room: None,
+            attestation: None,
+            expires: Expiration::Never {},
+        },
+    };
+    message.expires = expires;
+    message.content.validate(message.kind, &message.message)?;
+
+    if let Some(room) = room {
+        message.attestation = state.check_room_access(ctx.store, &room, &info.sender)?;
+        state.check_encrypted_message(ctx.store, &room, &message)?;
+        message.room = Some(room);
+    }
+    let index = state.push_chat_message(ctx, message.clone())?;
+
+    // the height is this chain's, so the message stays here
+    if validate_expiration(&message.expires).is_err() {
+        return Ok(None);
+    }
+
+    Ok(Some(IbcExecuteMsg::SendMessageToServer { 
+        message: ChatMessageWithIndex {
+            msg: message,
+            index
+        }
+    }))
+}
+
 #[entry_point]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<QueryResponse> {
     let (state, store) = State::new(deps, env)?;
@@ -81,19 +270,153 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<QueryResponse> {
             let server_channel = state.get_server_channel(store)?;
             let info = InfoResp {
                 server_channel,
+                dead_server_channel: state.get_dead_server_channel(store)?,
                 network_id: state.get_network_id(store)?,
+                channel_order: state.get_channel_order(store)?,
+                owner: state.get_owner(store)?,
+                topic: state.get_topic(store)?,
+                nois_proxy: state.get_nois_proxy(store)?,
+                transfer_channels: state.get_transfer_channels(store)?,
             };
             info.query_result()
         },
-        QueryMsg::ChatMessages { after_index, order } => {
-            let messages = state.get_chat_messages(store, after_index, order.map(|order| order.into()))?;
+        QueryMsg::Outbox {  } => {
+            let chat_messages = |msgs: Vec<IbcExecuteMsg>| msgs
+                .into_iter()
+                .flat_map(IbcExecuteMsg::into_items)
+                .filter_map(|msg| match msg {
+                    IbcExecuteMsg::SendMessageToServer { message } => Some(message),
+                    _ => None,
+                })
+                .collect();
+
+            OutboxResp {
+                messages: chat_messages(state.get_outbox(store)?),
+                buffered: chat_messages(state.get_send_buffer(store)?),
+            }.query_result()
+        },
+        QueryMsg::Profile { user } => {
+            // just a lookup, an address we've never seen simply has no profile
+            let user = Addr::unchecked(user);
+            ProfileResp {
+                nickname: state.get_nickname(store, &user)?,
+                encryption_key: state.get_encryption_key(store, &user)?,
+            }.query_result()
+        },
+        QueryMsg::MessageTips { index } => {
+            TipsResp {
+                total: state.get_message_tips(store, index)?,
+            }.query_result()
+        },
+        QueryMsg::UserTips { network_id, address } => {
+            TipsResp {
+                total: state.get_user_tips(store, network_id, &address)?,
+            }.query_result()
+        },
+        QueryMsg::Polls {  } => {
+            PollsResp {
+                polls: state.get_polls(store)?,
+            }.query_result()
+        },
+        QueryMsg::Rooms {  } => {
+            RoomsResp {
+                rooms: state.get_rooms(store)?,
+            }.query_result()
+        },
+        QueryMsg::RoomAccess { room, user } => {
+            // like the profile, just a lookup
+            let resp = match state.check_room_access(store, &room, &Addr::unchecked(user)) {
+                Ok(_) => RoomAccessResp { qualifies: true, reason: None },
+                Err(err) => RoomAccessResp { qualifies: false, reason: Some(err.to_string()) },
+            };
+            resp.query_result()
+        },
+        QueryMsg::Mentions { user, after_index, order, limit, unread_only } => {
+            let user = Addr::unchecked(user);
+            MentionsResp {
+                mentions: state.get_mentions(store, &user, after_index, order.map(|order| order.into()), limit, unread_only.unwrap_or_default())?,
+            }.query_result()
+        },
+        QueryMsg::UnreadMentions { user } => {
+            UnreadMentionsResp {
+                count: state.get_unread_mention_count(store, &Addr::unchecked(user))?,
+            }.query_result()
+        },
+        QueryMsg::Pins { room } => {
+            PinsResp {
+                pins: state.get_pins(store, room.as_ref())?,
+            }.query_result()
+        },
+        QueryMsg::UnreadCounts { user } => {
+            UnreadCountsResp {
+                rooms: state.get_unread_counts(store, &Addr::unchecked(user))?,
+            }.query_result()
+        },
+        QueryMsg::Blocked { viewer } => {
+            BlockedResp {
+                blocked: state.get_blocked(store, &Addr::unchecked(viewer))?,
+            }.query_result()
+        },
+        QueryMsg::Identity { address } => {
+            let identity = state.resolve_identity(store, &identity_of_address(&address)?)?;
+            IdentityResp {
+                members: state.get_identity_members(store, &identity)?,
+                identity,
+            }.query_result()
+        },
+        QueryMsg::RoomKeys { room, member } => {
+            let keys = state.get_room_keys(store, &room, &Addr::unchecked(member))?;
+            let epoch = match state.get_room(store, &room)?.access {
+                RoomAccess::Private { epoch, .. } => epoch,
+                _ => 0,
+            };
+            RoomKeysResp { epoch, keys }.query_result()
+        },
+        QueryMsg::Nonce { user } => {
+            NonceResp {
+                nonce: state.get_nonce(store, &Addr::unchecked(user))?,
+            }.query_result()
+        },
+        QueryMsg::Hooks {  } => {
+            HooksResp {
+                hooks: state.get_hooks(store)?,
+            }.query_result()
+        },
+        QueryMsg::ChatMessages { after_index, order, limit, viewer } => {
+            let viewer = viewer.map(Addr::unchecked);
+            let messages = state.get_chat_messages(store, after_index, order.map(|order| order.into()), limit, viewer.as_ref())?;
             ChatMessagesResp {
-                messages
+                identities: state.get_user_identities(store, &messages)?,
+                messages,
             }.query_result()
         }
     }
 }
 
+#[entry_point]
+pub fn reply(deps: DepsMut, env: Env, reply: Reply) -> Result<Response> {
+    let (state, mut ctx) = StateContext::new(deps, env)?;
+
+    match reply.id {
+        HOOK_REPLY_ID => state.handle_hook_reply(&mut ctx, reply)?,
+        TIP_TRANSFER_REPLY_ID => state.handle_tip_transfer_reply(&mut ctx, reply)?,
+        id => anyhow::bail!("unknown reply id {}", id),
+    }
+
+    Ok(ctx.response.into_response())
+}
+
+#[entry_point]
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response> {
+    let (state, mut ctx) = StateContext::new(deps, env)?;
+
+    match msg {
+        SudoMsg::IbcLifecycleComplete(complete) => state.settle_tip(&mut ctx, complete)?,
+    }
+
+    Ok(ctx.response.into_response())
+}
+
 #[entry_point]
 pub fn migrate(deps: DepsMut, env: Env, msg: Empty) -> Result<Response> {
     let (state, mut ctx) = StateContext::new(deps, env)?;
@@ -108,9 +431,9 @@ pub fn ibc_channel_open(
     env: Env,
     msg: IbcChannelOpenMsg,
 ) -> Result<IbcChannelOpenResponse> {
-    let (state, _) = StateContext::new(deps, env)?;
-    state.handle_ibc_channel_open(msg)?;
-    Ok(None)
+    let (state, ctx) = StateContext::new(deps, env)?;
+    let version = state.handle_ibc_channel_open(ctx.store, msg)?;
+    Ok(Some(Ibc3ChannelOpenResponse { version }))
 }
 
 #[entry_point]
@@ -142,15 +465,19 @@ pub fn ibc_packet_receive(
     msg: IbcPacketReceiveMsg,
 ) -> Result<IbcReceiveResponse> {
     let (state, mut ctx) = StateContext::new(deps, env)?;
-    state.handle_ibc_packet_receive(&mut ctx, msg)?;
 
-    Ok(ctx.response.into_ibc_recv_response_success(None))
+    // Always acknowledge instead of aborting, so the sender finds out what went wrong.
+    // A failed packet leaves nothing behind, its writes are dropped along with the partial response
+    match ctx.transactional(|ctx| state.handle_ibc_packet_receive(ctx, msg)) {
+        Ok(success) => ctx.response.into_ibc_recv_response_ack(&IbcAck::Success(success)),
+        Err(err) => ResponseBuilder::new_mute_events().into_ibc_recv_response_ack(&IbcAck::from(err)),
+    }
 }
 
 #[entry_point]
 pub fn ibc_packet_ack(deps: DepsMut, env: Env, ack: IbcPacketAckMsg) -> Result<IbcBasicResponse> {
-    let (state, ctx) = StateContext::new(deps, env)?;
-    state.handle_ibc_packet_ack(ack)?;
+    let (state, mut ctx) = StateContext::new(deps, env)?;
+    state.handle_ibc_packet_ack(&mut ctx, ack)?;
     Ok(ctx.response.into_ibc_response())
 }
 
@@ -160,7 +487,7 @@ pub fn ibc_packet_timeout(
     env: Env,
     msg: IbcPacketTimeoutMsg,
 ) -> Result<IbcBasicResponse> {
-    let (state, ctx) = StateContext::new(deps, env)?;
-    state.handle_ibc_packet_timeout(msg)?;
+    let (state, mut ctx) = StateContext::new(deps, env)?;
+    state.handle_ibc_packet_timeout(&mut ctx, msg)?;
     Ok(ctx.response.into_ibc_response())
 }
@@ -1,13 +1,26 @@
 pub mod ibc;
 pub mod chat;
 pub mod meta;
+pub mod hook;
+pub mod command;
+pub mod poll;
+pub mod roll;
+pub mod room;
+pub mod block;
+pub mod tip;
+pub mod signed;
+pub mod identity;
+pub mod private_room;
+pub mod mention;
+pub mod read_cursor;
+pub mod pin;
 
 /// Generally speaking - all entry points get a State (read-only)
 /// instantiate/execute/migrate get that _and_ a StateContext (writable)
 use cosmwasm_std::{Api, Deps, DepsMut, Empty, Env, QuerierWrapper, Storage};
 use anyhow::Result;
 use cw2::get_contract_version;
-use shared::{contract_kind::ContractKind, response::ResponseBuilder};
+use shared::{contract_kind::ContractKind, response::ResponseBuilder, storage::transactional};
 
 
 /// State is a wrapper around the environment and storage, and provides a simplified API
@@ -57,4 +70,17 @@ impl<'a> StateContext<'a> {
     pub fn response_mut(&mut self) -> &mut ResponseBuilder {
         &mut self.response
     }
+
+    /// Runs `f` on a cache of the store, see [transactional]
+    pub fn transactional<T>(&mut self, f: impl FnOnce(&mut StateContext) -> Result<T>) -> Result<T> {
+        transactional(self.store, &mut self.response, |store, response| {
+            let mut inner = StateContext {
+                store,
+                response: std::mem::replace(response, ResponseBuilder::new_mute_events()),
+            };
+            let result = f(&mut inner);
+            *response = inner.response;
+            result
+        })
+    }
 }
\ No newline at end of file
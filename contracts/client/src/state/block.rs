@@ -0,0 +1,45 @@
+use std::collections::HashSet;
+
+use cosmwasm_std::{Addr, Empty, Order, Storage};
+use cw_storage_plus::Map;
+use shared::msg::{contract::client::BlockedUser, network::NetworkId};
+use anyhow::Result;
+
+use super::{State, StateContext};
+
+// (viewer, network id, address), each user's own list
+// the address is kept as given, it may be from another chain
+const BLOCKED: Map<(&Addr, &str, &str), Empty> = Map::new("blocked");
+
+impl State<'_> {
+    pub fn get_blocked(&self, store: &dyn Storage, viewer: &Addr) -> Result<Vec<BlockedUser>> {
+        BLOCKED.sub_prefix(viewer)
+            .keys(store, None, None, Order::Ascending)
+            .map(|key| {
+                let (network_id, address) = key?;
+                Ok(BlockedUser {
+                    network_id: network_id.parse()?,
+                    address,
+                })
+            })
+            .collect()
+    }
+
+    pub fn block(&self, ctx: &mut StateContext, viewer: &Addr, network_id: NetworkId, address: &str) -> Result<()> {
+        BLOCKED.save(ctx.store, (viewer, &network_id.to_string(), address), &Empty {})?;
+        Ok(())
+    }
+
+    pub fn unblock(&self, ctx: &mut StateContext, viewer: &Addr, network_id: NetworkId, address: &str) -> Result<()> {
+        BLOCKED.remove(ctx.store, (viewer, &network_id.to_string(), address));
+        Ok(())
+    }
+
+    /// The senders the viewer hid, to filter messages with
+    pub fn get_blocked_set(&self, store: &dyn Storage, viewer: &Addr) -> Result<HashSet<(NetworkId, Addr)>> {
+        Ok(self.get_blocked(store, viewer)?
+            .into_iter()
+            .map(|blocked| (blocked.network_id, Addr::unchecked(blocked.address)))
+            .collect())
+    }
+}
@@ -1,15 +1,41 @@
-use cosmwasm_std::{Order, Storage};
-use cw_storage_plus::{Bound, Map};
-use shared::msg::chat_message::{event::ChatMessageEvent, ChatMessage, ChatMessageIndex, ChatMessageWithIndex};
+use cosmwasm_std::{Addr, Empty, Order, Storage};
+use cw_storage_plus::{Bound, Item, Map};
+use cw_utils::Expiration;
+use shared::msg::{chat_message::{event::ChatMessageEvent, ChatMessage, ChatMessageId, ChatMessageIndex, ChatMessageKind, ChatMessageWithIndex}, content::MessageContent, room::RoomId};
 
 use super::{State, StateContext};
 use anyhow::Result;
 
 const CHAT_MESSAGES:Map<u64, ChatMessage> = Map::new("chat_messages");
+// kept on its own, since the newest message may be deleted when it expires
+const LAST_CHAT_MESSAGE_INDEX: Item<u64> = Item::new("last_chat_message_index");
+// (expiry in nanos, index), soonest first
+const EXPIRING_MESSAGES: Map<(u64, u64), Empty> = Map::new("expiring_messages");
+// (expiry height, index), for messages kept on this chain
+const EXPIRING_MESSAGES_AT_HEIGHT: Map<(u64, u64), Empty> = Map::new("expiring-messages-at-height");
+
+// local index -> the server's id, from the server's packet or the ack of ours
+const CHAT_MESSAGE_IDS: Map<u64, u64> = Map::new("chat-message-ids");
+// and back, for pins
+const CHAT_MESSAGE_INDEXES: Map<u64, u64> = Map::new("chat-message-indexes");
+// (room, index), to go through one room at a time, the main chat is ""
+const ROOM_MESSAGES: Map<(&str, u64), Empty> = Map::new("room-messages");
+
+// how many expired messages are deleted with each new one
+const MAX_EXPIRED_DELETES: usize = 10;
 
 impl State<'_> {
-    pub fn get_chat_messages(&self, store: &dyn Storage, after_index: Option<ChatMessageIndex>, order: Option<Order>) -> Result<Vec<ChatMessageWithIndex>> {
+    /// With a viewer, messages from senders they blocked are skipped
+    pub fn get_chat_messages(&self, store: &dyn Storage, after_index: Option<ChatMessageIndex>, order: Option<Order>, limit: Option<u32>, viewer: Option<&Addr>) -> Result<Vec<ChatMessageWithIndex>> {
+        let blocked = viewer.map(|viewer| self.get_blocked_set(store, viewer)).transpose()?.unwrap_or_default();
+
+        // filtered before the limit, so a page is only short at the end
         CHAT_MESSAGES.range(store, after_index.map(|x| Bound::exclusive(x.u64())), None, order.unwrap_or(Order::Ascending))
+            .filter(|x| match x {
+                Ok((_, msg)) => !msg.expires.is_expired(&self.env.block) && !blocked.contains(&(msg.network_id, msg.user.clone())),
+                Err(_) => true,
+            })
+            .take(limit.map_or(usize::MAX, |limit| limit as usize))
             .map(|x| x
                 .map(|(index, msg)| ChatMessageWithIndex { msg, index: index.into() })
                 .map_err(|err| err.into())
@@ -17,19 +43,143 @@ impl State<'_> {
             .collect()
     }
 
+    /// Expired messages are gone, as far as anyone asking is concerned
+    pub fn get_chat_message(&self, store: &dyn Storage, index: ChatMessageIndex) -> Result<Option<ChatMessage>> {
+        Ok(CHAT_MESSAGES.may_load(store, index.u64())?.filter(|msg| !msg.expires.is_expired(&self.env.block)))
+    }
+
+    pub fn get_chat_message_id(&self, store: &dyn Storage, index: ChatMessageIndex) -> Result<Option<ChatMessageId>> {
+        Ok(CHAT_MESSAGE_IDS.may_load(store, index.u64())?.map(ChatMessageId::from))
+    }
+
+    pub fn set_chat_message_id(&self, ctx: &mut StateContext, index: ChatMessageIndex, id: ChatMessageId) -> Result<()> {
+        CHAT_MESSAGE_IDS.save(ctx.store, index.u64(), &id.u64())?;
+        CHAT_MESSAGE_INDEXES.save(ctx.store, id.u64(), &index.u64())?;
+        Ok(())
+    }
+
+    /// The local copy of a message the server gave that id
+    pub fn get_chat_message_by_id(&self, store: &dyn Storage, id: ChatMessageId) -> Result<Option<ChatMessageWithIndex>> {
+        let Some(index) = CHAT_MESSAGE_INDEXES.may_load(store, id.u64())? else {
+            return Ok(None);
+        };
+
+        Ok(self.get_chat_message(store, index.into())?.map(|msg| ChatMessageWithIndex { msg, index: index.into() }))
+    }
+
+    /// Messages in the room after the index that someone other than the viewer wrote
+    /// as the viewer sees them, and stops counting at `max`
+    pub fn count_room_messages_for(&self, store: &dyn Storage, room: Option<&RoomId>, after_index: Option<ChatMessageIndex>, viewer: &Addr, max: u32) -> Result<u32> {
+        let blocked = self.get_blocked_set(store, viewer)?;
+
+        let mut count = 0;
+        for index in ROOM_MESSAGES.prefix(room_key(room)).keys(store, after_index.map(|x| Bound::exclusive(x.u64())), None, Order::Ascending) {
+            if count == max {
+                break;
+            }
+
+            let Some(msg) = self.get_chat_message(store, index?.into())? else { continue };
+            if msg.user != *viewer && !blocked.contains(&(msg.network_id, msg.user.clone())) {
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+
     pub fn push_chat_message(&self, ctx: &mut StateContext, message: ChatMessage) -> Result<ChatMessageIndex> {
-        let next_index = CHAT_MESSAGES.keys(ctx.store, None, None, Order::Descending).next().unwrap_or(Ok(0))? + 1;
-        
+        self.delete_expired_messages(ctx)?;
+
+        let last_index = match LAST_CHAT_MESSAGE_INDEX.may_load(ctx.store)? {
+            Some(index) => index,
+            // from before messages could expire
+            None => CHAT_MESSAGES.keys(ctx.store, None, None, Order::Descending).next().unwrap_or(Ok(0))?,
+        };
+        let next_index = last_index + 1;
+
         CHAT_MESSAGES.save(ctx.store, next_index, &message)?;
+        LAST_CHAT_MESSAGE_INDEX.save(ctx.store, &next_index)?;
+        ROOM_MESSAGES.save(ctx.store, (room_key(message.room.as_ref()), next_index), &Empty {})?;
+        match message.expires {
+            Expiration::AtTime(time) => EXPIRING_MESSAGES.save(ctx.store, (time.nanos(), next_index), &Empty {})?,
+            Expiration::AtHeight(height) => EXPIRING_MESSAGES_AT_HEIGHT.save(ctx.store, (height, next_index), &Empty {})?,
+            Expiration::Never {} => {},
+        }
 
         let index = next_index.into();
+        let message = ChatMessageWithIndex {
+            msg: message,
+            index,
+        };
 
-        ctx.response.add_event(ChatMessageEvent {
-            message: ChatMessageWithIndex {
-                msg: message,
-                index,
-            }
-        });
+        // local or from the server, subscribers hear about it either way
+        // and so does anyone here it mentions
+        self.store_mentions(ctx, &message)?;
+        self.dispatch_chat_hooks(ctx, &message)?;
+
+        ctx.response.add_event(ChatMessageEvent { message });
         Ok(index)
     }
-}
\ No newline at end of file
+
+    // queries already leave expired messages out, this only frees the storage
+    fn delete_expired_messages(&self, ctx: &mut StateContext) -> Result<()> {
+        let now = self.env.block.time.nanos();
+        let expired = EXPIRING_MESSAGES.keys(ctx.store, None, None, Order::Ascending)
+            .take_while(|key| key.as_ref().map_or(true, |(expires, _)| *expires <= now))
+            .take(MAX_EXPIRED_DELETES)
+            .collect::<Result<Vec<_>, _>>()?;
+        let height = self.env.block.height;
+        let expired_at_height = EXPIRING_MESSAGES_AT_HEIGHT.keys(ctx.store, None, None, Order::Ascending)
+            .take_while(|key| key.as_ref().map_or(true, |(expires, _)| *expires <= height))
+            .take(MAX_EXPIRED_DELETES - expired.len())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (expires, index) in expired {
+            EXPIRING_MESSAGES.remove(ctx.store, (expires, index));
+            self.delete_chat_message(ctx, index)?;
+        }
+        for (expires, index) in expired_at_height {
+            EXPIRING_MESSAGES_AT_HEIGHT.remove(ctx.store, (expires, index));
+            self.delete_chat_message(ctx, index)?;
+        }
+
+        Ok(())
+    }
+
+    // along with everything kept by its index
+    fn delete_chat_message(&self, ctx: &mut StateContext, index: u64) -> Result<()> {
+        if let Some(message) = CHAT_MESSAGES.may_load(ctx.store, index)? {
+            ROOM_MESSAGES.remove(ctx.store, (room_key(message.room.as_ref()), index));
+        }
+        CHAT_MESSAGES.remove(ctx.store, index);
+        if let Some(id) = CHAT_MESSAGE_IDS.may_load(ctx.store, index)? {
+            CHAT_MESSAGE_INDEXES.remove(ctx.store, id);
+        }
+        CHAT_MESSAGE_IDS.remove(ctx.store, index);
+
+        self.delete_mentions_of(ctx, index)?;
+        self.delete_message_tips(ctx, index);
+        Ok(())
+    }
+
+    /// Stores a message written by the contract itself, it's never sent to the server
+    pub fn push_system_message(&self, ctx: &mut StateContext, message: String) -> Result<ChatMessageIndex> {
+        let message = ChatMessage {
+            user: self.env.contract.address.clone(),
+            network_id: self.get_network_id(ctx.store)?,
+            message,
+            kind: ChatMessageKind::System,
+            content: MessageContent::Notice,
+            room: None,
+            attestation: None,
+            expires: Expiration::Never {},
+        };
+
+        self.push_chat_message(ctx, message)
+    }
+}
+
+// the main chat is "" in maps keyed by room
+pub(super) fn room_key(room: Option<&RoomId>) -> &str {
+    room.map_or("", |room| room.as_str())
+}
@@ -0,0 +1,106 @@
+use cosmwasm_std::{Addr, BankMsg, MessageInfo, Storage};
+use cw_storage_plus::{Item, Map};
+use cw_utils::Expiration;
+use shared::msg::{chat_message::{ChatMessage, ChatMessageKind}, command::ChatCommand, content::MessageContent};
+use anyhow::Result;
+
+use super::{State, StateContext};
+
+const NICKNAMES: Map<&Addr, String> = Map::new("nicknames");
+// lowercased nickname -> whoever set it last, for mentions
+const NICKNAME_OWNERS: Map<&str, Addr> = Map::new("nickname-owners");
+const TOPIC: Item<String> = Item::new("topic");
+
+impl State<'_> {
+    pub fn get_nickname(&self, store: &dyn Storage, user: &Addr) -> Result<Option<String>> {
+        NICKNAMES.may_load(store, user).map_err(|err| err.into())
+    }
+
+    /// Case doesn't matter, `@Alice` is alice
+    pub fn get_nickname_owner(&self, store: &dyn Storage, name: &str) -> Result<Option<Addr>> {
+        NICKNAME_OWNERS.may_load(store, &name.to_lowercase()).map_err(|err| err.into())
+    }
+
+    pub fn get_topic(&self, store: &dyn Storage) -> Result<Option<String>> {
+        TOPIC.may_load(store).map_err(|err| err.into())
+    }
+
+    /// Runs a command sent by the user
+    /// returns the message to send to the server, if the command has one
+    /// everything else only changes local state or leaves a system message here
+    pub fn handle_chat_command(&self, ctx: &mut StateContext, info: &MessageInfo, command: Result<ChatCommand>) -> Result<Option<ChatMessage>> {
+        let sender = &info.sender;
+
+        // only a roll has a use for funds, the proxy's fee
+        if !matches!(command, Ok(ChatCommand::Roll { .. })) {
+            self.return_funds(ctx, info);
+        }
+
+        let command = match command {
+            Ok(command) => command,
+            Err(err) => {
+                // a bad command is the user's problem, not a failed transaction
+                self.push_system_message(ctx, err.to_string())?;
+                return Ok(None);
+            }
+        };
+
+        match command {
+            ChatCommand::Nick { name } => {
+                // mentions go to whoever holds the name, so it isn't taken from them
+                if self.get_nickname_owner(ctx.store, &name)?.is_some_and(|owner| owner != *sender) {
+                    self.push_system_message(ctx, format!("{} is taken", name))?;
+                } else {
+                    if let Some(old) = NICKNAMES.may_load(ctx.store, sender)? {
+                        if self.get_nickname_owner(ctx.store, &old)?.as_ref() == Some(sender) {
+                            NICKNAME_OWNERS.remove(ctx.store, &old.to_lowercase());
+                        }
+                    }
+                    NICKNAMES.save(ctx.store, sender, &name)?;
+                    NICKNAME_OWNERS.save(ctx.store, &name.to_lowercase(), sender)?;
+                    self.push_system_message(ctx, format!("{} is now known as {}", sender, name))?;
+                }
+            },
+            ChatCommand::Topic { topic } => {
+                // it's the whole chat's topic, so it's the owner's to set
+                if *sender != self.get_owner(ctx.store)? {
+                    self.push_system_message(ctx, "only the owner can set the topic".to_string())?;
+                } else {
+                    TOPIC.save(ctx.store, &topic)?;
+                    self.push_system_message(ctx, format!("{} set the topic to: {}", sender, topic))?;
+                }
+            },
+            ChatCommand::Roll { dice } => {
+                // e.g. no Nois proxy on this chain, then the fee goes back too
+                if let Err(err) = ctx.transactional(|ctx| self.request_roll(ctx, sender, dice, info.funds.clone())) {
+                    self.return_funds(ctx, info);
+                    self.push_system_message(ctx, err.to_string())?;
+                }
+            },
+            ChatCommand::Help => {
+                self.push_system_message(ctx, ChatCommand::HELP.join("\n"))?;
+            },
+            ChatCommand::Me { action } => {
+                return Ok(Some(ChatMessage {
+                    user: sender.clone(),
+                    network_id: self.get_network_id(ctx.store)?,
+                    message: action,
+                    kind: ChatMessageKind::Action,
+                    content: MessageContent::Text,
+                    room: None,
+                    attestation: None,
+                    expires: Expiration::Never {},
+                }));
+            },
+        }
+
+        Ok(None)
+    }
+
+    // a command the user sent funds along with doesn't keep them unless it pays for something
+    fn return_funds(&self, ctx: &mut StateContext, info: &MessageInfo) {
+        if !info.funds.is_empty() {
+            ctx.response.add_message(BankMsg::Send { to_address: info.sender.to_string(), amount: info.funds.clone() });
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use cosmwasm_std::{wasm_execute, Addr, Binary, Order, Reply, Storage, SubMsg, SubMsgResult};
+use cw_storage_plus::Map;
+use shared::msg::{
+    chat_message::ChatMessageWithIndex,
+    contract::client::Hook,
+    hook::{event::ChatHookFailedEvent, ChatHookMsg, HookFilter},
+};
+use anyhow::{bail, Result};
+
+use super::{State, StateContext};
+
+const HOOKS: Map<&Addr, HookFilter> = Map::new("hooks");
+
+/// Every message is dispatched to every matching hook, so keep the list short
+pub const MAX_HOOKS: usize = 10;
+
+/// A broken or greedy hook runs out of this and fails on its own
+/// without taking the message (or the IBC packet it came in) down with it
+pub const HOOK_GAS_LIMIT: u64 = 500_000;
+
+pub const HOOK_REPLY_ID: u64 = 1;
+
+impl State<'_> {
+    pub fn get_hooks(&self, store: &dyn Storage) -> Result<Vec<Hook>> {
+        HOOKS.range(store, None, None, Order::Ascending)
+            .map(|x| x
+                .map(|(contract, filter)| Hook { contract, filter })
+                .map_err(|err| err.into())
+            )
+            .collect()
+    }
+
+    /// Registers the hook, or replaces its filter if it's already registered
+    pub fn set_hook(&self, ctx: &mut StateContext, contract: &Addr, filter: HookFilter) -> Result<()> {
+        if !HOOKS.has(ctx.store, contract) && HOOKS.keys(ctx.store, None, None, Order::Ascending).count() >= MAX_HOOKS {
+            bail!("too many hooks, max is {}", MAX_HOOKS);
+        }
+
+        HOOKS.save(ctx.store, contract, &filter).map_err(|err| err.into())
+    }
+
+    pub fn remove_hook(&self, ctx: &mut StateContext, contract: &Addr) -> Result<()> {
+        if !HOOKS.has(ctx.store, contract) {
+            bail!("no hook registered for {}", contract);
+        }
+
+        HOOKS.remove(ctx.store, contract);
+
+        Ok(())
+    }
+
+    /// Sends the message to every hook whose filter matches
+    /// failures come back to [Self::handle_hook_reply] instead of aborting
+    pub fn dispatch_chat_hooks(&self, ctx: &mut StateContext, message: &ChatMessageWithIndex) -> Result<()> {
+        for hook in self.get_hooks(ctx.store)? {
+            if !hook.filter.matches(&message.msg) {
+                continue;
+            }
+
+            let msg = wasm_execute(hook.contract.to_string(), &ChatHookMsg::ChatHook { message: message.clone() }, vec![])?;
+
+            ctx.response.add_raw_submessage(
+                SubMsg::reply_on_error(msg, HOOK_REPLY_ID)
+                    .with_gas_limit(HOOK_GAS_LIMIT)
+                    .with_payload(Binary::from(hook.contract.as_bytes()))
+            );
+        }
+
+        Ok(())
+    }
+
+    pub fn handle_hook_reply(&self, ctx: &mut StateContext, reply: Reply) -> Result<()> {
+        // the hook's own changes were already reverted, we just report it
+        if let SubMsgResult::Err(error) = reply.result {
+            ctx.response.add_event(ChatHookFailedEvent {
+                contract: Addr::unchecked(String::from_utf8_lossy(reply.payload.as_slice())),
+                error,
+            });
+        }
+
+        Ok(())
+    }
+}
@@ -1,16 +1,27 @@
 use cosmwasm_std::{
-    from_binary, from_json, to_json_binary, IbcChannel, IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcMsg, IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcTimeout, Order, Storage
+    from_binary, from_json, to_json_binary, IbcChannel, IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcMsg, IbcOrder, IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcTimeout, Order, Storage
 };
 use cw_storage_plus::{Deque, Item, Map};
 use shared::{ibc::{
-    event::{IbcChannelCloseEvent, IbcChannelConnectEvent},
-    validate_ibc_channel_order_and_version, TIMEOUT_SECONDS,
-}, msg::ibc::IbcExecuteMsg};
-use anyhow::Result;
+    event::{IbcChannelCloseEvent, IbcChannelConnectEvent, IbcPacketAckEvent},
+    IbcAck, IbcAckError, IbcAckErrorCode,
+    channel_protocol_version, negotiate_ibc_channel_connect, negotiate_ibc_channel_open, MAX_BATCH_SIZE, TIMEOUT_SECONDS,
+}, msg::{chat_message::validate_expiration, ibc::{IbcAckSuccess, IbcExecuteMsg}}};
+use anyhow::{Context, Result};
 
 use super::{State, StateContext};
 
 const SERVER_CHANNEL: Item<IbcChannel> = Item::new("server");
+// an ordered server channel that was closed by a timeout
+const DEAD_SERVER_CHANNEL: Item<IbcChannel> = Item::new("dead-server");
+// outgoing packets wait here while there's no server channel
+// and are flushed as soon as one connects
+const OUTBOX: Deque<IbcExecuteMsg> = Deque::new("outbox");
+// packets that were in flight when the server channel died, by their sequence there
+// they were sent before anything in the outbox, so they go out again before it
+const TIMED_OUT: Map<u64, Vec<IbcExecuteMsg>> = Map::new("timed-out");
+// messages held back on purpose, to go out together in one batch packet
+const SEND_BUFFER: Deque<IbcExecuteMsg> = Deque::new("send-buffer");
 
 impl State<'_> {
 
@@ -18,24 +29,117 @@ impl State<'_> {
         SERVER_CHANNEL.may_load(store).map_err(|err| err.into())
     }
 
-    pub fn handle_ibc_channel_open(&self, msg: IbcChannelOpenMsg) -> Result<()> {
-        validate_ibc_channel_order_and_version(msg.channel(), msg.counterparty_version())?;
+    pub fn get_dead_server_channel(&self, store: &dyn Storage) -> Result<Option<IbcChannel>> {
+        DEAD_SERVER_CHANNEL.may_load(store).map_err(|err| err.into())
+    }
+
+    /// Messages that are waiting for a server channel, in the order they'll be sent
+    pub fn get_outbox(&self, store: &dyn Storage) -> Result<Vec<IbcExecuteMsg>> {
+        let mut msgs = Vec::new();
+        for packet in TIMED_OUT.range(store, None, None, Order::Ascending) {
+            msgs.extend(packet?.1);
+        }
+        for msg in OUTBOX.iter(store)? {
+            msgs.push(msg?);
+        }
+
+        Ok(msgs)
+    }
+
+    /// Drops everything in the outbox
+    /// the local copies of the messages are kept, they just won't reach the server
+    pub fn purge_outbox(&self, ctx: &mut StateContext) -> Result<()> {
+        while OUTBOX.pop_front(ctx.store)?.is_some() {}
+        TIMED_OUT.clear(ctx.store);
+
+        Ok(())
+    }
+
+    /// Messages that are buffered until the next flush, oldest first
+    pub fn get_send_buffer(&self, store: &dyn Storage) -> Result<Vec<IbcExecuteMsg>> {
+        SEND_BUFFER.iter(store)?.map(|msg| msg.map_err(|err| err.into())).collect()
+    }
+
+    /// Holds the message back until the buffer is flushed
+    /// a full buffer is flushed right away
+    pub fn buffer_for_server(&self, ctx: &mut StateContext, msg: IbcExecuteMsg) -> Result<()> {
+        SEND_BUFFER.push_back(ctx.store, &msg)?;
+
+        if SEND_BUFFER.len(ctx.store)? as usize >= MAX_BATCH_SIZE {
+            self.flush_send_buffer(ctx)?;
+        }
+
         Ok(())
     }
 
+    /// Sends everything in the buffer to the server as a single packet
+    pub fn flush_send_buffer(&self, ctx: &mut StateContext) -> Result<()> {
+        let mut msgs = Vec::new();
+        while let Some(msg) = SEND_BUFFER.pop_front(ctx.store)? {
+            msgs.push(msg);
+        }
+
+        for msg in IbcExecuteMsg::batches(msgs) {
+            self.send_to_server(ctx, msg)?;
+        }
+
+        Ok(())
+    }
+
+    /// Sends the message over the server channel
+    /// or puts it in the outbox if there isn't one (yet, or anymore)
+    pub fn send_to_server(&self, ctx: &mut StateContext, msg: IbcExecuteMsg) -> Result<()> {
+        match SERVER_CHANNEL.may_load(ctx.store)? {
+            Some(channel) => {
+                // outbound IBC message, where packet is then received on other chain
+                ctx.response_mut().add_message(IbcMsg::SendPacket {
+                    data: msg.encode(channel_protocol_version(&channel)?)?,
+                    channel_id: channel.endpoint.channel_id,
+                    timeout: IbcTimeout::with_timestamp(self.env.block.time.plus_seconds(TIMEOUT_SECONDS)),
+                });
+            },
+            None => {
+                OUTBOX.push_back(ctx.store, &msg)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the version string the channel should be opened with
+    pub fn handle_ibc_channel_open(&self, store: &dyn Storage, msg: IbcChannelOpenMsg) -> Result<String> {
+        negotiate_ibc_channel_open(msg.channel(), msg.counterparty_version(), self.get_channel_order(store)?)
+    }
+
     pub fn handle_ibc_channel_connect(
         &self,
         ctx: &mut StateContext,
         msg: IbcChannelConnectMsg,
     ) -> Result<()> {
-        let channel = msg.channel();
+        let version = negotiate_ibc_channel_connect(msg.channel(), msg.counterparty_version(), self.get_channel_order(ctx.store)?)?;
 
-        validate_ibc_channel_order_and_version(channel, msg.counterparty_version())?;
+        // the stored channel always carries the agreed version, not the proposal
+        let mut channel = msg.channel().clone();
+        channel.version = version.to_string();
 
-        SERVER_CHANNEL.save(ctx.store, channel)?;
+        SERVER_CHANNEL.save(ctx.store, &channel)?;
+        DEAD_SERVER_CHANNEL.remove(ctx.store);
 
         ctx.response_mut()
-            .add_event(IbcChannelConnectEvent { channel });
+            .add_event(IbcChannelConnectEvent { channel: &channel });
+
+        // tell the server who we are before anything else, in a packet of its own
+        self.send_to_server(ctx, IbcExecuteMsg::ClientConnected { network_id: self.get_network_id(ctx.store)? })?;
+
+        // flush whatever was queued while there was no channel
+        // what timed out first, in its original order, then what came after
+        let msgs = self.get_outbox(ctx.store)?;
+        TIMED_OUT.clear(ctx.store);
+        while OUTBOX.pop_front(ctx.store)?.is_some() {}
+
+        for msg in IbcExecuteMsg::batches(msgs) {
+            self.send_to_server(ctx, msg)?;
+        }
 
         Ok(())
     }
@@ -46,10 +150,26 @@ impl State<'_> {
         msg: IbcChannelCloseMsg,
     ) -> Result<()> {
         let channel = msg.channel();
+        if self.get_server_channel(ctx.store)?.is_some_and(|server_channel| server_channel.endpoint == channel.endpoint) {
+            self.close_server_channel(ctx)?;
+        }
+
+        Ok(())
+    }
+
+    fn close_server_channel(&self, ctx: &mut StateContext) -> Result<()> {
+        let channel = SERVER_CHANNEL.load(ctx.store)?;
         SERVER_CHANNEL.remove(ctx.store);
 
+        // with ordered channels we expect a new channel to come back
+        // so in-flight packets that time out are put back in the outbox
+        if channel.order == IbcOrder::Ordered {
+            DEAD_SERVER_CHANNEL.save(ctx.store, &channel)?;
+        }
+
         ctx.response_mut()
-            .add_event(IbcChannelCloseEvent { channel });
+            .add_event(IbcChannelCloseEvent { channel: &channel });
+
         Ok(())
     }
 
@@ -57,35 +177,147 @@ impl State<'_> {
         &self,
         ctx: &mut StateContext,
         recv_msg: IbcPacketReceiveMsg,
-    ) -> Result<()> {
-        from_json(&recv_msg.packet.data)
-            .map_err(|err| err.into())
-            .and_then(|msg| {
-                match msg {
-                    IbcExecuteMsg::SendMessageToClient{ message } => {
-                        self.push_chat_message(ctx, message)?;
-                        Ok(())
-                    },
-                    _ => anyhow::bail!("unsupported message type"),
+    ) -> Result<IbcAckSuccess> {
+        let version = self
+            .get_server_channel(ctx.store)?
+            .filter(|channel| channel.endpoint == recv_msg.packet.dest)
+            .ok_or_else(|| IbcAckError::new(IbcAckErrorCode::UnknownChannel, "packet did not arrive on the server channel"))
+            .map_err(anyhow::Error::from)
+            .and_then(|channel| channel_protocol_version(&channel))?;
+
+        match IbcExecuteMsg::decode(version, &recv_msg.packet.data)? {
+            IbcExecuteMsg::Batch { msgs } => {
+                let results = msgs
+                    .into_iter()
+                    // each item on its own, a failed one leaves nothing behind
+                    .map(|msg| match ctx.transactional(|ctx| self.handle_ibc_execute_msg(ctx, msg)) {
+                        Ok(success) => IbcAck::Success(success),
+                        Err(err) => IbcAck::from(err),
+                    })
+                    .collect();
+
+                Ok(IbcAckSuccess::Batch { results })
+            },
+            msg => self.handle_ibc_execute_msg(ctx, msg),
+        }
+    }
+
+    // handles a single (non-batch) message from the server
+    fn handle_ibc_execute_msg(&self, ctx: &mut StateContext, msg: IbcExecuteMsg) -> Result<IbcAckSuccess> {
+        match msg {
+            IbcExecuteMsg::SendMessageToClient{ message, id } => {
+                validate_expiration(&message.expires)
+                    .and_then(|_| self.check_incoming_room_message(ctx.store, &message))
+                    .map_err(|err| IbcAckError::new(IbcAckErrorCode::Rejected, err.to_string()))?;
+
+                let index = self.push_chat_message(ctx, message)?;
+                if let Some(id) = id {
+                    self.set_chat_message_id(ctx, index, id)?;
                 }
-            })
+                Ok(IbcAckSuccess::MessageStored { index })
+            },
+            IbcExecuteMsg::PollCreated { poll } => {
+                self.store_poll(ctx, poll)?;
+                Ok(IbcAckSuccess::PollStored { })
+            },
+            IbcExecuteMsg::PollClosed { tally } => {
+                self.store_closed_poll(ctx, tally)?;
+                Ok(IbcAckSuccess::PollStored { })
+            },
+            IbcExecuteMsg::RoomCreated { room } => {
+                self.store_room(ctx, room)
+                    .map_err(|err| IbcAckError::new(IbcAckErrorCode::Rejected, err.to_string()))?;
+                Ok(IbcAckSuccess::RoomStored { })
+            },
+            IbcExecuteMsg::RoomUpdated { room } => {
+                self.store_room_update(ctx, room)
+                    .map_err(|err| IbcAckError::new(IbcAckErrorCode::Rejected, err.to_string()))?;
+                Ok(IbcAckSuccess::RoomStored { })
+            },
+            IbcExecuteMsg::IdentityLinked { link } => {
+                self.store_identity_link(ctx, link)?;
+                Ok(IbcAckSuccess::IdentityStored { })
+            },
+            IbcExecuteMsg::UpdatePin { pin, pinned } => {
+                self.store_pin(ctx, pin, pinned)
+                    .map_err(|err| IbcAckError::new(IbcAckErrorCode::Rejected, err.to_string()))?;
+                Ok(IbcAckSuccess::PinStored { })
+            },
+            IbcExecuteMsg::Batch { .. } => anyhow::bail!(IbcAckError::new(IbcAckErrorCode::UnsupportedMessage, "batches can't be nested")),
+            _ => anyhow::bail!(IbcAckError::new(IbcAckErrorCode::UnsupportedMessage, "unsupported message type")),
+        }
     }
 
-    pub fn handle_ibc_packet_ack(&self, _ack: IbcPacketAckMsg) -> Result<()> {
-        // Nothing to do here. We don't keep any state about the other
-        // chain, just deliver messages so nothing to update.
-        //
-        // If we did care about how the other chain received our message
-        // we could deserialize the data field into an `Ack` and inspect
-        // it.
+    pub fn handle_ibc_packet_ack(&self, ctx: &mut StateContext, ack: IbcPacketAckMsg) -> Result<()> {
+        // The outcome is reported as an event
+        // the only thing we keep is the id the server gave each of our messages
+        let decoded = IbcAck::decode_or_error(&ack.acknowledgement.data);
+        if let IbcAck::Error(IbcAckError { code: IbcAckErrorCode::DecodeFailed, message }) = &decoded {
+            self.api.debug(message);
+        }
+        self.store_acked_message_ids(ctx, &ack, &decoded)?;
+
+        ctx.response_mut().add_event(IbcPacketAckEvent {
+            packet: &ack.original_packet,
+            ack: &decoded,
+        });
+
         Ok(())
     }
 
-    pub fn handle_ibc_packet_timeout(&self, _msg: IbcPacketTimeoutMsg) -> Result<()> {
-        // As with ack above, nothing to do here. If we cared about
-        // keeping track of state between the two chains then we'd want to
-        // respond to this likely as it means that the packet in question
-        // isn't going anywhere.
+    fn store_acked_message_ids(&self, ctx: &mut StateContext, ack: &IbcPacketAckMsg, decoded: &IbcAck) -> Result<()> {
+        // the channel may have closed since, but the packet still went out on it
+        let channel = [self.get_server_channel(ctx.store)?, self.get_dead_server_channel(ctx.store)?]
+            .into_iter()
+            .flatten()
+            .find(|channel| channel.endpoint == ack.original_packet.src);
+        let Some(channel) = channel else {
+            return Ok(());
+        };
+
+        let results = match decoded {
+            IbcAck::Success(IbcAckSuccess::Batch { results }) => results.iter().collect(),
+            ack => vec![ack],
+        };
+        let msgs = IbcExecuteMsg::decode(channel_protocol_version(&channel)?, &ack.original_packet.data)?.into_items();
+
+        for (msg, result) in msgs.into_iter().zip(results) {
+            if let (IbcExecuteMsg::SendMessageToServer { message }, IbcAck::Success(IbcAckSuccess::MessageBroadcast { id, .. })) = (msg, result) {
+                self.set_chat_message_id(ctx, message.index, *id)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn handle_ibc_packet_timeout(&self, ctx: &mut StateContext, msg: IbcPacketTimeoutMsg) -> Result<()> {
+        let packet = msg.packet;
+
+        // On unordered channels a timeout only loses that one packet, nothing to do here.
+        // On ordered channels a timeout closes the channel, so we mark it as dead
+        if let Some(channel) = self.get_server_channel(ctx.store)? {
+            if channel.endpoint == packet.src && channel.order == IbcOrder::Ordered {
+                self.close_server_channel(ctx)?;
+            }
+        }
+
+        // Every packet that was in flight on the dead channel times out too
+        // keep them by sequence, so they're sent again in their original order when the new channel connects
+        // the timeouts themselves may come in any order
+        if let Some(channel) = self.get_dead_server_channel(ctx.store)? {
+            if channel.endpoint == packet.src {
+                // except the announcement, the new channel starts with its own
+                let msgs = IbcExecuteMsg::decode(channel_protocol_version(&channel)?, &packet.data)?
+                    .into_items()
+                    .into_iter()
+                    .filter(|msg| !matches!(msg, IbcExecuteMsg::ClientConnected { .. }))
+                    .collect::<Vec<_>>();
+                if !msgs.is_empty() {
+                    TIMED_OUT.save(ctx.store, packet.sequence, &msgs)?;
+                }
+            }
+        }
+
         Ok(())
     }
-}
\ No newline at end of file
+}
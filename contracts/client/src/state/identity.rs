@@ -0,0 +1,77 @@
+use cosmwasm_std::{Addr, Binary, Empty, Order, Storage};
+use cw_storage_plus::Map;
+use shared::msg::{
+    contract::client::UserIdentity,
+    chat_message::ChatMessageWithIndex,
+    identity::{event::IdentityLinkedEvent, identity_of_address, IdentityId, IdentityLink, IdentityLinkSignature},
+};
+use anyhow::{bail, Result};
+
+use super::{State, StateContext};
+
+// the server's links, as it sent them
+// member -> the identity it joined
+const IDENTITY_LINKS: Map<&str, IdentityId> = Map::new("identity-links");
+// (identity, member), to list the members
+const IDENTITY_MEMBERS: Map<(&str, &str), Empty> = Map::new("identity-members");
+
+impl State<'_> {
+    /// The identity a key belongs to, itself if it never joined one
+    pub fn resolve_identity(&self, store: &dyn Storage, id: &str) -> Result<IdentityId> {
+        Ok(IDENTITY_LINKS.may_load(store, id)?.unwrap_or_else(|| id.to_string()))
+    }
+
+    pub fn get_identity_members(&self, store: &dyn Storage, identity: &str) -> Result<Vec<IdentityId>> {
+        IDENTITY_MEMBERS.prefix(identity)
+            .keys(store, None, None, Order::Ascending)
+            .map(|x| x.map_err(|err| err.into()))
+            .collect()
+    }
+
+    /// The identity of each author, skipping addresses that aren't bech32
+    pub fn get_user_identities(&self, store: &dyn Storage, messages: &[ChatMessageWithIndex]) -> Result<Vec<UserIdentity>> {
+        let mut identities: Vec<UserIdentity> = Vec::new();
+
+        for message in messages {
+            let user = &message.msg.user;
+            if identities.iter().any(|x| x.user == *user) {
+                continue;
+            }
+
+            if let Ok(id) = identity_of_address(user.as_str()) {
+                identities.push(UserIdentity {
+                    user: user.clone(),
+                    identity: self.resolve_identity(store, &id)?,
+                });
+            }
+        }
+
+        Ok(identities)
+    }
+
+    /// Checks that the key behind `address` signed a statement for the sender's identity
+    /// returns it for the server, which checks it again and may still refuse the link
+    pub fn check_identity_link(&self, sender: &Addr, address: String, pubkey: Binary, signature: Binary) -> Result<IdentityLinkSignature> {
+        let signature = IdentityLinkSignature {
+            address,
+            pubkey,
+            signature,
+            link_identity: identity_of_address(sender.as_str())?,
+        };
+
+        if signature.verify(self.api)? == signature.link_identity {
+            bail!("{} is already {}, it's the same key", signature.address, sender);
+        }
+
+        Ok(signature)
+    }
+
+    pub fn store_identity_link(&self, ctx: &mut StateContext, link: IdentityLink) -> Result<()> {
+        IDENTITY_LINKS.save(ctx.store, &link.member, &link.identity)?;
+        IDENTITY_MEMBERS.save(ctx.store, (&link.identity, &link.member), &Empty {})?;
+
+        ctx.response.add_event(IdentityLinkedEvent { link: &link });
+
+        Ok(())
+    }
+}
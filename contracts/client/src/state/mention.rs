@@ -0,0 +1,147 @@
+use cosmwasm_std::{Addr, Empty, Order, Storage};
+use cw_storage_plus::{Bound, Map};
+use shared::msg::{
+    chat_message::{ChatMessageIndex, ChatMessageWithIndex},
+    mention::{can_mention, event::MentionEvent, parse_mentions, Mention},
+    room::RoomAccess,
+};
+use anyhow::Result;
+
+use super::{State, StateContext};
+
+// (user, message index), every mention of users on this chain
+const MENTIONS: Map<(&Addr, u64), Empty> = Map::new("mentions");
+// the ones not marked read yet, a subset of MENTIONS
+const UNREAD_MENTIONS: Map<(&Addr, u64), Empty> = Map::new("unread-mentions");
+// kept alongside, so the count doesn't go through them all
+const UNREAD_MENTION_COUNT: Map<&Addr, u32> = Map::new("unread-mention-count");
+// message index -> the users it mentions, to clean up when the message expires
+const MESSAGE_MENTIONS: Map<u64, Vec<Addr>> = Map::new("message-mentions");
+
+impl State<'_> {
+    /// Skips mentions of messages that expired since
+    pub fn get_mentions(&self, store: &dyn Storage, user: &Addr, after_index: Option<ChatMessageIndex>, order: Option<Order>, limit: Option<u32>, unread_only: bool) -> Result<Vec<Mention>> {
+        let map = if unread_only { UNREAD_MENTIONS } else { MENTIONS };
+
+        let mut mentions = Vec::new();
+        for index in map.prefix(user).keys(store, after_index.map(|x| Bound::exclusive(x.u64())), None, order.unwrap_or(Order::Ascending)) {
+            if limit.is_some_and(|limit| mentions.len() >= limit as usize) {
+                break;
+            }
+
+            let index = index?;
+            if let Some(msg) = self.get_chat_message(store, index.into())? {
+                mentions.push(Mention {
+                    message: ChatMessageWithIndex { msg, index: index.into() },
+                    read: !UNREAD_MENTIONS.has(store, (user, index)),
+                });
+            }
+        }
+
+        Ok(mentions)
+    }
+
+    pub fn get_unread_mention_count(&self, store: &dyn Storage, user: &Addr) -> Result<u32> {
+        Ok(UNREAD_MENTION_COUNT.may_load(store, user)?.unwrap_or_default())
+    }
+
+    /// Adds the message to the inbox of everyone on this chain it mentions
+    pub fn store_mentions(&self, ctx: &mut StateContext, message: &ChatMessageWithIndex) -> Result<()> {
+        let users = self.mentioned_users(ctx.store, message)?;
+        if !users.is_empty() {
+            MESSAGE_MENTIONS.save(ctx.store, message.index.u64(), &users)?;
+        }
+
+        for user in users {
+            let index = message.index.u64();
+            MENTIONS.save(ctx.store, (&user, index), &Empty {})?;
+            UNREAD_MENTIONS.save(ctx.store, (&user, index), &Empty {})?;
+            UNREAD_MENTION_COUNT.update(ctx.store, &user, |count| -> Result<_> { Ok(count.unwrap_or_default() + 1) })?;
+
+            ctx.response.add_event(MentionEvent {
+                user,
+                index: message.index,
+                author: message.msg.user.clone(),
+                author_network_id: message.msg.network_id,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn mark_mentions_read(&self, ctx: &mut StateContext, user: &Addr, up_to: Option<ChatMessageIndex>) -> Result<()> {
+        let unread = UNREAD_MENTIONS.prefix(user)
+            .keys(ctx.store, None, up_to.map(|x| Bound::inclusive(x.u64())), Order::Ascending)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for index in &unread {
+            UNREAD_MENTIONS.remove(ctx.store, (user, *index));
+        }
+
+        self.subtract_unread_mentions(ctx, user, unread.len() as u32)
+    }
+
+    /// Takes a deleted message out of the inbox of everyone it mentioned
+    pub fn delete_mentions_of(&self, ctx: &mut StateContext, index: u64) -> Result<()> {
+        let Some(users) = MESSAGE_MENTIONS.may_load(ctx.store, index)? else {
+            return Ok(());
+        };
+        MESSAGE_MENTIONS.remove(ctx.store, index);
+
+        for user in users {
+            MENTIONS.remove(ctx.store, (&user, index));
+            if UNREAD_MENTIONS.has(ctx.store, (&user, index)) {
+                UNREAD_MENTIONS.remove(ctx.store, (&user, index));
+                self.subtract_unread_mentions(ctx, &user, 1)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn subtract_unread_mentions(&self, ctx: &mut StateContext, user: &Addr, read: u32) -> Result<()> {
+        let count = self.get_unread_mention_count(ctx.store, user)?.saturating_sub(read);
+        if count == 0 {
+            UNREAD_MENTION_COUNT.remove(ctx.store, user);
+        } else {
+            UNREAD_MENTION_COUNT.save(ctx.store, user, &count)?;
+        }
+
+        Ok(())
+    }
+
+    // an address valid on this chain, or a nickname set here
+    // the author isn't notified of their own mention, nor anyone who blocked them
+    fn mentioned_users(&self, store: &dyn Storage, message: &ChatMessageWithIndex) -> Result<Vec<Addr>> {
+        let msg = &message.msg;
+        if !can_mention(msg) {
+            return Ok(Vec::new());
+        }
+        // the text is ciphertext
+        if let Some(room) = &msg.room {
+            if matches!(self.get_room(store, room).map(|room| room.access), Ok(RoomAccess::Private { .. })) {
+                return Ok(Vec::new());
+            }
+        }
+
+        let mut users: Vec<Addr> = Vec::new();
+        for name in parse_mentions(&msg.message) {
+            let user = match self.api.addr_validate(&name) {
+                Ok(user) => Some(user),
+                Err(_) => self.get_nickname_owner(store, &name)?,
+            };
+
+            let Some(user) = user else { continue };
+            if user == msg.user || users.contains(&user) {
+                continue;
+            }
+            if self.get_blocked_set(store, &user)?.contains(&(msg.network_id, msg.user.clone())) {
+                continue;
+            }
+
+            users.push(user);
+        }
+
+        Ok(users)
+    }
+}
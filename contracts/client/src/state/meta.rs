@@ -1,11 +1,13 @@
-use cosmwasm_std::{Order, Storage};
+use cosmwasm_std::{Addr, Order, Storage};
 use cw_storage_plus::{Bound, Item, Map};
-use shared::msg::network::NetworkId;
+use shared::msg::{misc::ChannelOrder, network::NetworkId};
 
 use super::{State, StateContext};
 use anyhow::Result;
 
 const NETWORK_ID:Item<NetworkId> = Item::new("network-id");
+const CHANNEL_ORDER:Item<ChannelOrder> = Item::new("channel-order");
+const OWNER:Item<Addr> = Item::new("owner");
 
 impl State<'_> {
     pub fn get_network_id(&self, store: &dyn Storage) -> Result<NetworkId> {
@@ -15,4 +17,28 @@ impl State<'_> {
     pub fn set_network_id(&self, ctx: &mut StateContext, network_id: NetworkId) -> Result<()> {
         NETWORK_ID.save(ctx.store, &network_id).map_err(|err| err.into())
     }
+
+    pub fn get_channel_order(&self, store: &dyn Storage) -> Result<ChannelOrder> {
+        CHANNEL_ORDER.load(store).map_err(|err| err.into())
+    }
+
+    pub fn set_channel_order(&self, ctx: &mut StateContext, channel_order: ChannelOrder) -> Result<()> {
+        CHANNEL_ORDER.save(ctx.store, &channel_order).map_err(|err| err.into())
+    }
+
+    pub fn get_owner(&self, store: &dyn Storage) -> Result<Addr> {
+        OWNER.load(store).map_err(|err| err.into())
+    }
+
+    pub fn set_owner(&self, ctx: &mut StateContext, owner: &Addr) -> Result<()> {
+        OWNER.save(ctx.store, owner).map_err(|err| err.into())
+    }
+
+    pub fn ensure_owner(&self, store: &dyn Storage, sender: &Addr) -> Result<()> {
+        if *sender != self.get_owner(store)? {
+            anyhow::bail!("{} is not the contract owner", sender);
+        }
+
+        Ok(())
+    }
 }
\ No newline at end of file
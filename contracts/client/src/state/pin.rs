@@ -0,0 +1,98 @@
+use cosmwasm_std::{Addr, Order, Storage};
+use cw_storage_plus::Map;
+use shared::msg::{
+    chat_message::{ChatMessageId, ChatMessageIndex},
+    pin::{event::PinUpdatedEvent, Pin, PinnedMessage, MAX_PINS},
+    room::{Room, RoomId},
+};
+use anyhow::{bail, Result};
+
+use super::{chat::room_key, State, StateContext};
+
+// (room, server id), the main chat is ""
+// as the server sent them, including our own
+const PINS: Map<(&str, u64), Pin> = Map::new("pins");
+
+impl State<'_> {
+    /// Oldest message first
+    pub fn get_pins(&self, store: &dyn Storage, room: Option<&RoomId>) -> Result<Vec<PinnedMessage>> {
+        PINS.prefix(room_key(room))
+            .range(store, None, None, Order::Ascending)
+            .map(|x| {
+                let (id, pin) = x?;
+                Ok(PinnedMessage {
+                    message: self.get_chat_message_by_id(store, id.into())?,
+                    pin,
+                })
+            })
+            .collect()
+    }
+
+    /// Checks that the sender may pin the message, and returns the pin for the server
+    /// it's only stored when the server sends it back
+    pub fn check_pin(&self, store: &dyn Storage, sender: &Addr, index: ChatMessageIndex) -> Result<Pin> {
+        let Some(message) = self.get_chat_message(store, index)? else {
+            bail!("no message {}", index);
+        };
+        let Some(room) = message.room else {
+            bail!("only the server's admin pins messages in the main chat");
+        };
+        self.load_own_room(store, sender, &room)?;
+
+        let Some(id) = self.get_chat_message_id(store, index)? else {
+            bail!("the server hasn't acknowledged message {} yet", index);
+        };
+        if PINS.prefix(&room).keys(store, None, None, Order::Ascending).count() >= MAX_PINS {
+            bail!("{} already has {} pinned messages", room, MAX_PINS);
+        }
+
+        Ok(Pin { room: Some(room), id, by: sender.clone() })
+    }
+
+    pub fn check_unpin(&self, store: &dyn Storage, sender: &Addr, room: Option<RoomId>, id: ChatMessageId) -> Result<Pin> {
+        let Some(room) = room else {
+            bail!("only the server's admin unpins messages in the main chat");
+        };
+        self.load_own_room(store, sender, &room)?;
+
+        if !PINS.has(store, (&room, id.u64())) {
+            bail!("message {} isn't pinned in {}", id, room);
+        }
+
+        Ok(Pin { room: Some(room), id, by: sender.clone() })
+    }
+
+    /// From the server, for any room
+    pub fn store_pin(&self, ctx: &mut StateContext, pin: Pin, pinned: bool) -> Result<()> {
+        if let Some(message) = self.get_chat_message_by_id(ctx.store, pin.id)? {
+            if message.msg.room != pin.room {
+                bail!("message {} isn't in {}", pin.id, pin.room.as_deref().unwrap_or("the main chat"));
+            }
+        }
+
+        let key = (room_key(pin.room.as_ref()), pin.id.u64());
+        if pinned {
+            PINS.save(ctx.store, key, &pin)?;
+        } else {
+            PINS.remove(ctx.store, key);
+        }
+
+        ctx.response.add_event(PinUpdatedEvent { pin: &pin, pinned });
+
+        Ok(())
+    }
+
+    // pins are changed by the room's owner, on its home chain
+    fn load_own_room(&self, store: &dyn Storage, sender: &Addr, room_id: &RoomId) -> Result<Room> {
+        let room = self.get_room(store, room_id)?;
+
+        if room.home != self.get_network_id(store)? {
+            bail!("pins in {} can only be changed on {}", room.id, room.home);
+        }
+        if room.creator != *sender {
+            bail!("only {} can pin messages in {}", room.creator, room.id);
+        }
+
+        Ok(room)
+    }
+}
@@ -0,0 +1,53 @@
+use cosmwasm_std::{Order, Storage};
+use cw_storage_plus::Map;
+use shared::msg::poll::{event::{PollClosedEvent, PollCreatedEvent}, Poll, PollId, PollTally};
+use anyhow::{bail, Result};
+
+use super::{State, StateContext};
+
+// what the server told us about, the live tally only exists on the server
+const POLLS: Map<u64, PollTally> = Map::new("polls");
+
+impl State<'_> {
+    pub fn get_polls(&self, store: &dyn Storage) -> Result<Vec<PollTally>> {
+        POLLS.range(store, None, None, Order::Ascending)
+            .map(|x| x
+                .map(|(_, tally)| tally)
+                .map_err(|err| err.into())
+            )
+            .collect()
+    }
+
+    /// Checks a vote against what we know before it's sent to the server
+    /// the server has the final say, e.g. about double votes
+    pub fn check_vote(&self, store: &dyn Storage, poll_id: PollId, option: u32) -> Result<()> {
+        let tally = match POLLS.may_load(store, poll_id.u64())? {
+            Some(tally) => tally,
+            None => bail!("no such poll {}", poll_id),
+        };
+
+        if tally.closed || self.env.block.time >= tally.poll.closes_at {
+            bail!("poll {} is closed", poll_id);
+        }
+
+        if option as usize >= tally.poll.options.len() {
+            bail!("poll {} has no option {}", poll_id, option);
+        }
+
+        Ok(())
+    }
+
+    pub fn store_poll(&self, ctx: &mut StateContext, poll: Poll) -> Result<()> {
+        ctx.response.add_event(PollCreatedEvent { poll: &poll });
+        POLLS.save(ctx.store, poll.id.u64(), &PollTally::new(poll))?;
+
+        Ok(())
+    }
+
+    pub fn store_closed_poll(&self, ctx: &mut StateContext, tally: PollTally) -> Result<()> {
+        ctx.response.add_event(PollClosedEvent { tally: &tally });
+        POLLS.save(ctx.store, tally.poll.id.u64(), &tally)?;
+
+        Ok(())
+    }
+}
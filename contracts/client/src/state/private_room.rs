@@ -0,0 +1,220 @@
+use cosmwasm_std::{Addr, Binary, Order, Storage};
+use cw_storage_plus::Map;
+use shared::msg::{
+    chat_message::ChatMessage,
+    contract::client::EpochRoomKey,
+    encryption::{validate_encryption_key, EncryptedMessage, WrappedRoomKey},
+    room::{Room, RoomAccess, RoomId},
+};
+use anyhow::{bail, Result};
+
+use super::{State, StateContext};
+
+// published by users, for wrapping room keys
+const ENCRYPTION_KEYS: Map<&Addr, Binary> = Map::new("encryption-keys");
+// (room, epoch, member), only on the room's home chain
+const ROOM_KEYS: Map<(&str, u32, &Addr), WrappedRoomKey> = Map::new("room-keys");
+// room -> the member who left, until the owner rotates the key
+const ROTATION_DUE: Map<&str, Addr> = Map::new("room-rotation-due");
+
+impl State<'_> {
+    pub fn get_encryption_key(&self, store: &dyn Storage, user: &Addr) -> Result<Option<Binary>> {
+        ENCRYPTION_KEYS.may_load(store, user).map_err(|err| err.into())
+    }
+
+    pub fn set_encryption_key(&self, ctx: &mut StateContext, user: &Addr, key: Option<Binary>) -> Result<()> {
+        match key {
+            Some(key) => {
+                validate_encryption_key(&key)?;
+                ENCRYPTION_KEYS.save(ctx.store, user, &key)?;
+            },
+            None => ENCRYPTION_KEYS.remove(ctx.store, user),
+        }
+
+        Ok(())
+    }
+
+    /// Every key the member was given, oldest first
+    pub fn get_room_keys(&self, store: &dyn Storage, room_id: &RoomId, member: &Addr) -> Result<Vec<EpochRoomKey>> {
+        let epoch = private_room_epoch(&self.get_room(store, room_id)?)?;
+
+        (0..=epoch)
+            .filter_map(|epoch| {
+                ROOM_KEYS.may_load(store, (room_id, epoch, member))
+                    .map(|key| key.map(|key| EpochRoomKey { epoch, key }))
+                    .transpose()
+            })
+            .map(|x| x.map_err(|err| err.into()))
+            .collect()
+    }
+
+    /// The members are whoever the key is wrapped for, the caller sends the room on to the server
+    pub fn create_private_room(&self, ctx: &mut StateContext, creator: &Addr, name: String, keys: Vec<WrappedRoomKey>) -> Result<Room> {
+        let keys = self.validate_room_keys(ctx.store, keys)?;
+        let members: Vec<Addr> = keys.iter().map(|key| key.member.clone()).collect();
+        if !members.contains(creator) {
+            bail!("wrap the room key for yourself too");
+        }
+
+        let room = self.create_room(ctx, creator, name, RoomAccess::Private { members, epoch: 0 })?;
+        self.save_room_keys(ctx, &room.id, 0, keys)?;
+
+        Ok(room)
+    }
+
+    pub fn add_room_member(&self, ctx: &mut StateContext, sender: &Addr, room_id: &RoomId, key: WrappedRoomKey) -> Result<Room> {
+        let mut room = self.load_own_private_room(ctx.store, sender, room_id)?;
+        let mut key = self.validate_room_keys(ctx.store, vec![key])?;
+        let key = key.remove(0);
+
+        let RoomAccess::Private { members, epoch } = &mut room.access else { unreachable!() };
+        if members.contains(&key.member) {
+            bail!("{} is already in {}", key.member, room_id);
+        }
+        members.push(key.member.clone());
+        let epoch = *epoch;
+
+        self.save_room_keys(ctx, room_id, epoch, vec![key])?;
+        self.update_room(ctx, room.clone())?;
+
+        Ok(room)
+    }
+
+    /// The removed member still has the old key, so everyone else gets a new one right away
+    pub fn remove_room_member(&self, ctx: &mut StateContext, sender: &Addr, room_id: &RoomId, member: &Addr, keys: Vec<WrappedRoomKey>) -> Result<Room> {
+        let mut room = self.load_own_private_room(ctx.store, sender, room_id)?;
+
+        let RoomAccess::Private { members, .. } = &mut room.access else { unreachable!() };
+        if member == sender {
+            bail!("the owner can't leave {}", room_id);
+        }
+        if !members.contains(member) {
+            bail!("{} is not in {}", member, room_id);
+        }
+        members.retain(|x| x != member);
+
+        self.rotate_room_key(ctx, room, keys)
+    }
+
+    /// Nobody else can make a new key, so posting waits for the owner to rotate it
+    pub fn leave_room(&self, ctx: &mut StateContext, sender: &Addr, room_id: &RoomId) -> Result<Room> {
+        let mut room = self.get_room(ctx.store, room_id)?;
+        private_room_epoch(&room)?;
+        if room.home != self.get_network_id(ctx.store)? {
+            bail!("leave {} on {}", room.id, room.home);
+        }
+        if room.creator == *sender {
+            bail!("the owner can't leave {}", room_id);
+        }
+
+        let RoomAccess::Private { members, .. } = &mut room.access else { unreachable!() };
+        if !members.contains(sender) {
+            bail!("{} is not in {}", sender, room_id);
+        }
+        members.retain(|x| x != sender);
+
+        ROTATION_DUE.save(ctx.store, room_id, sender)?;
+        self.update_room(ctx, room.clone())?;
+
+        Ok(room)
+    }
+
+    pub fn set_room_key(&self, ctx: &mut StateContext, sender: &Addr, room_id: &RoomId, keys: Vec<WrappedRoomKey>) -> Result<Room> {
+        let room = self.load_own_private_room(ctx.store, sender, room_id)?;
+        self.rotate_room_key(ctx, room, keys)
+    }
+
+    /// Posting stops when a member left and the key wasn't rotated yet
+    pub fn check_room_key_current(&self, store: &dyn Storage, room_id: &RoomId) -> Result<()> {
+        if let Some(left) = ROTATION_DUE.may_load(store, room_id)? {
+            bail!("{} left {}, the owner has to rotate the key first", left, room_id);
+        }
+
+        Ok(())
+    }
+
+    /// Private rooms only carry ciphertext, made with the current key
+    /// and the content is plain text, anything else would be in the clear
+    pub fn check_encrypted_message(&self, store: &dyn Storage, room_id: &RoomId, message: &ChatMessage) -> Result<()> {
+        let room = self.get_room(store, room_id)?;
+        if let RoomAccess::Private { epoch, .. } = room.access {
+            if !message.content.is_text() {
+                bail!("messages in a private room are plain ciphertext");
+            }
+            let message = EncryptedMessage::from_message_text(&message.message)?;
+            if message.epoch != epoch {
+                bail!("{} is at key epoch {}, the message is for {}", room_id, epoch, message.epoch);
+            }
+        }
+
+        Ok(())
+    }
+
+    // one key per member, for members with a published encryption key
+    fn validate_room_keys(&self, store: &dyn Storage, keys: Vec<WrappedRoomKey>) -> Result<Vec<WrappedRoomKey>> {
+        let mut validated: Vec<WrappedRoomKey> = Vec::with_capacity(keys.len());
+
+        for mut key in keys {
+            key.member = self.api.addr_validate(key.member.as_str())?;
+            if validated.iter().any(|x| x.member == key.member) {
+                bail!("two keys for {}", key.member);
+            }
+            if self.get_encryption_key(store, &key.member)?.is_none() {
+                bail!("{} hasn't published an encryption key", key.member);
+            }
+            validate_encryption_key(&key.ephemeral_pubkey)?;
+
+            validated.push(key);
+        }
+
+        Ok(validated)
+    }
+
+    // our own private room, and the sender owns it
+    fn load_own_private_room(&self, store: &dyn Storage, sender: &Addr, room_id: &RoomId) -> Result<Room> {
+        let room = self.get_room(store, room_id)?;
+        private_room_epoch(&room)?;
+
+        if room.home != self.get_network_id(store)? {
+            bail!("{} can only be changed on {}", room.id, room.home);
+        }
+        if room.creator != *sender {
+            bail!("only {} can change {}", room.creator, room.id);
+        }
+
+        Ok(room)
+    }
+
+    // the next epoch, with a key for exactly the current members
+    fn rotate_room_key(&self, ctx: &mut StateContext, mut room: Room, keys: Vec<WrappedRoomKey>) -> Result<Room> {
+        let keys = self.validate_room_keys(ctx.store, keys)?;
+
+        let RoomAccess::Private { members, epoch } = &mut room.access else { unreachable!() };
+        if keys.len() != members.len() || !keys.iter().all(|key| members.contains(&key.member)) {
+            bail!("the new key has to be wrapped for exactly the members of {}", room.id);
+        }
+        *epoch += 1;
+        let epoch = *epoch;
+
+        self.save_room_keys(ctx, &room.id, epoch, keys)?;
+        ROTATION_DUE.remove(ctx.store, &room.id);
+        self.update_room(ctx, room.clone())?;
+
+        Ok(room)
+    }
+
+    fn save_room_keys(&self, ctx: &mut StateContext, room_id: &RoomId, epoch: u32, keys: Vec<WrappedRoomKey>) -> Result<()> {
+        for key in keys {
+            ROOM_KEYS.save(ctx.store, (room_id, epoch, &key.member.clone()), &key)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn private_room_epoch(room: &Room) -> Result<u32> {
+    match room.access {
+        RoomAccess::Private { epoch, .. } => Ok(epoch),
+        _ => bail!("{} is not a private room", room.id),
+    }
+}
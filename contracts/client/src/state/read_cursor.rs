@@ -0,0 +1,66 @@
+use cosmwasm_std::{Addr, Storage};
+use cw_storage_plus::Map;
+use shared::msg::{
+    chat_message::ChatMessageIndex,
+    contract::client::{ReadCursor, RoomUnread, MAX_UNREAD_COUNT},
+    room::{RoomAccess, RoomId},
+};
+use anyhow::{bail, Result};
+
+use super::{chat::room_key, State, StateContext};
+
+// (user, room), the main chat is ""
+const READ_CURSORS: Map<(&Addr, &str), ReadCursor> = Map::new("read-cursors");
+
+impl State<'_> {
+    /// The message has to be in the room, the cursor takes its server id if there is one already
+    pub fn set_read_cursor(&self, ctx: &mut StateContext, user: &Addr, room: Option<RoomId>, index: ChatMessageIndex) -> Result<()> {
+        let Some(message) = self.get_chat_message(ctx.store, index)? else {
+            bail!("no message {}", index);
+        };
+        if message.room != room {
+            bail!("message {} isn't in {}", index, room.as_deref().unwrap_or("the main chat"));
+        }
+
+        let cursor = ReadCursor {
+            index,
+            id: self.get_chat_message_id(ctx.store, index)?,
+        };
+        READ_CURSORS.save(ctx.store, (user, room_key(room.as_ref())), &cursor)?;
+
+        Ok(())
+    }
+
+    /// The main chat first, then every room the user can read
+    pub fn get_unread_counts(&self, store: &dyn Storage, user: &Addr) -> Result<Vec<RoomUnread>> {
+        let rooms = self.get_rooms(store)?
+            .into_iter()
+            .filter(|room| match &room.access {
+                RoomAccess::Private { members, .. } => members.contains(user),
+                _ => true,
+            })
+            .map(|room| Some(room.id));
+
+        std::iter::once(None)
+            .chain(rooms)
+            .map(|room| {
+                let cursor = self.get_read_cursor(store, user, room.as_ref())?;
+                let unread = self.count_room_messages_for(store, room.as_ref(), cursor.as_ref().map(|x| x.index), user, MAX_UNREAD_COUNT)?;
+
+                Ok(RoomUnread { room, cursor, unread })
+            })
+            .collect()
+    }
+
+    // filled in with the server id, if it came after the cursor was set
+    fn get_read_cursor(&self, store: &dyn Storage, user: &Addr, room: Option<&RoomId>) -> Result<Option<ReadCursor>> {
+        let Some(mut cursor) = READ_CURSORS.may_load(store, (user, room_key(room)))? else {
+            return Ok(None);
+        };
+        if cursor.id.is_none() {
+            cursor.id = self.get_chat_message_id(store, cursor.index)?;
+        }
+
+        Ok(Some(cursor))
+    }
+}
@@ -0,0 +1,86 @@
+use cosmwasm_std::{wasm_execute, Addr, Coin, Storage};
+use cw_storage_plus::{Item, Map};
+use shared::msg::{
+    dice::{event::DiceRollEvent, Dice},
+    nois::{NoisCallback, NoisProxyExecuteMsg},
+};
+use anyhow::{bail, Result};
+
+use super::{State, StateContext};
+
+const NOIS_PROXY: Item<Addr> = Item::new("nois-proxy");
+const LAST_ROLL_ID: Item<u64> = Item::new("last-roll-id");
+// rolls waiting for randomness, keyed by the Nois job id
+const PENDING_ROLLS: Map<&str, (Addr, Dice)> = Map::new("pending-rolls");
+
+impl State<'_> {
+    pub fn get_nois_proxy(&self, store: &dyn Storage) -> Result<Option<Addr>> {
+        NOIS_PROXY.may_load(store).map_err(|err| err.into())
+    }
+
+    pub fn set_nois_proxy(&self, ctx: &mut StateContext, proxy: Option<&Addr>) -> Result<()> {
+        match proxy {
+            Some(proxy) => NOIS_PROXY.save(ctx.store, proxy)?,
+            None => NOIS_PROXY.remove(ctx.store),
+        }
+
+        Ok(())
+    }
+
+    /// Asks the Nois proxy for the next round of randomness
+    /// the roll itself happens in [Self::handle_nois_callback]
+    pub fn request_roll(&self, ctx: &mut StateContext, user: &Addr, dice: Dice, funds: Vec<Coin>) -> Result<()> {
+        let proxy = match self.get_nois_proxy(ctx.store)? {
+            Some(proxy) => proxy,
+            None => bail!("dice rolls need a Nois proxy, and none is set up on this chain"),
+        };
+
+        let id = LAST_ROLL_ID.may_load(ctx.store)?.unwrap_or_default() + 1;
+        LAST_ROLL_ID.save(ctx.store, &id)?;
+
+        let job_id = format!("roll-{}", id);
+        PENDING_ROLLS.save(ctx.store, &job_id, &(user.clone(), dice))?;
+
+        // the proxy takes its fee from the funds sent along with the command
+        ctx.response.add_message(wasm_execute(proxy, &NoisProxyExecuteMsg::GetNextRandomness { job_id }, funds)?);
+
+        Ok(())
+    }
+
+    pub fn handle_nois_callback(&self, ctx: &mut StateContext, sender: &Addr, callback: NoisCallback) -> Result<()> {
+        if Some(sender) != self.get_nois_proxy(ctx.store)?.as_ref() {
+            bail!("only the Nois proxy can deliver randomness");
+        }
+
+        let (user, dice) = match PENDING_ROLLS.may_load(ctx.store, &callback.job_id)? {
+            Some(pending) => pending,
+            None => bail!("no pending roll for job {}", callback.job_id),
+        };
+        PENDING_ROLLS.remove(ctx.store, &callback.job_id);
+
+        let values = dice.roll(callback.randomness.as_slice())?;
+        let total = values.iter().map(|value| *value as u32).sum::<u32>();
+
+        // the randomness and its publish time are enough for anyone to redo the roll
+        let message = format!(
+            "{} rolled {}: {} = {} (nois round published at {}, randomness {})",
+            user,
+            dice,
+            values.iter().map(|value| value.to_string()).collect::<Vec<_>>().join(" + "),
+            total,
+            callback.published,
+            callback.randomness.to_hex(),
+        );
+
+        ctx.response.add_event(DiceRollEvent {
+            user: &user,
+            dice,
+            values: &values,
+            callback: &callback,
+        });
+
+        self.push_system_message(ctx, message)?;
+
+        Ok(())
+    }
+}
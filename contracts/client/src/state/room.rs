@@ -0,0 +1,222 @@
+use cosmwasm_std::{Addr, Order, Storage};
+use cw_storage_plus::Map;
+use shared::msg::{
+    chat_message::ChatMessage,
+    cw20::{BalanceResponse, Cw20QueryMsg},
+    cw721::{Cw721QueryMsg, TokensResponse},
+    room::{event::{RoomAccessSetEvent, RoomCreatedEvent}, validate_room_name, Room, RoomAccess, RoomAttestation, RoomDenom, RoomId},
+};
+use anyhow::{bail, Result};
+
+use super::{State, StateContext};
+
+// ours and the ones other clients told us about
+const ROOMS: Map<&str, Room> = Map::new("rooms");
+
+impl State<'_> {
+    pub fn get_rooms(&self, store: &dyn Storage) -> Result<Vec<Room>> {
+        ROOMS.range(store, None, None, Order::Ascending)
+            .map(|x| x
+                .map(|(_, room)| room)
+                .map_err(|err| err.into())
+            )
+            .collect()
+    }
+
+    pub fn get_room(&self, store: &dyn Storage, room_id: &RoomId) -> Result<Room> {
+        match ROOMS.may_load(store, room_id)? {
+            Some(room) => Ok(room),
+            None => bail!("no such room {}", room_id),
+        }
+    }
+
+    /// Checks the addresses in a rule from an execute message
+    pub fn validate_room_access(&self, access: RoomAccess) -> Result<RoomAccess> {
+        Ok(match access {
+            RoomAccess::Open => RoomAccess::Open,
+            RoomAccess::Cw721 { collection } => RoomAccess::Cw721 {
+                collection: self.api.addr_validate(collection.as_str())?,
+            },
+            RoomAccess::Balance { denom, min } => {
+                if min.is_zero() {
+                    bail!("a minimum balance of 0 is an open room");
+                }
+
+                let denom = match denom {
+                    RoomDenom::Native { denom } if denom.is_empty() => bail!("the denom can't be empty"),
+                    RoomDenom::Native { denom } => RoomDenom::Native { denom },
+                    RoomDenom::Cw20 { contract } => RoomDenom::Cw20 {
+                        contract: self.api.addr_validate(contract.as_str())?,
+                    },
+                };
+
+                RoomAccess::Balance { denom, min }
+            },
+            RoomAccess::Private { .. } => bail!("private rooms need keys, create one with create_private_room"),
+        })
+    }
+
+    /// Creates a room with this chain as its home, the caller sends it on to the server
+    pub fn create_room(&self, ctx: &mut StateContext, creator: &Addr, name: String, access: RoomAccess) -> Result<Room> {
+        validate_room_name(&name)?;
+
+        let home = self.get_network_id(ctx.store)?;
+        let room = Room {
+            id: Room::id_for(home, &name),
+            name,
+            home,
+            access,
+            creator: creator.clone(),
+        };
+        self.store_room(ctx, room.clone())?;
+
+        Ok(room)
+    }
+
+    /// Room ids are only ever created by their home client, so a second one is a mistake
+    pub fn store_room(&self, ctx: &mut StateContext, room: Room) -> Result<()> {
+        if ROOMS.has(ctx.store, &room.id) {
+            bail!("room {} already exists", room.id);
+        }
+
+        ctx.response.add_event(RoomCreatedEvent { room: &room });
+        ROOMS.save(ctx.store, &room.id, &room)?;
+
+        Ok(())
+    }
+
+    /// Changes who may post in one of our own rooms, the caller sends it on to the server
+    /// room creator only
+    pub fn set_room_access(&self, ctx: &mut StateContext, sender: &Addr, room_id: &RoomId, access: RoomAccess) -> Result<Room> {
+        let mut room = match ROOMS.may_load(ctx.store, room_id)? {
+            Some(room) => room,
+            None => bail!("no such room {}", room_id),
+        };
+
+        if room.home != self.get_network_id(ctx.store)? {
+            bail!("{} can only be changed on {}", room.id, room.home);
+        }
+
+        if room.creator != *sender {
+            bail!("only {} can change {}", room.creator, room.id);
+        }
+
+        if matches!(room.access, RoomAccess::Private { .. }) {
+            bail!("{} is private, and stays that way", room.id);
+        }
+
+        room.access = access;
+        self.update_room(ctx, room.clone())?;
+
+        Ok(room)
+    }
+
+    /// A changed rule from the room's home client, by way of the server
+    /// only the rule changes, the room is still the one we know
+    pub fn store_room_update(&self, ctx: &mut StateContext, update: Room) -> Result<()> {
+        let mut room = match ROOMS.may_load(ctx.store, &update.id)? {
+            Some(room) => room,
+            None => bail!("no such room {}", update.id),
+        };
+
+        if update.name != room.name || update.home != room.home || update.creator != room.creator {
+            bail!("only the access of {} can change", room.id);
+        }
+
+        // a private room is created that way and stays that way, see [Self::set_room_access]
+        if matches!(room.access, RoomAccess::Private { .. }) != matches!(update.access, RoomAccess::Private { .. }) {
+            bail!("{} can't become or stop being private", room.id);
+        }
+
+        room.access = update.access;
+        self.update_room(ctx, room)
+    }
+
+    /// A changed rule, from here or the room's home client
+    pub fn update_room(&self, ctx: &mut StateContext, room: Room) -> Result<()> {
+        ctx.response.add_event(RoomAccessSetEvent { room: &room });
+        ROOMS.save(ctx.store, &room.id, &room)?;
+
+        Ok(())
+    }
+
+    /// Checks that the user may post in the room from here
+    /// for a gated room, that's only on its home chain, where we can ask the collection
+    pub fn check_room_access(&self, store: &dyn Storage, room_id: &RoomId, user: &Addr) -> Result<Option<RoomAttestation>> {
+        let room = match ROOMS.may_load(store, room_id)? {
+            Some(room) => room,
+            None => bail!("no such room {}", room_id),
+        };
+
+        match room.access {
+            RoomAccess::Open => Ok(None),
+            RoomAccess::Cw721 { collection } => {
+                if room.home != self.get_network_id(store)? {
+                    bail!("{} is for holders, post to it from {}", room.id, room.home);
+                }
+
+                let resp: TokensResponse = self.querier.query_wasm_smart(&collection, &Cw721QueryMsg::Tokens {
+                    owner: user.to_string(),
+                    start_after: None,
+                    limit: Some(1),
+                })?;
+
+                match resp.tokens.into_iter().next() {
+                    Some(token_id) => Ok(Some(RoomAttestation::Cw721 {
+                        collection,
+                        token_id,
+                        height: self.env.block.height,
+                    })),
+                    None => bail!("{} is for holders of a token from {}", room.id, collection),
+                }
+            },
+            RoomAccess::Balance { denom, min } => {
+                if room.home != self.get_network_id(store)? {
+                    bail!("{} is for holders, post to it from {}", room.id, room.home);
+                }
+
+                let amount = match &denom {
+                    RoomDenom::Native { denom } => self.querier.query_balance(user, denom)?.amount,
+                    RoomDenom::Cw20 { contract } => {
+                        let resp: BalanceResponse = self.querier.query_wasm_smart(contract, &Cw20QueryMsg::Balance {
+                            address: user.to_string(),
+                        })?;
+                        resp.balance
+                    },
+                };
+
+                if amount < min {
+                    bail!("{} is for holders of at least {} {}, you have {}", room.id, min, denom, amount);
+                }
+
+                Ok(Some(RoomAttestation::Balance {
+                    denom,
+                    amount,
+                    height: self.env.block.height,
+                }))
+            },
+            RoomAccess::Private { members, .. } => {
+                if room.home != self.get_network_id(store)? {
+                    bail!("{} is private, post to it from {}", room.id, room.home);
+                }
+
+                if !members.contains(user) {
+                    bail!("{} is only for its members", room.id);
+                }
+
+                self.check_room_key_current(store, &room.id)?;
+
+                Ok(None)
+            },
+        }
+    }
+
+    /// Checks a message from another chain against the room's rule
+    /// rooms we haven't heard of yet can't be checked, the packet may simply have overtaken it
+    pub fn check_incoming_room_message(&self, store: &dyn Storage, message: &ChatMessage) -> Result<()> {
+        match message.room.as_ref().map(|room_id| ROOMS.may_load(store, room_id)).transpose()?.flatten() {
+            Some(room) => room.honors(message),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use cosmwasm_std::{from_json, Addr, Binary, CanonicalAddr, Storage};
+use cw_storage_plus::Map;
+use shared::msg::signed::{adr036_sign_hash, pubkey_to_canonical, SignedPayload};
+use anyhow::{bail, Result};
+
+use super::{State, StateContext};
+
+// signer -> the nonce their next signed message must have
+const NONCES: Map<&Addr, u64> = Map::new("nonces");
+
+impl State<'_> {
+    pub fn get_nonce(&self, store: &dyn Storage, user: &Addr) -> Result<u64> {
+        Ok(NONCES.may_load(store, user)?.unwrap_or_default())
+    }
+
+    /// Checks the signature and uses up the nonce
+    /// returns the signer, who the message is from no matter who submitted it
+    pub fn verify_signed_message(&self, ctx: &mut StateContext, payload: &Binary, pubkey: &Binary, signature: &Binary) -> Result<(Addr, SignedPayload)> {
+        let signer = self.api.addr_humanize(&CanonicalAddr::from(pubkey_to_canonical(pubkey)))?;
+
+        let hash = adr036_sign_hash(signer.as_str(), payload);
+        if !self.api.secp256k1_verify(&hash, signature, pubkey)? {
+            bail!("invalid signature for {}", signer);
+        }
+
+        let payload: SignedPayload = from_json(payload)?;
+        if payload.contract != self.env.contract.address.as_str() {
+            bail!("the message was signed for {}", payload.contract);
+        }
+
+        let nonce = self.get_nonce(ctx.store, &signer)?;
+        if payload.nonce != nonce {
+            bail!("expected nonce {} for {}, got {}", nonce, signer, payload.nonce);
+        }
+        NONCES.save(ctx.store, &signer, &(nonce + 1))?;
+
+        Ok((signer, payload))
+    }
+}
@@ -0,0 +1,215 @@
+use std::collections::VecDeque;
+
+use cosmwasm_std::{to_json_string, BankMsg, Coin, IbcMsg, IbcTimeout, MessageInfo, Order, Reply, Storage, SubMsg};
+use cw_storage_plus::{Item, Map};
+use cw_utils::Expiration;
+use shared::{
+    ibc::TIMEOUT_SECONDS,
+    msg::{
+        chat_message::{ChatMessage, ChatMessageIndex, ChatMessageKind},
+        content::MessageContent,
+        contract::client::TransferChannel,
+        memo::{IbcCallbackMemo, IbcLifecycleComplete},
+        network::NetworkId,
+        tip::{add_tip, event::{TipEvent, TipSettledEvent}, format_coins, PendingTip},
+    },
+};
+use anyhow::{bail, Context, Result};
+
+use super::{State, StateContext};
+
+// network id -> our end of the ICS-20 channel to it
+const TRANSFER_CHANNELS: Map<&str, String> = Map::new("transfer-channels");
+// totals of what was sent from here, by local message index
+const MESSAGE_TIPS: Map<u64, Vec<Coin>> = Map::new("message-tips");
+// and by (network id, author)
+const USER_TIPS: Map<(&str, &str), Vec<Coin>> = Map::new("user-tips");
+// tips whose transfers were sent in this tx, in order, until each one's reply gives us its packet
+const UNSENT_TIPS: Item<VecDeque<PendingTip>> = Item::new("unsent-tips");
+// (our end of the transfer channel, packet sequence) -> the tip it carries, until ibc-hooks calls back
+const PENDING_TIPS: Map<(&str, u64), PendingTip> = Map::new("pending-tips");
+
+/// The transfer's reply, to learn its packet sequence
+pub const TIP_TRANSFER_REPLY_ID: u64 = 2;
+
+impl State<'_> {
+    pub fn get_transfer_channels(&self, store: &dyn Storage) -> Result<Vec<TransferChannel>> {
+        TRANSFER_CHANNELS.range(store, None, None, Order::Ascending)
+            .map(|x| {
+                let (network_id, channel_id) = x?;
+                Ok(TransferChannel {
+                    network_id: network_id.parse()?,
+                    channel_id,
+                })
+            })
+            .collect()
+    }
+
+    pub fn set_transfer_channel(&self, ctx: &mut StateContext, network_id: NetworkId, channel_id: Option<String>) -> Result<()> {
+        match channel_id {
+            Some(channel_id) => TRANSFER_CHANNELS.save(ctx.store, &network_id.to_string(), &channel_id)?,
+            None => TRANSFER_CHANNELS.remove(ctx.store, &network_id.to_string()),
+        }
+
+        Ok(())
+    }
+
+    pub fn get_message_tips(&self, store: &dyn Storage, index: ChatMessageIndex) -> Result<Vec<Coin>> {
+        Ok(MESSAGE_TIPS.may_load(store, index.u64())?.unwrap_or_default())
+    }
+
+    /// The message is gone, its author's total keeps the tips
+    pub fn delete_message_tips(&self, ctx: &mut StateContext, index: u64) {
+        MESSAGE_TIPS.remove(ctx.store, index);
+    }
+
+    pub fn get_user_tips(&self, store: &dyn Storage, network_id: NetworkId, address: &str) -> Result<Vec<Coin>> {
+        Ok(USER_TIPS.may_load(store, (&network_id.to_string(), address))?.unwrap_or_default())
+    }
+
+    /// Sends the funds on to the author of the message
+    /// returns the action message telling everyone about it, for the caller to send to the server
+    /// a tip to another chain only counts once its transfer is acknowledged, see [Self::settle_tip]
+    pub fn tip(&self, ctx: &mut StateContext, info: &MessageInfo, index: ChatMessageIndex) -> Result<ChatMessage> {
+        if info.funds.is_empty() {
+            bail!("send the tip along as funds");
+        }
+
+        let message = match self.get_chat_message(ctx.store, index)? {
+            Some(message) => message,
+            None => bail!("no such message {}", index),
+        };
+
+        if message.kind == ChatMessageKind::System {
+            bail!("system messages can't be tipped");
+        }
+
+        let network_id = self.get_network_id(ctx.store)?;
+        if message.network_id == network_id {
+            ctx.response.add_message(BankMsg::Send {
+                to_address: message.user.to_string(),
+                amount: info.funds.clone(),
+            });
+            self.add_to_tip_totals(ctx, index, message.network_id, message.user.as_str(), &info.funds)?;
+        } else {
+            let channel_id = match TRANSFER_CHANNELS.may_load(ctx.store, &message.network_id.to_string())? {
+                Some(channel_id) => channel_id,
+                None => bail!("tips to {} aren't set up on this chain", message.network_id),
+            };
+
+            // ibc-hooks tells us how it went
+            let memo = to_json_string(&IbcCallbackMemo { ibc_callback: self.env.contract.address.to_string() })?;
+
+            let mut unsent = UNSENT_TIPS.may_load(ctx.store)?.unwrap_or_default();
+            for coin in &info.funds {
+                unsent.push_back(PendingTip {
+                    index,
+                    tipper: info.sender.clone(),
+                    author: message.user.clone(),
+                    network_id: message.network_id,
+                    amount: coin.clone(),
+                });
+                let msg = IbcMsg::Transfer {
+                    channel_id: channel_id.clone(),
+                    to_address: message.user.to_string(),
+                    amount: coin.clone(),
+                    timeout: IbcTimeout::with_timestamp(self.env.block.time.plus_seconds(TIMEOUT_SECONDS)),
+                    memo: Some(memo.clone()),
+                };
+
+                ctx.response.add_raw_submessage(SubMsg::reply_on_success(msg, TIP_TRANSFER_REPLY_ID));
+            }
+            UNSENT_TIPS.save(ctx.store, &unsent)?;
+        }
+
+        ctx.response.add_event(TipEvent {
+            index,
+            tipper: &info.sender,
+            author: &message.user,
+            network_id: message.network_id,
+            amount: &info.funds,
+        });
+
+        Ok(ChatMessage {
+            user: info.sender.clone(),
+            network_id,
+            message: format!("tipped {} {}", message.user, format_coins(&info.funds)),
+            kind: ChatMessageKind::Action,
+            content: MessageContent::Text,
+            room: None,
+            attestation: None,
+            expires: Expiration::Never {},
+        })
+    }
+
+    /// The transfer went out, keep the tip by its packet until ibc-hooks calls back
+    /// the replies come back in the order the transfers were sent
+    pub fn handle_tip_transfer_reply(&self, ctx: &mut StateContext, reply: Reply) -> Result<()> {
+        let mut unsent = UNSENT_TIPS.may_load(ctx.store)?.unwrap_or_default();
+        let tip = unsent.pop_front().context("no tip waiting for its transfer")?;
+        if unsent.is_empty() {
+            UNSENT_TIPS.remove(ctx.store);
+        } else {
+            UNSENT_TIPS.save(ctx.store, &unsent)?;
+        }
+
+        let response = reply.result.into_result().map_err(anyhow::Error::msg)?;
+
+        let send_packet = response.events
+            .iter()
+            .find(|event| event.ty == "send_packet")
+            .context("no send_packet event for the transfer")?;
+        let attr = |key: &str| send_packet.attributes
+            .iter()
+            .find(|attr| attr.key == key)
+            .map(|attr| attr.value.clone())
+            .with_context(|| format!("no {} in the send_packet event", key));
+
+        let channel = attr("packet_src_channel")?;
+        let sequence = attr("packet_sequence")?.parse::<u64>()?;
+        PENDING_TIPS.save(ctx.store, (&channel, sequence), &tip)?;
+
+        Ok(())
+    }
+
+    /// Called by ibc-hooks once the transfer is acknowledged or timed out
+    /// a delivered tip is added to the totals, otherwise the refund goes on to the tipper
+    pub fn settle_tip(&self, ctx: &mut StateContext, complete: IbcLifecycleComplete) -> Result<()> {
+        let (channel, sequence, delivered) = match complete {
+            IbcLifecycleComplete::IbcAck { channel, sequence, success, .. } => (channel, sequence, success),
+            IbcLifecycleComplete::IbcTimeout { channel, sequence } => (channel, sequence, false),
+        };
+
+        let Some(tip) = PENDING_TIPS.may_load(ctx.store, (&channel, sequence))? else {
+            bail!("no tip sent as {} on {}", sequence, channel);
+        };
+        PENDING_TIPS.remove(ctx.store, (&channel, sequence));
+
+        if delivered {
+            self.add_to_tip_totals(ctx, tip.index, tip.network_id, tip.author.as_str(), &[tip.amount.clone()])?;
+        } else {
+            ctx.response.add_message(BankMsg::Send {
+                to_address: tip.tipper.to_string(),
+                amount: vec![tip.amount.clone()],
+            });
+        }
+
+        ctx.response.add_event(TipSettledEvent { tip: &tip, delivered });
+
+        Ok(())
+    }
+
+    fn add_to_tip_totals(&self, ctx: &mut StateContext, index: ChatMessageIndex, network_id: NetworkId, author: &str, amount: &[Coin]) -> Result<()> {
+        // a tip settled after its message expired only counts for the author
+        if self.get_chat_message(ctx.store, index)?.is_some() {
+            let total = add_tip(self.get_message_tips(ctx.store, index)?, amount)?;
+            MESSAGE_TIPS.save(ctx.store, index.u64(), &total)?;
+        }
+
+        let user_key = (&*network_id.to_string(), author);
+        let total = add_tip(USER_TIPS.may_load(ctx.store, user_key)?.unwrap_or_default(), amount)?;
+        USER_TIPS.save(ctx.store, user_key, &total)?;
+
+        Ok(())
+    }
+}
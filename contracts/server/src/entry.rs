@@ -1,9 +1,9 @@
 use cosmwasm_std::{
-    entry_point, Deps, DepsMut, Empty, Env, IbcBasicResponse, IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcChannelOpenResponse, IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse, MessageInfo, QueryResponse, Response
+    entry_point, Ibc3ChannelOpenResponse, Deps, DepsMut, Empty, Env, IbcBasicResponse, IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcChannelOpenResponse, IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse, MessageInfo, QueryResponse, Response
 };
 use cw2::{get_contract_version, set_contract_version};
 use shared::{
-    msg::contract::server::{InfoResp, QueryMsg}, response::{QueryResponseExt, ResponseBuilder},
+    ibc::IbcAck, msg::{contract::server::{ExecuteMsg, IdentityResp, InfoResp, InstantiateMsg, PollResp, PollsResp, QueryMsg}, ibc::IbcExecuteMsg, network::NetworkId, pin::Pin}, response::{QueryResponseExt, ResponseBuilder},
 };
 use anyhow::Result;
 
@@ -15,19 +15,63 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 #[entry_point]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
-    _info: MessageInfo,
-    _msg: Empty,
+    env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
 ) -> Result<Response> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    let (state, mut ctx) = StateContext::new(deps, env)?;
+    state.set_channel_order(&mut ctx, msg.channel_order.unwrap_or_default())?;
+    state.set_owner(&mut ctx, &info.sender)?;
+    state.set_network_notices(&mut ctx, msg.network_notices.unwrap_or_default())?;
+    state.set_network_id(&mut ctx, msg.network_id.unwrap_or(NetworkId::Neutron))?;
+
     Ok(Response::default())
 }
 
 #[entry_point]
-pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: Empty) -> Result<Response> {
+pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> Result<Response> {
     let (state, mut ctx) = StateContext::new(deps, env)?;
 
+    match msg {
+        ExecuteMsg::ClosePolls {  } => {
+            let msgs = state.close_expired_polls(&mut ctx)?
+                .into_iter()
+                .map(|tally| IbcExecuteMsg::PollClosed { tally })
+                .collect();
+
+            state.broadcast_to_clients(&mut ctx, msgs)?;
+        },
+        ExecuteMsg::PostFromTransfer { post } => {
+            state.post_from_transfer(&mut ctx, &info.sender, post)?;
+        },
+        ExecuteMsg::SetMemoChannel { channel_id, network_id } => {
+            state.ensure_owner(ctx.store, &info.sender)?;
+            state.set_memo_channel(&mut ctx, &channel_id, network_id)?;
+        },
+        ExecuteMsg::SetClientConnection { connection_id, network_id } => {
+            state.ensure_owner(ctx.store, &info.sender)?;
+            state.set_client_connection(&mut ctx, &connection_id, network_id)?;
+        },
+        ExecuteMsg::SetNetworkNotices { enabled } => {
+            state.ensure_owner(ctx.store, &info.sender)?;
+            state.set_network_notices(&mut ctx, enabled)?;
+        },
+        ExecuteMsg::Announce { message, content } => {
+            state.ensure_owner(ctx.store, &info.sender)?;
+            state.announce(&mut ctx, &info.sender, message, content.unwrap_or_default())?;
+        },
+        ExecuteMsg::Pin { room, id } => {
+            state.ensure_owner(ctx.store, &info.sender)?;
+            state.broadcast_pin(&mut ctx, Pin { room, id, by: info.sender }, true)?;
+        },
+        ExecuteMsg::Unpin { room, id } => {
+            state.ensure_owner(ctx.store, &info.sender)?;
+            state.broadcast_pin(&mut ctx, Pin { room, id, by: info.sender }, false)?;
+        },
+    }
+
     Ok(ctx.response.into_response())
 }
 
@@ -39,9 +83,33 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<QueryResponse> {
         QueryMsg::Info {  } => {
             let client_channels = state.get_client_channels(store)?;
             let info = InfoResp {
-                client_channels
+                client_channels,
+                dead_client_channels: state.get_dead_client_channels(store)?,
+                channel_order: state.get_channel_order(store)?,
+                owner: state.get_owner(store)?,
+                memo_channels: state.get_memo_channels(store)?,
+                client_connections: state.get_client_connections(store)?,
+                network_notices: state.get_network_notices(store)?,
+                network_id: state.get_network_id(store)?,
             };
             info.query_result()
+        },
+        QueryMsg::Poll { poll_id } => {
+            PollResp {
+                tally: state.get_poll(store, poll_id)?,
+            }.query_result()
+        },
+        QueryMsg::Polls {  } => {
+            PollsResp {
+                polls: state.get_polls(store)?,
+            }.query_result()
+        },
+        QueryMsg::Identity { identity } => {
+            let identity = state.resolve_identity(store, &identity)?;
+            IdentityResp {
+                members: state.get_identity_members(store, &identity)?,
+                identity,
+            }.query_result()
         }
     }
 }
@@ -60,9 +128,9 @@ pub fn ibc_channel_open(
     env: Env,
     msg: IbcChannelOpenMsg,
 ) -> Result<IbcChannelOpenResponse> {
-    let (state, _) = StateContext::new(deps, env)?;
-    state.handle_ibc_channel_open(msg)?;
-    Ok(None)
+    let (state, ctx) = StateContext::new(deps, env)?;
+    let version = state.handle_ibc_channel_open(ctx.store, msg)?;
+    Ok(Some(Ibc3ChannelOpenResponse { version }))
 }
 
 #[entry_point]
@@ -94,15 +162,19 @@ pub fn ibc_packet_receive(
     msg: IbcPacketReceiveMsg,
 ) -> Result<IbcReceiveResponse> {
     let (state, mut ctx) = StateContext::new(deps, env)?;
-    state.handle_ibc_packet_receive(&mut ctx, msg)?;
 
-    Ok(ctx.response.into_ibc_recv_response_success(None))
+    // Always acknowledge instead of aborting, so the sender finds out what went wrong.
+    // A failed packet leaves nothing behind, its writes are dropped along with the partial response
+    match ctx.transactional(|ctx| state.handle_ibc_packet_receive(ctx, msg)) {
+        Ok(success) => ctx.response.into_ibc_recv_response_ack(&IbcAck::Success(success)),
+        Err(err) => ResponseBuilder::new_mute_events().into_ibc_recv_response_ack(&IbcAck::from(err)),
+    }
 }
 
 #[entry_point]
 pub fn ibc_packet_ack(deps: DepsMut, env: Env, ack: IbcPacketAckMsg) -> Result<IbcBasicResponse> {
-    let (state, ctx) = StateContext::new(deps, env)?;
-    state.handle_ibc_packet_ack(ack)?;
+    let (state, mut ctx) = StateContext::new(deps, env)?;
+    state.handle_ibc_packet_ack(&mut ctx, ack)?;
     Ok(ctx.response.into_ibc_response())
 }
 
@@ -112,7 +184,7 @@ pub fn ibc_packet_timeout(
     env: Env,
     msg: IbcPacketTimeoutMsg,
 ) -> Result<IbcBasicResponse> {
-    let (state, ctx) = StateContext::new(deps, env)?;
-    state.handle_ibc_packet_timeout(msg)?;
+    let (state, mut ctx) = StateContext::new(deps, env)?;
+    state.handle_ibc_packet_timeout(&mut ctx, msg)?;
     Ok(ctx.response.into_ibc_response())
 }
@@ -1,11 +1,17 @@
 pub mod ibc;
+pub mod chat;
+pub mod meta;
+pub mod poll;
+pub mod memo;
+pub mod identity;
+pub mod pin;
 
 /// Generally speaking - all entry points get a State (read-only)
 /// instantiate/execute/migrate get that _and_ a StateContext (writable)
 use cosmwasm_std::{Api, Deps, DepsMut, Empty, Env, QuerierWrapper, Storage};
 use anyhow::Result;
 use cw2::get_contract_version;
-use shared::{contract_kind::ContractKind, response::ResponseBuilder};
+use shared::{contract_kind::ContractKind, response::ResponseBuilder, storage::transactional};
 
 
 /// State is a wrapper around the environment and storage, and provides a simplified API
@@ -51,4 +57,17 @@ impl<'a> StateContext<'a> {
             },
         ))
     }
+
+    /// Runs `f` on a cache of the store, see [transactional]
+    pub fn transactional<T>(&mut self, f: impl FnOnce(&mut StateContext) -> Result<T>) -> Result<T> {
+        transactional(self.store, &mut self.response, |store, response| {
+            let mut inner = StateContext {
+                store,
+                response: std::mem::replace(response, ResponseBuilder::new_mute_events()),
+            };
+            let result = f(&mut inner);
+            *response = inner.response;
+            result
+        })
+    }
 }
\ No newline at end of file
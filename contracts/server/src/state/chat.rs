@@ -0,0 +1,34 @@
+use cosmwasm_std::Storage;
+use cw_storage_plus::Item;
+use shared::{ibc::{IbcAckError, IbcAckErrorCode}, msg::chat_message::{ChatMessage, ChatMessageId, ChatMessageKind}};
+
+use super::{State, StateContext};
+use anyhow::Result;
+
+const LAST_CHAT_MESSAGE_ID:Item<u64> = Item::new("last-chat-message-id");
+
+impl State<'_> {
+    /// Assigns the next globally unique id to a message passing through the server
+    pub fn next_chat_message_id(&self, ctx: &mut StateContext) -> Result<ChatMessageId> {
+        let id = LAST_CHAT_MESSAGE_ID.may_load(ctx.store)?.unwrap_or_default() + 1;
+        LAST_CHAT_MESSAGE_ID.save(ctx.store, &id)?;
+
+        Ok(id.into())
+    }
+
+    /// What every message relayed to the clients has to pass, however it got here
+    /// the other clients render it, so the sender isn't taken at its word
+    pub fn check_relayed_message(&self, message: &ChatMessage) -> Result<()> {
+        // system messages are local to each client, and can't be relayed as if a contract wrote them
+        // network notices and announcements only come from here
+        if matches!(message.kind, ChatMessageKind::System | ChatMessageKind::Network | ChatMessageKind::Announcement) {
+            anyhow::bail!(IbcAckError::new(IbcAckErrorCode::UnsupportedMessage, "system messages are not broadcast"));
+        }
+
+        if let Err(err) = message.content.validate(message.kind, &message.message) {
+            anyhow::bail!(IbcAckError::new(IbcAckErrorCode::Rejected, err.to_string()));
+        }
+
+        Ok(())
+    }
+}
@@ -1,23 +1,73 @@
+use std::collections::BTreeMap;
+
 use cosmwasm_std::{
-    from_binary, from_json, to_json_binary, IbcChannel, IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcEndpoint, IbcMsg, IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcTimeout, Order, Storage
+    from_binary, from_json, to_json_binary, IbcChannel, IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcEndpoint, IbcMsg, IbcOrder, IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcTimeout, Order, Storage
 };
 use cw_storage_plus::{Deque, Item, Map};
 use shared::{ibc::{
-    event::{IbcChannelCloseEvent, IbcChannelConnectEvent},
-    validate_ibc_channel_order_and_version, TIMEOUT_SECONDS,
-}, msg::{chat_message::event::ChatMessageEvent, ibc::IbcExecuteMsg}};
-use anyhow::Result;
+    event::{DeadClientForgottenEvent, IbcChannelCloseEvent, IbcChannelConnectEvent, IbcPacketAckEvent},
+    IbcAck, IbcAckError, IbcAckErrorCode,
+    channel_protocol_version, negotiate_ibc_channel_connect, negotiate_ibc_channel_open, TIMEOUT_SECONDS,
+}, msg::{chat_message::{event::ChatMessageEvent, ChatMessage, ChatMessageKind, ChatMessageWithIndex}, content::MessageContent, contract::server::ClientConnection, ibc::{IbcAckSuccess, IbcExecuteMsg}, identity::IdentityLink, network::NetworkId, pin::event::PinUpdatedEvent, room::Room}};
+use cw_utils::Expiration;
+use anyhow::{Context, Result};
 
 use super::{State, StateContext};
 
 // TODO - make newtype with all the impls
 type IbcChannelKey = String;
 
-// keyed by the counterside port id
+// identifies a client across channels, i.e. the connection and the counterside port id
+type ClientKey = String;
+
+// keyed by our own endpoint
 const CLIENT_CHANNELS: Map<IbcChannelKey, IbcChannel> = Map::new("clients");
 
+// ordered client channels that were closed by a timeout
+// outgoing packets are queued until that client connects a new channel
+const DEAD_CLIENT_CHANNELS: Map<ClientKey, IbcChannel> = Map::new("dead-clients");
+const CLIENT_PACKET_QUEUE: Map<(ClientKey, u64), IbcExecuteMsg> = Map::new("client-packet-queue");
+// past this many queued packets the client is given up on, see [State::queue_for_client]
+const MAX_CLIENT_PACKET_QUEUE: u64 = 1000;
+// packets that were in flight when the channel died, by their sequence there
+// they were sent before anything in the queue, so they go out again before it
+const CLIENT_TIMED_OUT: Map<(ClientKey, u64), Vec<IbcExecuteMsg>> = Map::new("client-timed-out");
+
+// which chain is on the other end, from [IbcExecuteMsg::ClientConnected]
+const CLIENT_NETWORKS: Map<IbcChannelKey, NetworkId> = Map::new("client-networks");
+
+// connection id -> the network the owner expects a client from on it
+const CLIENT_CONNECTIONS: Map<&str, NetworkId> = Map::new("client-connections");
+
+// outgoing messages collected while handling a packet
+#[derive(Default)]
+struct FanOut {
+    live: BTreeMap<IbcChannelKey, (IbcChannel, Vec<IbcExecuteMsg>)>,
+    dead: BTreeMap<ClientKey, Vec<IbcExecuteMsg>>,
+}
+
+impl FanOut {
+    // queues the other's messages after ours
+    fn append(&mut self, other: FanOut) {
+        for (key, (channel, msgs)) in other.live {
+            self.live.entry(key).or_insert_with(|| (channel, Vec::new())).1.extend(msgs);
+        }
+        for (key, msgs) in other.dead {
+            self.dead.entry(key).or_default().extend(msgs);
+        }
+    }
+}
+
 fn channel_to_key(channel: &IbcChannel) -> IbcChannelKey {
-    format!("{}-{}", channel.endpoint.port_id, channel.endpoint.channel_id)
+    endpoint_to_key(&channel.endpoint)
+}
+
+fn endpoint_to_key(endpoint: &IbcEndpoint) -> IbcChannelKey {
+    format!("{}-{}", endpoint.port_id, endpoint.channel_id)
+}
+
+fn channel_to_client_key(channel: &IbcChannel) -> ClientKey {
+    format!("{}-{}", channel.connection_id, channel.counterparty_endpoint.port_id)
 }
 
 impl State<'_> {
@@ -31,19 +81,78 @@ impl State<'_> {
             .collect()
     }
 
-    pub fn handle_ibc_channel_open(&self, msg: IbcChannelOpenMsg) -> Result<()> {
-        validate_ibc_channel_order_and_version(msg.channel(), msg.counterparty_version())?;
+    pub fn get_dead_client_channels(&self, store: &dyn Storage) -> Result<Vec<IbcChannel>> {
+        DEAD_CLIENT_CHANNELS.range(store, None, None, Order::Ascending)
+            .map(|x| x
+                .map(|(_, channel)| channel)
+                .map_err(|err| err.into())
+            )
+            .collect()
+    }
+
+    /// Sends the message to a connected client
+    /// each client gets it in whatever protocol version its channel agreed on
+    fn send_to_client(&self, ctx: &mut StateContext, channel: &IbcChannel, msg: &IbcExecuteMsg) -> Result<()> {
+        ctx.response.add_message(IbcMsg::SendPacket {
+            channel_id: channel.endpoint.channel_id.clone(),
+            data: msg.encode(channel_protocol_version(channel)?)?,
+            timeout: IbcTimeout::with_timestamp(self.env.block.time.plus_seconds(TIMEOUT_SECONDS)),
+        });
+
+        Ok(())
+    }
+
+    /// Queues the message for a client whose channel died
+    /// a client that stays away until its queue is full is forgotten, it starts over like a new client if it's ever back
+    fn queue_for_client(&self, ctx: &mut StateContext, client_key: &ClientKey, msg: &IbcExecuteMsg) -> Result<()> {
+        if !DEAD_CLIENT_CHANNELS.has(ctx.store, client_key.clone()) {
+            return Ok(());
+        }
+
+        let next_index = CLIENT_PACKET_QUEUE
+            .prefix(client_key.clone())
+            .keys(ctx.store, None, None, Order::Descending)
+            .next()
+            .transpose()?
+            .map_or(0, |index| index + 1);
+
+        if next_index >= MAX_CLIENT_PACKET_QUEUE {
+            self.forget_dead_client(ctx, client_key)?;
+            return Ok(());
+        }
+
+        CLIENT_PACKET_QUEUE.save(ctx.store, (client_key.clone(), next_index), msg)?;
+
+        Ok(())
+    }
+
+    fn forget_dead_client(&self, ctx: &mut StateContext, client_key: &ClientKey) -> Result<()> {
+        let channel = DEAD_CLIENT_CHANNELS.load(ctx.store, client_key.clone())?;
+        DEAD_CLIENT_CHANNELS.remove(ctx.store, client_key.clone());
+        CLIENT_PACKET_QUEUE.prefix(client_key.clone()).clear(ctx.store, None);
+        CLIENT_TIMED_OUT.prefix(client_key.clone()).clear(ctx.store, None);
+
+        ctx.response
+            .add_event(DeadClientForgottenEvent { channel: &channel });
+
         Ok(())
     }
 
+    /// Returns the version string the channel should be opened with
+    pub fn handle_ibc_channel_open(&self, store: &dyn Storage, msg: IbcChannelOpenMsg) -> Result<String> {
+        negotiate_ibc_channel_open(msg.channel(), msg.counterparty_version(), self.get_channel_order(store)?)
+    }
+
     pub fn handle_ibc_channel_connect(
         &self,
         ctx: &mut StateContext,
         msg: IbcChannelConnectMsg,
     ) -> Result<()> {
-        let channel = msg.channel();
+        let version = negotiate_ibc_channel_connect(msg.channel(), msg.counterparty_version(), self.get_channel_order(ctx.store)?)?;
 
-        validate_ibc_channel_order_and_version(channel, msg.counterparty_version())?;
+        // the stored channel always carries the agreed version, not the proposal
+        let mut channel = msg.channel().clone();
+        channel.version = version.to_string();
 
         let key = channel_to_key(&channel); 
 
@@ -51,10 +160,40 @@ impl State<'_> {
             anyhow::bail!("channel for {} already exists", key);
         }
 
-        CLIENT_CHANNELS.save(ctx.store, key, channel)?;
+        CLIENT_CHANNELS.save(ctx.store, key, &channel)?;
 
         ctx.response
-            .add_event(IbcChannelConnectEvent { channel });
+            .add_event(IbcChannelConnectEvent { channel: &channel });
+
+        // if this client's previous channel died, resume with whatever was queued for it
+        let client_key = channel_to_client_key(&channel);
+        if DEAD_CLIENT_CHANNELS.has(ctx.store, client_key.clone()) {
+            DEAD_CLIENT_CHANNELS.remove(ctx.store, client_key.clone());
+
+            // what timed out first, in its original order, then what came after
+            let timed_out = CLIENT_TIMED_OUT
+                .prefix(client_key.clone())
+                .range(ctx.store, None, None, Order::Ascending)
+                .collect::<Result<Vec<_>, _>>()?;
+            let queued = CLIENT_PACKET_QUEUE
+                .prefix(client_key.clone())
+                .range(ctx.store, None, None, Order::Ascending)
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let mut msgs = Vec::new();
+            for (sequence, packet) in timed_out {
+                CLIENT_TIMED_OUT.remove(ctx.store, (client_key.clone(), sequence));
+                msgs.extend(packet);
+            }
+            for (index, msg) in queued {
+                CLIENT_PACKET_QUEUE.remove(ctx.store, (client_key.clone(), index));
+                msgs.push(msg);
+            }
+
+            for msg in IbcExecuteMsg::batches(msgs) {
+                self.send_to_client(ctx, &channel, &msg)?;
+            }
+        }
 
         Ok(())
     }
@@ -65,10 +204,34 @@ impl State<'_> {
         msg: IbcChannelCloseMsg,
     ) -> Result<()> {
         let channel = msg.channel();
-        CLIENT_CHANNELS.remove(ctx.store, channel_to_key(&channel));
+        if CLIENT_CHANNELS.has(ctx.store, channel_to_key(channel)) {
+            self.close_client_channel(ctx, channel_to_key(channel))?;
+        }
+
+        Ok(())
+    }
+
+    fn close_client_channel(&self, ctx: &mut StateContext, key: IbcChannelKey) -> Result<()> {
+        let channel = CLIENT_CHANNELS.load(ctx.store, key.clone())?;
+        CLIENT_CHANNELS.remove(ctx.store, key.clone());
+
+        // before it's a dead channel, it doesn't need to hear about itself when it's back
+        if let Some(network_id) = CLIENT_NETWORKS.may_load(ctx.store, key.clone())? {
+            CLIENT_NETWORKS.remove(ctx.store, key);
+            if self.get_network_notices(ctx.store)? {
+                let notice = self.network_notice(ctx, network_id, format!("{} left the chat", network_id))?;
+                self.broadcast_to_clients(ctx, vec![notice])?;
+            }
+        }
+
+        // with ordered channels we expect the client to come back on a new channel, so start queueing
+        if channel.order == IbcOrder::Ordered {
+            DEAD_CLIENT_CHANNELS.save(ctx.store, channel_to_client_key(&channel), &channel)?;
+        }
 
         ctx.response
-            .add_event(IbcChannelCloseEvent { channel });
+            .add_event(IbcChannelCloseEvent { channel: &channel });
+
         Ok(())
     }
 
@@ -76,56 +239,342 @@ impl State<'_> {
         &self,
         ctx: &mut StateContext,
         recv_msg: IbcPacketReceiveMsg,
-    ) -> Result<()> {
-        from_json(&recv_msg.packet.data)
-            .map_err(|err| err.into())
-            .and_then(|msg| {
-                match msg {
-                    IbcExecuteMsg::SendMessageToServer{ message } => {
-                        let mut response_messages = Vec::new();
-                        for item in CLIENT_CHANNELS.range(ctx.store, None, None, Order::Ascending) { 
-                            let (_, channel) = item?;
-                            if channel.counterparty_endpoint != recv_msg.packet.src {
-
-                                response_messages.push(IbcMsg::SendPacket {
-                                    channel_id: channel.endpoint.channel_id,
-                                    data: to_json_binary(&IbcExecuteMsg::SendMessageToClient { message: message.msg.clone() })?,
-                                    timeout: IbcTimeout::with_timestamp(self.env.block.time.plus_seconds(TIMEOUT_SECONDS)),
-                                });
-                            }
-                        }
+    ) -> Result<IbcAckSuccess> {
+        let key = endpoint_to_key(&recv_msg.packet.dest);
+        let version = CLIENT_CHANNELS
+            .may_load(ctx.store, key.clone())?
+            .ok_or_else(|| IbcAckError::new(IbcAckErrorCode::UnknownChannel, format!("no client channel for {}", key)))
+            .map_err(anyhow::Error::from)
+            .and_then(|channel| channel_protocol_version(&channel))?;
 
-                        ctx.response.add_event(ChatMessageEvent {
-                            message
-                        });
+        let msg = IbcExecuteMsg::decode(version, &recv_msg.packet.data)?;
+        let mut fan_out = FanOut::default();
 
-                        for response_message in response_messages {
-                            ctx.response.add_message(response_message);
+        let success = match msg {
+            IbcExecuteMsg::Batch { msgs } => {
+                let results = msgs
+                    .into_iter()
+                    // each item on its own, a failed one leaves nothing behind, not even outgoing messages
+                    .map(|msg| {
+                        let mut item_fan_out = FanOut::default();
+                        match ctx.transactional(|ctx| self.handle_ibc_execute_msg(ctx, &recv_msg.packet.src, msg, &mut item_fan_out)) {
+                            Ok(success) => {
+                                fan_out.append(item_fan_out);
+                                IbcAck::Success(success)
+                            },
+                            Err(err) => IbcAck::from(err),
                         }
-                        Ok(())
-                    },
-                    _ => {
-                        anyhow::bail!("unsupported message type")
-                    }
+                    })
+                    .collect();
+
+                IbcAckSuccess::Batch { results }
+            },
+            msg => self.handle_ibc_execute_msg(ctx, &recv_msg.packet.src, msg, &mut fan_out)?,
+        };
+
+        // polls close with whatever traffic comes first after their time is up
+        for tally in self.close_expired_polls(ctx)? {
+            self.add_to_fan_out(ctx.store, &mut fan_out, &IbcExecuteMsg::PollClosed { tally }, None)?;
+        }
+
+        self.send_fan_out(ctx, fan_out)?;
+
+        Ok(success)
+    }
+
+    // handles a single (non-batch) message from a client
+    // outgoing messages are only collected here, and sent once the whole packet is handled
+    fn handle_ibc_execute_msg(
+        &self,
+        ctx: &mut StateContext,
+        src: &IbcEndpoint,
+        msg: IbcExecuteMsg,
+        fan_out: &mut FanOut,
+    ) -> Result<IbcAckSuccess> {
+        match msg {
+            IbcExecuteMsg::SendMessageToServer{ message } => {
+                self.check_relayed_message(&message.msg)?;
+
+                // the sending client isn't taken at its word about where it's from either, the other clients let gated rooms trust an attestation from the home network
+                self.ensure_client_network(ctx.store, src, message.msg.network_id)?;
+                if message.msg.attestation.is_some() && message.msg.room.as_deref().and_then(Room::home_of) != Some(message.msg.network_id) {
+                    anyhow::bail!(IbcAckError::new(IbcAckErrorCode::Rejected, "attestations are only given on the room's home chain"));
+                }
+
+                let id = self.next_chat_message_id(ctx)?;
+                let outgoing = IbcExecuteMsg::SendMessageToClient { message: message.msg.clone(), id: Some(id) };
+                let recipients = self.add_to_fan_out(ctx.store, fan_out, &outgoing, Some(src))?;
+
+                ctx.response.add_event(ChatMessageEvent {
+                    message
+                });
+
+                Ok(IbcAckSuccess::MessageBroadcast { id, recipients })
+            },
+            IbcExecuteMsg::ClientConnected { network_id } => {
+                let channel = self.get_client_channels(ctx.store)?
+                    .into_iter()
+                    .find(|channel| channel.counterparty_endpoint == *src)
+                    .context("no channel for the client")?;
+                self.check_client_network(ctx.store, &channel, network_id)?;
+                CLIENT_NETWORKS.save(ctx.store, channel_to_key(&channel), &network_id)?;
+
+                if !self.get_network_notices(ctx.store)? {
+                    return Ok(IbcAckSuccess::ClientRegistered { recipients: 0 });
                 }
+
+                let notice = self.network_notice(ctx, network_id, format!("{} joined the chat", network_id))?;
+                let recipients = self.add_to_fan_out(ctx.store, fan_out, &notice, Some(src))?;
+
+                Ok(IbcAckSuccess::ClientRegistered { recipients })
+            },
+            IbcExecuteMsg::CreatePoll { question, options, closes_at, creator } => {
+                self.ensure_client_network(ctx.store, src, creator.network_id)?;
+                let poll = self.create_poll(ctx, question, options, closes_at, creator)?;
+                let id = poll.id;
+
+                // the creator hears about it like everyone else, that's where it learns the id
+                self.add_to_fan_out(ctx.store, fan_out, &IbcExecuteMsg::PollCreated { poll }, None)?;
+
+                Ok(IbcAckSuccess::PollCreated { id })
+            },
+            IbcExecuteMsg::Vote { poll_id, option, voter } => {
+                // one vote per user, so a client only votes for users on its own chain
+                self.ensure_client_network(ctx.store, src, voter.network_id)?;
+                self.vote(ctx, poll_id, option, &voter)?;
+
+                Ok(IbcAckSuccess::VoteCounted { })
+            },
+            msg @ (IbcExecuteMsg::RoomCreated { .. } | IbcExecuteMsg::RoomUpdated { .. }) => {
+                // the rule is checked on the room's home chain, the others only need to know it
+                // so it has to come from there, or anyone could take a room's id before its home chain does
+                let (IbcExecuteMsg::RoomCreated { room } | IbcExecuteMsg::RoomUpdated { room }) = &msg else { unreachable!() };
+                if Room::home_of(&room.id) != Some(room.home) {
+                    anyhow::bail!(IbcAckError::new(IbcAckErrorCode::Rejected, format!("{} isn't a room of {}", room.id, room.home)));
+                }
+                self.ensure_client_network(ctx.store, src, room.home)?;
+
+                let recipients = self.add_to_fan_out(ctx.store, fan_out, &msg, Some(src))?;
+
+                Ok(IbcAckSuccess::RoomBroadcast { recipients })
+            },
+            IbcExecuteMsg::UpdatePin { pin, pinned } => {
+                // the owner was checked on the room's home chain, so it has to come from there
+                // the main chat is only pinned by the admin, here
+                // the client's network was checked against the connection the owner set up for it, see `check_client_network`
+                let home = pin.room.as_deref().and_then(Room::home_of);
+                if home.is_none() || home != self.get_client_network(ctx.store, src)? {
+                    anyhow::bail!(IbcAckError::new(IbcAckErrorCode::Rejected, "pins are only changed on the room's home chain"));
+                }
+
+                // the source stores it when it comes back, like everyone else
+                let recipients = self.add_to_fan_out(ctx.store, fan_out, &IbcExecuteMsg::UpdatePin { pin: pin.clone(), pinned }, None)?;
+                ctx.response.add_event(PinUpdatedEvent { pin: &pin, pinned });
+
+                Ok(IbcAckSuccess::PinBroadcast { recipients })
+            },
+            IbcExecuteMsg::LinkIdentity { signature } => {
+                // only a registered client, and the key's signature rather than the client's word
+                if self.get_client_network(ctx.store, src)?.is_none() {
+                    anyhow::bail!(IbcAckError::new(IbcAckErrorCode::Rejected, "links only come from a registered client"));
+                }
+                let member = signature.verify(self.api)
+                    .map_err(|err| IbcAckError::new(IbcAckErrorCode::Rejected, err.to_string()))?;
+
+                let link = self.link_identity(ctx, IdentityLink { member, identity: signature.link_identity })?;
+
+                // every client stores it the same way, the source included
+                self.add_to_fan_out(ctx.store, fan_out, &IbcExecuteMsg::IdentityLinked { link }, None)?;
+
+                Ok(IbcAckSuccess::IdentityLinked { })
+            },
+            IbcExecuteMsg::Batch { .. } => {
+                anyhow::bail!(IbcAckError::new(IbcAckErrorCode::UnsupportedMessage, "batches can't be nested"))
+            },
+            _ => {
+                anyhow::bail!(IbcAckError::new(IbcAckErrorCode::UnsupportedMessage, "unsupported message type"))
+            }
+        }
+    }
+
+    pub fn get_client_connections(&self, store: &dyn Storage) -> Result<Vec<ClientConnection>> {
+        CLIENT_CONNECTIONS.range(store, None, None, Order::Ascending)
+            .map(|x| {
+                let (connection_id, network_id) = x?;
+                Ok(ClientConnection { connection_id, network_id })
             })
+            .collect()
+    }
+
+    pub fn set_client_connection(&self, ctx: &mut StateContext, connection_id: &str, network_id: Option<NetworkId>) -> Result<()> {
+        match network_id {
+            Some(network_id) => CLIENT_CONNECTIONS.save(ctx.store, connection_id, &network_id)?,
+            None => CLIENT_CONNECTIONS.remove(ctx.store, connection_id),
+        }
+
+        Ok(())
+    }
+
+    // a client says which chain it's on once per channel, and only over a connection the owner set up for that chain
+    // one client per network, so nothing else on the same chain can take its place while it's connected
+    fn check_client_network(&self, store: &dyn Storage, channel: &IbcChannel, network_id: NetworkId) -> Result<()> {
+        if let Some(existing) = CLIENT_NETWORKS.may_load(store, channel_to_key(channel))? {
+            anyhow::bail!(IbcAckError::new(IbcAckErrorCode::Rejected, format!("the client already said it's on {}", existing)));
+        }
+
+        match CLIENT_CONNECTIONS.may_load(store, &channel.connection_id)? {
+            Some(expected) if expected == network_id => {},
+            Some(expected) => anyhow::bail!(IbcAckError::new(IbcAckErrorCode::Rejected, format!("{} is for {}, not {}", channel.connection_id, expected, network_id))),
+            None => anyhow::bail!(IbcAckError::new(IbcAckErrorCode::Rejected, format!("{} isn't set up for a client", channel.connection_id))),
+        }
+
+        let taken = CLIENT_NETWORKS.range(store, None, None, Order::Ascending)
+            .any(|x| matches!(x, Ok((_, other)) if other == network_id));
+        if taken {
+            anyhow::bail!(IbcAckError::new(IbcAckErrorCode::Rejected, format!("{} is already connected", network_id)));
+        }
+
+        Ok(())
+    }
+
+    // the chain on the other end of the client's channel, once it said so
+    fn get_client_network(&self, store: &dyn Storage, src: &IbcEndpoint) -> Result<Option<NetworkId>> {
+        let Some(channel) = self.get_client_channels(store)?
+            .into_iter()
+            .find(|channel| channel.counterparty_endpoint == *src) else {
+            return Ok(None);
+        };
+
+        CLIENT_NETWORKS.may_load(store, channel_to_key(&channel)).map_err(|err| err.into())
+    }
+
+    // a client only speaks for users on its own chain
+    fn ensure_client_network(&self, store: &dyn Storage, src: &IbcEndpoint, network_id: NetworkId) -> Result<()> {
+        if self.get_client_network(store, src)? != Some(network_id) {
+            anyhow::bail!(IbcAckError::new(IbcAckErrorCode::Rejected, format!("the client isn't on {}", network_id)));
+        }
+
+        Ok(())
+    }
+
+    // a chain joined or left, it's in the server's history too
+    fn network_notice(&self, ctx: &mut StateContext, network_id: NetworkId, text: String) -> Result<IbcExecuteMsg> {
+        let message = ChatMessage {
+            user: self.env.contract.address.clone(),
+            network_id,
+            message: text,
+            kind: ChatMessageKind::Network,
+            content: MessageContent::Notice,
+            room: None,
+            attestation: None,
+            expires: Expiration::Never {},
+        };
+
+        let id = self.next_chat_message_id(ctx)?;
+        ctx.response.add_event(ChatMessageEvent {
+            message: ChatMessageWithIndex { msg: message.clone(), index: id },
+        });
+
+        Ok(IbcExecuteMsg::SendMessageToClient { message, id: Some(id) })
     }
 
-    pub fn handle_ibc_packet_ack(&self, _ack: IbcPacketAckMsg) -> Result<()> {
-        // Nothing to do here. We don't keep any state about the other
-        // chain, just deliver messages so nothing to update.
-        //
-        // If we did care about how the other chain received our message
-        // we could deserialize the data field into an `Ack` and inspect
-        // it.
+    /// Sends the messages to every client, e.g. polls closed outside of any packet
+    pub fn broadcast_to_clients(&self, ctx: &mut StateContext, msgs: Vec<IbcExecuteMsg>) -> Result<()> {
+        let mut fan_out = FanOut::default();
+        for msg in msgs {
+            self.add_to_fan_out(ctx.store, &mut fan_out, &msg, None)?;
+        }
+
+        self.send_fan_out(ctx, fan_out)
+    }
+
+    // queues the message for every client, except the one at `except` if given
+    // returns how many clients will get it
+    fn add_to_fan_out(&self, store: &dyn Storage, fan_out: &mut FanOut, msg: &IbcExecuteMsg, except: Option<&IbcEndpoint>) -> Result<u32> {
+        let live_channels = self.get_client_channels(store)?
+            .into_iter()
+            .filter(|channel| except != Some(&channel.counterparty_endpoint))
+            .collect::<Vec<_>>();
+        let dead_channels = self.get_dead_client_channels(store)?;
+
+        let recipients = (live_channels.len() + dead_channels.len()) as u32;
+
+        for channel in live_channels {
+            fan_out.live
+                .entry(channel_to_key(&channel))
+                .or_insert_with(|| (channel, Vec::new()))
+                .1
+                .push(msg.clone());
+        }
+
+        for channel in dead_channels {
+            fan_out.dead
+                .entry(channel_to_client_key(&channel))
+                .or_default()
+                .push(msg.clone());
+        }
+
+        Ok(recipients)
+    }
+
+    // one packet per client, no matter how many messages are going its way
+    fn send_fan_out(&self, ctx: &mut StateContext, fan_out: FanOut) -> Result<()> {
+        for (_, (channel, msgs)) in fan_out.live {
+            for msg in IbcExecuteMsg::batches(msgs) {
+                self.send_to_client(ctx, &channel, &msg)?;
+            }
+        }
+
+        for (client_key, msgs) in fan_out.dead {
+            for msg in IbcExecuteMsg::batches(msgs) {
+                self.queue_for_client(ctx, &client_key, &msg)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn handle_ibc_packet_ack(&self, ctx: &mut StateContext, ack: IbcPacketAckMsg) -> Result<()> {
+        // We don't keep any state about the clients, the outcome
+        // of the fan-out is only reported as an event
+        let decoded = IbcAck::decode_or_error(&ack.acknowledgement.data);
+        if let IbcAck::Error(IbcAckError { code: IbcAckErrorCode::DecodeFailed, message }) = &decoded {
+            self.api.debug(message);
+        }
+
+        ctx.response.add_event(IbcPacketAckEvent {
+            packet: &ack.original_packet,
+            ack: &decoded,
+        });
+
         Ok(())
     }
 
-    pub fn handle_ibc_packet_timeout(&self, _msg: IbcPacketTimeoutMsg) -> Result<()> {
-        // As with ack above, nothing to do here. If we cared about
-        // keeping track of state between the two chains then we'd want to
-        // respond to this likely as it means that the packet in question
-        // isn't going anywhere.
+    pub fn handle_ibc_packet_timeout(&self, ctx: &mut StateContext, msg: IbcPacketTimeoutMsg) -> Result<()> {
+        let packet = msg.packet;
+        let key = endpoint_to_key(&packet.src);
+
+        // On unordered channels a timeout only loses that one packet, nothing to do here.
+        // On ordered channels a timeout closes the channel, so we mark it as dead
+        if let Some(channel) = CLIENT_CHANNELS.may_load(ctx.store, key.clone())? {
+            if channel.order == IbcOrder::Ordered {
+                self.close_client_channel(ctx, key)?;
+            }
+        }
+
+        // Every packet that was in flight on the dead channel times out too
+        // keep them by sequence, so they're sent again in their original order when the client reconnects
+        // the timeouts themselves may come in any order
+        let dead_channel = self.get_dead_client_channels(ctx.store)?
+            .into_iter()
+            .find(|channel| channel.endpoint == packet.src);
+
+        if let Some(channel) = dead_channel {
+            // a batch is split up, it's batched again with whatever else is resent
+            let msgs = IbcExecuteMsg::decode(channel_protocol_version(&channel)?, &packet.data)?.into_items();
+            CLIENT_TIMED_OUT.save(ctx.store, (channel_to_client_key(&channel), packet.sequence), &msgs)?;
+        }
+
         Ok(())
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,53 @@
+use cosmwasm_std::{Empty, Order, Storage};
+use cw_storage_plus::Map;
+use shared::{
+    ibc::{IbcAckError, IbcAckErrorCode},
+    msg::identity::{event::IdentityLinkedEvent, IdentityId, IdentityLink},
+};
+use anyhow::Result;
+
+use super::{State, StateContext};
+
+// member -> the identity it joined
+const IDENTITY_LINKS: Map<&str, IdentityId> = Map::new("identity-links");
+// (identity, member), to list the members
+const IDENTITY_MEMBERS: Map<(&str, &str), Empty> = Map::new("identity-members");
+
+impl State<'_> {
+    /// The identity a key belongs to, itself if it never joined one
+    pub fn resolve_identity(&self, store: &dyn Storage, id: &str) -> Result<IdentityId> {
+        Ok(IDENTITY_LINKS.may_load(store, id)?.unwrap_or_else(|| id.to_string()))
+    }
+
+    pub fn get_identity_members(&self, store: &dyn Storage, identity: &str) -> Result<Vec<IdentityId>> {
+        IDENTITY_MEMBERS.prefix(identity)
+            .keys(store, None, None, Order::Ascending)
+            .map(|x| x.map_err(|err| err.into()))
+            .collect()
+    }
+
+    /// Stores the link, with the identity resolved in case the client didn't know it had joined another
+    /// returns the link as stored, for the clients
+    pub fn link_identity(&self, ctx: &mut StateContext, link: IdentityLink) -> Result<IdentityLink> {
+        let identity = self.resolve_identity(ctx.store, &link.identity)?;
+
+        let rejected = |msg: String| IbcAckError::new(IbcAckErrorCode::Rejected, msg);
+        if link.member == identity {
+            anyhow::bail!(rejected(format!("{} is already part of {}", link.member, identity)));
+        }
+        if let Some(existing) = IDENTITY_LINKS.may_load(ctx.store, &link.member)? {
+            anyhow::bail!(rejected(format!("{} already joined {}", link.member, existing)));
+        }
+        if !self.get_identity_members(ctx.store, &link.member)?.is_empty() {
+            anyhow::bail!(rejected(format!("{} has members of its own, link them the other way", link.member)));
+        }
+
+        let link = IdentityLink { member: link.member, identity };
+        IDENTITY_LINKS.save(ctx.store, &link.member, &link.identity)?;
+        IDENTITY_MEMBERS.save(ctx.store, (&link.identity, &link.member), &Empty {})?;
+
+        ctx.response.add_event(IdentityLinkedEvent { link: &link });
+
+        Ok(link)
+    }
+}
@@ -0,0 +1,75 @@
+use cosmwasm_std::{Addr, CanonicalAddr, Order, Storage};
+use cw_storage_plus::Map;
+use cw_utils::Expiration;
+use shared::msg::{
+    chat_message::{event::ChatMessageEvent, ChatMessage, ChatMessageKind, ChatMessageWithIndex},
+    content::MessageContent,
+    contract::server::MemoChannel,
+    ibc::IbcExecuteMsg,
+    memo::{hook_intermediary_sender, TransferChatPost},
+    network::NetworkId,
+};
+use anyhow::{bail, Result};
+
+use super::{State, StateContext};
+
+// our end of a transfer channel -> the network on the other end
+const MEMO_CHANNELS: Map<&str, NetworkId> = Map::new("memo-channels");
+
+impl State<'_> {
+    pub fn get_memo_channels(&self, store: &dyn Storage) -> Result<Vec<MemoChannel>> {
+        MEMO_CHANNELS.range(store, None, None, Order::Ascending)
+            .map(|x| {
+                let (channel_id, network_id) = x?;
+                Ok(MemoChannel { channel_id, network_id })
+            })
+            .collect()
+    }
+
+    pub fn set_memo_channel(&self, ctx: &mut StateContext, channel_id: &str, network_id: Option<NetworkId>) -> Result<()> {
+        match network_id {
+            Some(network_id) => MEMO_CHANNELS.save(ctx.store, channel_id, &network_id)?,
+            None => MEMO_CHANNELS.remove(ctx.store, channel_id),
+        }
+
+        Ok(())
+    }
+
+    /// Broadcasts a message posted with a transfer memo, as written by the sender on the source chain
+    /// the funds of the transfer stay with the server
+    pub fn post_from_transfer(&self, ctx: &mut StateContext, sender: &Addr, post: TransferChatPost) -> Result<()> {
+        let intermediary = self.api.addr_humanize(&CanonicalAddr::from(hook_intermediary_sender(&post.channel, &post.sender)))?;
+        if *sender != intermediary {
+            bail!("only the ibc-hooks sender for {} on {} can post as them", post.sender, post.channel);
+        }
+
+        let network_id = match MEMO_CHANNELS.may_load(ctx.store, &post.channel)? {
+            Some(network_id) => network_id,
+            None => bail!("chat messages can't be posted over {}", post.channel),
+        };
+
+        if post.message.trim().is_empty() {
+            bail!("empty message");
+        }
+
+        let message = ChatMessage {
+            user: Addr::unchecked(post.sender),
+            network_id,
+            message: post.message,
+            kind: ChatMessageKind::Text,
+            content: MessageContent::Text,
+            room: None,
+            attestation: None,
+            expires: Expiration::Never {},
+        };
+        self.check_relayed_message(&message)?;
+
+        // no client stored it first, so the id stands in for the index
+        let id = self.next_chat_message_id(ctx)?;
+        ctx.response.add_event(ChatMessageEvent {
+            message: ChatMessageWithIndex { msg: message.clone(), index: id },
+        });
+
+        self.broadcast_to_clients(ctx, vec![IbcExecuteMsg::SendMessageToClient { message, id: Some(id) }])
+    }
+}
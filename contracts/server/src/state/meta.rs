@@ -0,0 +1,54 @@
+use cosmwasm_std::{Addr, Storage};
+use cw_storage_plus::Item;
+use shared::msg::{misc::ChannelOrder, network::NetworkId};
+
+use super::{State, StateContext};
+use anyhow::Result;
+
+const CHANNEL_ORDER:Item<ChannelOrder> = Item::new("channel-order");
+const OWNER:Item<Addr> = Item::new("owner");
+const NETWORK_NOTICES:Item<bool> = Item::new("network-notices");
+const NETWORK_ID:Item<NetworkId> = Item::new("network-id");
+
+impl State<'_> {
+    pub fn get_channel_order(&self, store: &dyn Storage) -> Result<ChannelOrder> {
+        CHANNEL_ORDER.load(store).map_err(|err| err.into())
+    }
+
+    pub fn set_channel_order(&self, ctx: &mut StateContext, channel_order: ChannelOrder) -> Result<()> {
+        CHANNEL_ORDER.save(ctx.store, &channel_order).map_err(|err| err.into())
+    }
+
+    pub fn get_network_notices(&self, store: &dyn Storage) -> Result<bool> {
+        Ok(NETWORK_NOTICES.may_load(store)?.unwrap_or_default())
+    }
+
+    pub fn set_network_notices(&self, ctx: &mut StateContext, enabled: bool) -> Result<()> {
+        NETWORK_NOTICES.save(ctx.store, &enabled).map_err(|err| err.into())
+    }
+
+    /// Servers from before it was set are on Neutron
+    pub fn get_network_id(&self, store: &dyn Storage) -> Result<NetworkId> {
+        Ok(NETWORK_ID.may_load(store)?.unwrap_or(NetworkId::Neutron))
+    }
+
+    pub fn set_network_id(&self, ctx: &mut StateContext, network_id: NetworkId) -> Result<()> {
+        NETWORK_ID.save(ctx.store, &network_id).map_err(|err| err.into())
+    }
+
+    pub fn get_owner(&self, store: &dyn Storage) -> Result<Addr> {
+        OWNER.load(store).map_err(|err| err.into())
+    }
+
+    pub fn set_owner(&self, ctx: &mut StateContext, owner: &Addr) -> Result<()> {
+        OWNER.save(ctx.store, owner).map_err(|err| err.into())
+    }
+
+    pub fn ensure_owner(&self, store: &dyn Storage, sender: &Addr) -> Result<()> {
+        if *sender != self.get_owner(store)? {
+            anyhow::bail!("{} is not the contract owner", sender);
+        }
+
+        Ok(())
+    }
+}
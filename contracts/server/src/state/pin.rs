@@ -0,0 +1,53 @@
+use cosmwasm_std::Addr;
+use cw_utils::Expiration;
+use shared::msg::{
+    chat_message::{event::ChatMessageEvent, ChatMessage, ChatMessageKind, ChatMessageWithIndex},
+    content::MessageContent,
+    ibc::IbcExecuteMsg,
+    pin::{event::PinUpdatedEvent, Pin},
+};
+use anyhow::{bail, Result};
+
+use super::{State, StateContext};
+
+impl State<'_> {
+    /// Sends an announcement to every client, pinned in the main chat
+    pub fn announce(&self, ctx: &mut StateContext, sender: &Addr, text: String, content: MessageContent) -> Result<()> {
+        if text.is_empty() {
+            bail!("empty message");
+        }
+
+        let message = ChatMessage {
+            user: self.env.contract.address.clone(),
+            network_id: self.get_network_id(ctx.store)?,
+            message: text,
+            kind: ChatMessageKind::Announcement,
+            content,
+            room: None,
+            attestation: None,
+            expires: Expiration::Never {},
+        };
+        message.content.validate(message.kind, &message.message)?;
+
+        let id = self.next_chat_message_id(ctx)?;
+        ctx.response.add_event(ChatMessageEvent {
+            message: ChatMessageWithIndex { msg: message.clone(), index: id },
+        });
+
+        let pin = Pin { room: None, id, by: sender.clone() };
+        ctx.response.add_event(PinUpdatedEvent { pin: &pin, pinned: true });
+
+        // in this order, so each client has the message when the pin arrives
+        self.broadcast_to_clients(ctx, vec![
+            IbcExecuteMsg::SendMessageToClient { message, id: Some(id) },
+            IbcExecuteMsg::UpdatePin { pin, pinned: true },
+        ])
+    }
+
+    /// The admin can pin in any room, the clients check nothing more than the message's room
+    pub fn broadcast_pin(&self, ctx: &mut StateContext, pin: Pin, pinned: bool) -> Result<()> {
+        ctx.response.add_event(PinUpdatedEvent { pin: &pin, pinned });
+
+        self.broadcast_to_clients(ctx, vec![IbcExecuteMsg::UpdatePin { pin, pinned }])
+    }
+}
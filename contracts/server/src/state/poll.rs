@@ -0,0 +1,105 @@
+use cosmwasm_std::{Order, Storage, Timestamp};
+use cw_storage_plus::{Item, Map};
+use shared::{
+    ibc::{IbcAckError, IbcAckErrorCode},
+    msg::poll::{event::{PollClosedEvent, PollCreatedEvent}, validate_poll, Poll, PollId, PollTally, Voter},
+};
+use anyhow::{bail, Result};
+
+use super::{State, StateContext};
+
+const LAST_POLL_ID: Item<u64> = Item::new("last-poll-id");
+const POLLS: Map<u64, PollTally> = Map::new("polls");
+// ids of the polls that still take votes, so closing doesn't have to scan every poll ever made
+const OPEN_POLLS: Map<u64, Timestamp> = Map::new("open-polls");
+// keyed by poll id and voter
+const VOTES: Map<(u64, String), u32> = Map::new("votes");
+
+impl State<'_> {
+    pub fn get_poll(&self, store: &dyn Storage, poll_id: PollId) -> Result<PollTally> {
+        match POLLS.may_load(store, poll_id.u64())? {
+            Some(tally) => Ok(tally),
+            None => bail!(IbcAckError::new(IbcAckErrorCode::Rejected, format!("no such poll {}", poll_id))),
+        }
+    }
+
+    pub fn get_polls(&self, store: &dyn Storage) -> Result<Vec<PollTally>> {
+        POLLS.range(store, None, None, Order::Ascending)
+            .map(|x| x
+                .map(|(_, tally)| tally)
+                .map_err(|err| err.into())
+            )
+            .collect()
+    }
+
+    pub fn create_poll(&self, ctx: &mut StateContext, question: String, options: Vec<String>, closes_at: Timestamp, creator: Voter) -> Result<Poll> {
+        validate_poll(&question, &options, closes_at, self.env.block.time)
+            .map_err(|err| IbcAckError::new(IbcAckErrorCode::Rejected, err.to_string()))?;
+
+        let id = LAST_POLL_ID.may_load(ctx.store)?.unwrap_or_default() + 1;
+        LAST_POLL_ID.save(ctx.store, &id)?;
+
+        let poll = Poll {
+            id: id.into(),
+            question,
+            options,
+            closes_at,
+            creator,
+        };
+
+        POLLS.save(ctx.store, id, &PollTally::new(poll.clone()))?;
+        OPEN_POLLS.save(ctx.store, id, &closes_at)?;
+
+        ctx.response.add_event(PollCreatedEvent { poll: &poll });
+
+        Ok(poll)
+    }
+
+    pub fn vote(&self, ctx: &mut StateContext, poll_id: PollId, option: u32, voter: &Voter) -> Result<()> {
+        let mut tally = self.get_poll(ctx.store, poll_id)?;
+
+        // the tally may not be marked closed yet, the clock is what counts
+        if tally.closed || self.env.block.time >= tally.poll.closes_at {
+            bail!(IbcAckError::new(IbcAckErrorCode::Rejected, format!("poll {} is closed", poll_id)));
+        }
+
+        let votes = tally.votes
+            .get_mut(option as usize)
+            .ok_or_else(|| IbcAckError::new(IbcAckErrorCode::Rejected, format!("poll {} has no option {}", poll_id, option)))?;
+
+        let key = (poll_id.u64(), voter.to_string());
+        if VOTES.has(ctx.store, key.clone()) {
+            bail!(IbcAckError::new(IbcAckErrorCode::Rejected, format!("{} already voted in poll {}", voter, poll_id)));
+        }
+
+        *votes += 1;
+        VOTES.save(ctx.store, key, &option)?;
+        POLLS.save(ctx.store, poll_id.u64(), &tally)?;
+
+        Ok(())
+    }
+
+    /// Closes every poll whose time is up, and returns their final tallies
+    pub fn close_expired_polls(&self, ctx: &mut StateContext) -> Result<Vec<PollTally>> {
+        let now = self.env.block.time;
+        let expired = OPEN_POLLS
+            .range(ctx.store, None, None, Order::Ascending)
+            .filter(|x| x.as_ref().map_or(true, |(_, closes_at)| *closes_at <= now))
+            .map(|x| x.map(|(id, _)| id))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut closed = Vec::with_capacity(expired.len());
+        for id in expired {
+            OPEN_POLLS.remove(ctx.store, id);
+
+            let mut tally = POLLS.load(ctx.store, id)?;
+            tally.closed = true;
+            POLLS.save(ctx.store, id, &tally)?;
+
+            ctx.response.add_event(PollClosedEvent { tally: &tally });
+            closed.push(tally);
+        }
+
+        Ok(closed)
+    }
+}
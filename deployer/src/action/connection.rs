@@ -0,0 +1,23 @@
+use shared::msg::network::NetworkId;
+
+use crate::{args::arg_var, prelude::*};
+
+// the server only takes a client's network from a connection set up for it
+// run once the relayer made the connections, before it opens the channels
+pub async fn set() -> Result<()> {
+    let connection_id = arg_var("connection").context("set --connection to the server chain's end of the connection")?;
+    let network_id = match arg_var("network") {
+        Some(network) => Some(network.parse::<NetworkId>()?),
+        None => None,
+    };
+
+    let mut server = Wallet::server().into_contract_server();
+    let resp = server.exec_set_client_connection(&connection_id, network_id).await?;
+
+    match network_id {
+        Some(network_id) => log::info!("clients on {} are from {}, tx {}", connection_id, network_id, resp.hash),
+        None => log::info!("{} is no longer set up for a client, tx {}", connection_id, resp.hash),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use cosmwasm_std::{coins, Uint128};
+use shared::msg::network::NetworkId;
+
+use crate::{args::arg_var, prelude::*};
+
+// x/feegrant can only restrict by message type, not by contract
+// so a grantee could spend the allowance on any contract, keep the limits low
+const ALLOWED_MESSAGES: &[&str] = &["/cosmwasm.wasm.v1.MsgExecuteContract"];
+
+// when --spend-limit isn't given, in the chain's gas denom
+const DEFAULT_SPEND_LIMIT: u128 = 1_000_000;
+
+// the CLI wallet is the sponsor, --network picks the client chain
+fn sponsor() -> Result<WalletSigning> {
+    let network_id: NetworkId = arg_var("network").context("set --network to a client chain")?.parse()?;
+
+    Wallet::all_clients()
+        .into_iter()
+        .find(|wallet| wallet.network_id() == network_id)
+        .context(format!("{} has no client contract", network_id))
+}
+
+fn grantee() -> Result<String> {
+    arg_var("grantee").context("set --grantee")
+}
+
+pub async fn list() -> Result<()> {
+    let sponsor = sponsor()?;
+    let grants = sponsor.fee_grants().await?;
+
+    log::info!("{} fee grants from {} on {}", grants.len(), sponsor.address(), sponsor.network_id());
+    for grant in grants {
+        log::info!("{}: {}", grant.grantee, grant.allowance);
+    }
+
+    Ok(())
+}
+
+pub async fn grant() -> Result<()> {
+    let sponsor = sponsor()?;
+    let grantee = grantee()?;
+
+    let spend_limit = match arg_var("spend-limit") {
+        Some(limit) => limit.parse::<Uint128>()?.u128(),
+        None => DEFAULT_SPEND_LIMIT,
+    };
+    let spend_limit = coins(spend_limit, sponsor.denom());
+
+    let expiration_seconds = arg_var("expires-days")
+        .map(|days| days.parse::<u64>())
+        .transpose()?
+        .map(|days| (js_sys::Date::now() / 1000.0) as u64 + days * 24 * 60 * 60);
+
+    let allowed_messages = ALLOWED_MESSAGES.iter().map(|msg| msg.to_string()).collect();
+    let resp = sponsor.fee_grant(&grantee, &spend_limit, expiration_seconds, allowed_messages).await?;
+
+    log::info!("granted {} up to {}{} in fees on {}, tx {}", grantee, spend_limit[0].amount, spend_limit[0].denom, sponsor.network_id(), resp.hash);
+
+    Ok(())
+}
+
+pub async fn revoke() -> Result<()> {
+    let sponsor = sponsor()?;
+    let grantee = grantee()?;
+
+    let resp = sponsor.fee_revoke(&grantee).await?;
+
+    log::info!("revoked the fee grant of {} on {}, tx {}", grantee, sponsor.network_id(), resp.hash);
+
+    Ok(())
+}
@@ -1,4 +1,6 @@
 use cosmwasm_std::{Addr, Empty};
+use awsm_web::env::env_var;
+use shared::msg::{misc::ChannelOrder, network::NetworkId};
 use crate::{bindings::crypto::HashAlgo, config::write_contract_deploy_config, prelude::*};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -23,18 +25,25 @@ pub async fn instantiate(wallet: &WalletSigning, contract_kind: ContractKind, ki
     };
 
     let code_id = config.code_id.expect("code_id not found");
+    let channel_order = ibc_channel_order()?;
 
     let resp = match contract_kind {
         ContractKind::Server => wallet.contract_instantiate(
             contract_kind.to_string(),
             code_id,
-            &Empty{},
+            &ServerInstantiateMsg {
+                channel_order,
+                network_notices: Some(true),
+                network_id: Some(wallet.network_id()),
+            },
         ).await?,
         ContractKind::Client => wallet.contract_instantiate(
             contract_kind.to_string(),
             code_id,
             &ClientInstantiateMsg {
                 network_id: wallet.network_id(),
+                channel_order,
+                nois_proxy: nois_proxy(wallet.network_id()),
             }
         ).await?,
     };
@@ -44,4 +53,17 @@ pub async fn instantiate(wallet: &WalletSigning, contract_kind: ContractKind, ki
     config.address = Some(resp.address);
 
     Ok(Some(config))
-}
\ No newline at end of file
+}
+
+// Channel ordering is a deployment option, it has to match the relayer's --order
+fn ibc_channel_order() -> Result<Option<ChannelOrder>> {
+    env_var("IBC_CHANNEL_ORDER")
+        .ok()
+        .map(|order| order.parse())
+        .transpose()
+}
+
+// The Nois proxy for `/roll`, e.g. NOIS_PROXY_STARGAZE, rolls are disabled without one
+fn nois_proxy(network_id: NetworkId) -> Option<String> {
+    env_var(&format!("NOIS_PROXY_{}", network_id.to_string().to_uppercase())).ok()
+}
@@ -1,3 +1,5 @@
 pub mod deploy;
 pub mod migrate;
+pub mod feegrant;
+pub mod connection;
 pub(super) mod helpers;
\ No newline at end of file
@@ -32,6 +32,18 @@ pub async fn run() -> Result<(), JsValue> {
         Action::Migrate => {
             action::migrate::run().await
         },
+        Action::FeeGrantList => {
+            action::feegrant::list().await
+        },
+        Action::FeeGrant => {
+            action::feegrant::grant().await
+        },
+        Action::FeeRevoke => {
+            action::feegrant::revoke().await
+        },
+        Action::ClientConnection => {
+            action::connection::set().await
+        },
     };
 
     if let Err(err) = res {
@@ -45,6 +57,10 @@ pub async fn run() -> Result<(), JsValue> {
 enum Action {
     Deploy,
     Migrate,
+    FeeGrantList,
+    FeeGrant,
+    FeeRevoke,
+    ClientConnection,
 }
 
 impl Action {
@@ -52,6 +68,10 @@ impl Action {
         match action {
             "deploy" => Some(Self::Deploy),
             "migrate" => Some(Self::Migrate),
+            "feegrant-list" => Some(Self::FeeGrantList),
+            "feegrant-grant" => Some(Self::FeeGrant),
+            "feegrant-revoke" => Some(Self::FeeRevoke),
+            "client-connection" => Some(Self::ClientConnection),
             _ => None 
         }
     }
@@ -5,7 +5,7 @@ pub use awsm_web::prelude::*;
 
 pub use shared::msg::contract::{
     client::{QueryMsg as ClientQueryMsg, ExecuteMsg as ClientExecuteMsg, InstantiateMsg as ClientInstantiateMsg, InfoResp as ClientInfoResp},
-    server::{QueryMsg as ServerQueryMsg, InfoResp as ServerInfoResp},
+    server::{QueryMsg as ServerQueryMsg, InfoResp as ServerInfoResp, InstantiateMsg as ServerInstantiateMsg},
 };
 pub use wallet::prelude::*;
 
@@ -1,5 +1,6 @@
 mod window;
 mod display;
+mod encryption;
 mod input;
 
 use shared::msg::contract::server::InfoResp as ServerInfoResp;
@@ -1,10 +1,25 @@
 
-use shared::msg::chat_message::{ChatMessage, ChatMessageWithIndex};
+use std::collections::HashMap;
+
+use cosmwasm_std::Addr;
+use dominator::DomBuilder;
+use web_sys::HtmlElement;
+use shared::msg::{chat_message::{ChatMessage, ChatMessageKind, ChatMessageWithIndex}, content::MessageContent, contract::client::UserIdentity, encryption::EncryptedMessage, identity::IdentityId, room::RoomId};
+use wallet::encryption::{decrypt_message, RoomKey};
 
 use crate::prelude::*;
 
+// how much of the identity is shown, enough to tell people apart
+const IDENTITY_TAG_LEN: usize = 8;
+// attachments are content-addressed, any gateway serves them
+const IPFS_GATEWAY: &str = "https://ipfs.io/ipfs/";
+
 pub struct ChatDisplay {
     pub messages: MutableVec<ChatMessageWithIndex>,
+    // by author, messages from the same person on different chains get the same tag
+    pub identities: Mutable<HashMap<Addr, IdentityId>>,
+    // unwrapped keys of the private rooms we're in, by epoch
+    pub room_keys: Mutable<HashMap<(RoomId, u32), RoomKey>>,
     pub kind: ContractKind,
 }
 
@@ -13,6 +28,8 @@ impl ChatDisplay {
         Self {
             kind,
             messages: MutableVec::new(),
+            identities: Mutable::new(HashMap::new()),
+            room_keys: Mutable::new(HashMap::new()),
         }
     }
 
@@ -33,14 +50,18 @@ impl ChatDisplay {
         });
 
         let kind = self.kind;
+        let identities = self.identities.clone();
+        let room_keys = self.room_keys.clone();
         html!("div", {
             .class(&*CONTAINER)
             .child(html!("div", {
                 .class(&*CONTENT)
-                .children_signal_vec(self.messages.signal_vec_cloned().map(move |msg| {
+                .children_signal_vec(self.messages.signal_vec_cloned().map(move |mut msg| {
+                    decrypt_for_display(&mut msg.msg, &room_keys.lock_ref());
                     match kind {
                         ContractKind::Client => {
-                            ClientMessage::new(msg).render()
+                            let identity = identities.lock_ref().get(&msg.msg.user).cloned();
+                            ClientMessage::new(msg, identity).render()
                         }
                         ContractKind::Server => {
                             ServerMessage::new(msg).render()
@@ -54,25 +75,37 @@ impl ChatDisplay {
     pub fn add_messages(&self, messages: Vec<ChatMessageWithIndex>) {
         self.messages.lock_mut().extend(messages);
     }
+
+    // before the messages they're for
+    pub fn add_identities(&self, identities: Vec<UserIdentity>) {
+        self.identities.lock_mut().extend(identities.into_iter().map(|x| (x.user, x.identity)));
+    }
 }
 
 struct ClientMessage {
     msg: ChatMessageWithIndex,
+    identity: Option<IdentityId>,
 }
 
 impl ClientMessage {
-    pub fn new(msg: ChatMessageWithIndex) -> Self {
+    pub fn new(msg: ChatMessageWithIndex, identity: Option<IdentityId>) -> Self {
         Self {
-            msg
+            msg,
+            identity,
         }
     }
 
     pub fn render(&self) -> Dom {
-        let ChatMessage {user, message, network_id} = &self.msg.msg;
-
         // TODO - delete / edit ?
+        let text = message_text(&self.msg.msg);
         html!("div", {
-            .text(&format!("({}) {}: {}", network_id, user, message))
+            .text(&match &self.identity {
+                Some(identity) => format!("~{} {}", &identity[..IDENTITY_TAG_LEN.min(identity.len())], text),
+                None => text,
+            })
+            .apply(|dom| with_content(dom, &self.msg.msg.content))
+            .apply_if(is_notice(&self.msg.msg), |dom| dom.class(&*NOTICE))
+            .apply_if(self.msg.msg.kind == ChatMessageKind::Announcement, |dom| dom.class(&*ANNOUNCEMENT))
         })
     }
 }
@@ -89,10 +122,115 @@ impl ServerMessage {
     }
 
     pub fn render(&self) -> Dom {
-        let ChatMessage {user, message, network_id} = &self.msg.msg;
-
         html!("div", {
-            .text(&format!("({}) {}: {}", network_id, user, message))
+            .text(&message_text(&self.msg.msg))
+            .apply(|dom| with_content(dom, &self.msg.msg.content))
+            .apply_if(is_notice(&self.msg.msg), |dom| dom.class(&*NOTICE))
+            .apply_if(self.msg.msg.kind == ChatMessageKind::Announcement, |dom| dom.class(&*ANNOUNCEMENT))
         })
     }
 }
+
+// from the contracts rather than a person, e.g. a chain joining or leaving
+static NOTICE:Lazy<String> = Lazy::new(|| {
+    class! {
+        .style("font-style", "italic")
+        .style("color", "gray")
+    }
+});
+
+// from the server's admin, to every chain
+static ANNOUNCEMENT:Lazy<String> = Lazy::new(|| {
+    class! {
+        .style("font-weight", "bold")
+        .style("color", "darkred")
+    }
+});
+
+fn is_notice(msg: &ChatMessage) -> bool {
+    matches!(msg.kind, ChatMessageKind::System | ChatMessageKind::Network)
+}
+
+// markdown is shown as its source for now, at least the line breaks are kept
+fn with_content(dom: DomBuilder<HtmlElement>, content: &MessageContent) -> DomBuilder<HtmlElement> {
+    match content {
+        MessageContent::Text | MessageContent::Notice => dom,
+        MessageContent::Markdown => dom.style("white-space", "pre-wrap"),
+        _ => dom.child(render_content(content)),
+    }
+}
+
+// whatever the content has besides the text, which message_text already shows
+fn render_content(content: &MessageContent) -> Dom {
+    static CONTENT:Lazy<String> = Lazy::new(|| {
+        class! {
+            .style("margin-left", "1rem")
+            .style("font-size", "1.25rem")
+        }
+    });
+
+    html!("div", {
+        .class(&*CONTENT)
+        .apply(|dom| match content {
+            MessageContent::Text | MessageContent::Notice | MessageContent::Markdown => dom,
+            MessageContent::Link { url, preview } => {
+                let dom = dom.child(external_link(url, preview.as_ref().map_or(url, |preview| &preview.title)));
+                match preview.as_ref().and_then(|preview| preview.description.as_ref()) {
+                    Some(description) => dom.child(html!("div", { .text(description) })),
+                    None => dom,
+                }
+            },
+            MessageContent::Attachment { cid, mime, size } => {
+                dom.child(external_link(&format!("{}{}", IPFS_GATEWAY, cid), &format!("{} ({})", mime, format_size(*size))))
+            },
+            MessageContent::Token { denom, amount } => dom.text(&match amount {
+                Some(amount) => format!("{} {}", amount, denom),
+                None => denom.clone(),
+            }),
+        })
+    })
+}
+
+fn external_link(href: &str, text: &str) -> Dom {
+    html!("a", {
+        .attr("href", href)
+        .attr("target", "_blank")
+        .attr("rel", "noopener noreferrer")
+        .text(text)
+    })
+}
+
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..=1023 => format!("{} B", bytes),
+        1024..=1_048_575 => format!("{:.1} KiB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MiB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+// private room messages are shown decrypted when we have the key, as a placeholder otherwise
+fn decrypt_for_display(msg: &mut ChatMessage, room_keys: &HashMap<(RoomId, u32), RoomKey>) {
+    let Some(room) = &msg.room else { return };
+    let Ok(encrypted) = EncryptedMessage::from_message_text(&msg.message) else { return };
+
+    msg.message = room_keys.get(&(room.clone(), encrypted.epoch))
+        .and_then(|key| decrypt_message(key, &encrypted).ok())
+        .unwrap_or_else(|| "(encrypted)".to_string());
+}
+
+fn message_text(msg: &ChatMessage) -> String {
+    let ChatMessage {user, message, network_id, kind, room, ..} = msg;
+
+    let text = match kind {
+        ChatMessageKind::Text => format!("({}) {}: {}", network_id, user, message),
+        ChatMessageKind::Action => format!("({}) * {} {}", network_id, user, message),
+        ChatMessageKind::System => format!("-- {}", message),
+        ChatMessageKind::Network => format!("** {}", message),
+        ChatMessageKind::Announcement => format!("!! {}", message),
+    };
+
+    match room {
+        Some(room) => format!("[{}] {}", room, text),
+        None => text,
+    }
+}
@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use shared::msg::{contract::client::RoomKeysResp, network::NetworkId, room::RoomId};
+use wallet::encryption::{unwrap_room_key, RoomKey};
+
+use crate::prelude::*;
+
+// the x25519 secret never leaves the browser, only its public key is published with `set_encryption_key`
+fn storage_key(network_id: NetworkId, address: &str) -> String {
+    format!("chat-encryption-secret/{}/{}", network_id, address)
+}
+
+fn local_storage() -> Result<web_sys::Storage> {
+    web_sys::window()
+        .context("no window")?
+        .local_storage()
+        .map_err(|err| anyhow!("{:?}", err))?
+        .context("no local storage")
+}
+
+/// The secret this browser made for the address, if it made one
+pub fn load_encryption_secret(network_id: NetworkId, address: &str) -> Option<Vec<u8>> {
+    let stored = local_storage().ok()?.get_item(&storage_key(network_id, address)).ok()??;
+    BASE64_STANDARD.decode(stored).ok()
+}
+
+/// A new secret for the address, kept in this browser
+/// publishing its key replaces the old one, room keys wrapped for that can't be opened anymore
+pub fn new_encryption_secret(network_id: NetworkId, address: &str) -> Result<Vec<u8>> {
+    let secret: [u8; 32] = rand::random();
+    local_storage()?
+        .set_item(&storage_key(network_id, address), &BASE64_STANDARD.encode(secret))
+        .map_err(|err| anyhow!("{:?}", err))?;
+    Ok(secret.to_vec())
+}
+
+/// Every epoch's key of the room we can open, by (room, epoch) as the display looks them up
+pub fn unwrap_room_keys(secret: &[u8], room: &RoomId, resp: RoomKeysResp) -> HashMap<(RoomId, u32), RoomKey> {
+    resp.keys
+        .into_iter()
+        .filter_map(|key| match unwrap_room_key(secret, &key.key) {
+            Ok(room_key) => Some(((room.clone(), key.epoch), room_key)),
+            Err(err) => {
+                log::warn!("couldn't open the key of {} for epoch {}: {}", room, key.epoch, err);
+                None
+            }
+        })
+        .collect()
+}
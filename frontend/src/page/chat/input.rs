@@ -52,7 +52,7 @@ impl ChatInput {
             .class(&*CONTAINER)
             .child(html!("input" => HtmlInputElement, {
                 .class(&*INPUT)
-                .attribute("placeholder", "Type a message...")
+                .attribute("placeholder", "Type a message, or /help for commands...")
                 .attribute("type", "text")
                 .attribute("autocomplete", "off")
                 .after_inserted(clone!(state => move |elem| {
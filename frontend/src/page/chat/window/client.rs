@@ -1,22 +1,39 @@
 
 use gloo_timers::future::TimeoutFuture;
-use shared::msg::{chat_message::event::ChatMessageEvent, contract::client::ChatMessagesResp};
-use wallet::wallet_contract_impls::{WalletSigningContractClient, WalletSigningContractServer};
+use shared::msg::{chat_message::event::ChatMessageEvent, command::ChatCommand, contract::client::ChatMessagesResp, room::{Room, RoomAccess, RoomId}};
+use wallet::{encryption::encryption_pubkey, wallet_contract_impls::{WalletSigningContractClient, WalletSigningContractServer}};
 use wasm_bindgen_futures::spawn_local;
+use web_sys::HtmlSelectElement;
 
-use crate::{config::CONFIG, page::chat::{display::ChatDisplay, input::ChatInput, window::{chat_window_label_render, WINDOW_CLASS}}, prelude::*};
+use crate::{config::CONFIG, page::chat::{display::ChatDisplay, encryption::{load_encryption_secret, new_encryption_secret, unwrap_room_keys}, input::ChatInput, window::{chat_window_label_render, WINDOW_CLASS}}, prelude::*};
 pub(super) struct ChatWindowClient {
     contract: WalletSigningContractClient,
     display: ChatDisplay,
     input: Arc<ChatInput>,
+    // of the connected wallet
+    unread_mentions: Mutable<u32>,
+    // of the main chat, as text
+    pins: Mutable<Vec<String>>,
+    // on this chain, for picking where to post
+    rooms: Mutable<Vec<Room>>,
+    // where the input posts, the main chat if none
+    room: Mutable<Option<RoomId>>,
+    // for the room keys wrapped for us, see page::chat::encryption
+    encryption_secret: Mutable<Option<Vec<u8>>>,
 }
 
 impl ChatWindowClient {
     pub fn new(contract: WalletSigningContractClient) -> Arc<Self> {
+        let encryption_secret = load_encryption_secret(contract.wallet.network_id(), &contract.wallet.address());
         Arc::new(Self {
             contract,
             display: ChatDisplay::new(ContractKind::Client),
             input: ChatInput::new(),
+            unread_mentions: Mutable::new(0),
+            pins: Mutable::new(Vec::new()),
+            rooms: Mutable::new(Vec::new()),
+            room: Mutable::new(None),
+            encryption_secret: Mutable::new(encryption_secret),
         })
     }
 
@@ -28,10 +45,13 @@ impl ChatWindowClient {
             }))
             .class(&*WINDOW_CLASS)
             .child(chat_window_label_render(state.contract.wallet.network_id(), ContractKind::Client))
+            .child(state.render_mentions_badge())
+            .child(state.render_pins())
             .child(state.display.render())
+            .child(state.render_room_select())
             .child(state.input.render(clone!(state => move |text| {
                 spawn_local(clone!(state => async move {
-                    if let Err(e) = state.contract.clone().exec_send_message(text).await {
+                    if let Err(e) = state.send(text).await {
                         web_sys::window().unwrap().alert_with_message(&format!("Error sending message: {:?}", e));
                     }
                 }));
@@ -39,16 +59,203 @@ impl ChatWindowClient {
         })
     }
 
+    // into the picked room, encrypted if it's private
+    // commands are for the contract, they go as typed
+    async fn send(self: &Arc<Self>, text: String) -> Result<()> {
+        let mut contract = self.contract.clone();
+        let room = match self.room.get_cloned() {
+            Some(room) if !text.starts_with(ChatCommand::PREFIX) => room,
+            _ => {
+                contract.exec_send_message(text).await?;
+                return Ok(());
+            }
+        };
+
+        let epoch = self.rooms.lock_ref()
+            .iter()
+            .find(|r| r.id == room)
+            .and_then(|r| match &r.access {
+                RoomAccess::Private { epoch, .. } => Some(*epoch),
+                _ => None,
+            });
+
+        match epoch {
+            None => contract.exec_send_room_message(room, text).await?,
+            Some(epoch) => {
+                let key = self.display.room_keys.lock_ref()
+                    .get(&(room.clone(), epoch))
+                    .copied()
+                    .with_context(|| format!("no key for {} yet", room))?;
+                contract.exec_send_encrypted_message(room, &key, epoch, &text).await?
+            }
+        };
+
+        Ok(())
+    }
+
+    // the main chat or one of the rooms, and the key for private ones if this browser has none yet
+    fn render_room_select(self: &Arc<Self>) -> Dom {
+        let state = self;
+        static CLASS:Lazy<String> = Lazy::new(|| {
+            class! {
+                .style("display", "flex")
+                .style("gap", "0.5rem")
+                .style("padding", "0.25rem 1rem")
+            }
+        });
+        html!("div", {
+            .class(&*CLASS)
+            .class(&*TEXT_SIZE_MD)
+            .child(html!("select" => HtmlSelectElement, {
+                .child(html!("option", {
+                    .attr("value", "")
+                    .text("main chat")
+                }))
+                .children_signal_vec(state.rooms.signal_cloned().to_signal_vec().map(|room| {
+                    html!("option", {
+                        .attr("value", &room.id)
+                        .text(&match room.access {
+                            RoomAccess::Private { .. } => format!("🔒 {}", room.id),
+                            _ => room.id.clone(),
+                        })
+                    })
+                }))
+                .with_node!(elem => {
+                    .event(clone!(state => move |_: events::Change| {
+                        let value = elem.value();
+                        state.room.set((!value.is_empty()).then_some(value));
+                    }))
+                })
+            }))
+            .child(html!("div", {
+                .style("cursor", "pointer")
+                .visible_signal(state.encryption_secret.signal_ref(|secret| secret.is_none()))
+                .text("🔑 enable private rooms")
+                .event(clone!(state => move |_: events::Click| {
+                    spawn_local(clone!(state => async move {
+                        if let Err(e) = state.publish_encryption_key().await {
+                            web_sys::window().unwrap().alert_with_message(&format!("Error setting the encryption key: {:?}", e));
+                        }
+                    }));
+                }))
+            }))
+        })
+    }
+
+    // members can only be added once it's published
+    async fn publish_encryption_key(self: &Arc<Self>) -> Result<()> {
+        let wallet = &self.contract.wallet;
+        let profile = self.contract.clone().query_profile(wallet.address()).await?;
+        if profile.encryption_key.is_some() {
+            let replace = web_sys::window()
+                .context("no window")?
+                .confirm_with_message("A key from somewhere else is already published, rooms shared with it won't open here. Replace it?")
+                .unwrap_or(false);
+            if !replace {
+                return Ok(());
+            }
+        }
+
+        let secret = new_encryption_secret(wallet.network_id(), &wallet.address())?;
+        self.contract.clone().exec_set_encryption_key(Some(encryption_pubkey(&secret)?)).await?;
+        self.encryption_secret.set(Some(secret));
+
+        Ok(())
+    }
+
+    // the keys of the private rooms we're in, loaded before the messages that need them
+    async fn load_room_keys(self: &Arc<Self>) {
+        let Ok(resp) = self.contract.clone().query_rooms().await else { return };
+        let address = self.contract.wallet.address();
+
+        if let Some(secret) = self.encryption_secret.get_cloned() {
+            for room in &resp.rooms {
+                let RoomAccess::Private { members, epoch } = &room.access else { continue };
+                let loaded = self.display.room_keys.lock_ref().contains_key(&(room.id.clone(), *epoch));
+                if loaded || !members.iter().any(|member| member.as_str() == address) {
+                    continue;
+                }
+
+                if let Ok(keys) = self.contract.clone().query_room_keys(&room.id, &address).await {
+                    self.display.room_keys.lock_mut().extend(unwrap_room_keys(&secret, &room.id, keys));
+                }
+            }
+        }
+
+        self.rooms.set(resp.rooms);
+    }
+
+    // hidden while there's nothing unread, clicking it marks everything read
+    fn render_mentions_badge(self: &Arc<Self>) -> Dom {
+        let state = self;
+        static CLASS:Lazy<String> = Lazy::new(|| {
+            class! {
+                .style("align-self", "center")
+                .style("cursor", "pointer")
+                .style("padding", "0 0.5rem")
+                .style("border-radius", "1rem")
+                .style("color", ColorSemantic::PureWhite.to_str())
+                .style("background-color", ColorSemantic::Accent.to_str())
+            }
+        });
+        html!("div", {
+            .class(&*CLASS)
+            .class(&*TEXT_SIZE_MD)
+            .visible_signal(state.unread_mentions.signal().map(|count| count > 0))
+            .text_signal(state.unread_mentions.signal().map(|count| format!("@ {}", count)))
+            .event(clone!(state => move |_: events::Click| {
+                spawn_local(clone!(state => async move {
+                    if state.contract.clone().exec_mark_read(None).await.is_ok() {
+                        state.unread_mentions.set_neq(0);
+                    }
+                }));
+            }))
+        })
+    }
+
+    // announcements and other pinned messages, above the chat
+    fn render_pins(self: &Arc<Self>) -> Dom {
+        let state = self;
+        static CLASS:Lazy<String> = Lazy::new(|| {
+            class! {
+                .style("padding", "0.5rem")
+                .style("border-bottom", "1px solid")
+                .style("font-weight", "bold")
+            }
+        });
+        html!("div", {
+            .class(&*CLASS)
+            .visible_signal(state.pins.signal_ref(|pins| !pins.is_empty()))
+            .children_signal_vec(state.pins.signal_cloned().to_signal_vec().map(|text| {
+                html!("div", {
+                    .text(&format!("📌 {}", text))
+                })
+            }))
+        })
+    }
+
     async fn poll_messages(self: &Arc<Self>) {
         let state = self;
         let mut message_cursor = None;
         loop {
-            let ChatMessagesResp {messages} = state.contract.clone().query_chat_messages(message_cursor, None).await.unwrap();
+            state.load_room_keys().await;
+
+            let ChatMessagesResp {messages, identities} = state.contract.clone().query_chat_messages(message_cursor, None).await.unwrap();
             if !messages.is_empty() {
                 message_cursor = Some(messages.last().unwrap().index);
+                state.display.add_identities(identities);
                 state.display.add_messages(messages);
             }
 
+            if let Ok(resp) = state.contract.clone().query_unread_mentions(state.contract.wallet.address()).await {
+                state.unread_mentions.set_neq(resp.count);
+            }
+
+            // a pinned message this chain doesn't have isn't shown
+            if let Ok(resp) = state.contract.clone().query_pins(None).await {
+                state.pins.set_neq(resp.pins.into_iter().filter_map(|pinned| pinned.message).map(|message| message.msg.message).collect());
+            }
+
             // sleep for a bit
             TimeoutFuture::new(CONFIG.messages_poll_delay_ms).await;
         }
@@ -1,28 +1,52 @@
 use std::{collections::HashMap, ops::{Deref, DerefMut}, sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard}, vec};
 
-use cosmwasm_std::{Addr, Empty};
-use cw_multi_test::{App, ContractWrapper, Executor};
-use shared::msg::{self, network::NetworkId};
-
-use crate::multitest_contract_impls::{TestAppContractClient, TestAppContractServer};
+use cosmwasm_std::{testing::{MockApi, MockStorage}, Addr, Empty};
+use cw_multi_test::{no_init, App, AppBuilder, BankKeeper, ContractWrapper, Executor, FailingModule, WasmKeeper};
+use shared::msg::{self, misc::ChannelOrder, network::NetworkId};
+
+use crate::{ibc::{IbcRecorder, TestIbc}, multitest_contract_impls::{TestAppContractClient, TestAppContractServer}};
+
+pub type MultiTestApp = App<
+    BankKeeper,
+    MockApi,
+    MockStorage,
+    FailingModule<Empty, Empty, Empty>,
+    WasmKeeper<Empty, Empty>,
+    // staking and distribution, without the multitest "staking" feature
+    FailingModule<Empty, Empty, Empty>,
+    FailingModule<Empty, Empty, Empty>,
+    IbcRecorder,
+>;
 
 #[derive(Clone)]
 pub struct TestApp {
     inner: Arc<RwLock<TestAppInner>>,
 }
 
+impl Default for TestApp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TestApp {
     pub fn new() -> Self {
+        Self::new_with_order(ChannelOrder::Unordered)
+    }
+
+    pub fn new_with_order(channel_order: ChannelOrder) -> Self {
+        // the tests are single-threaded, the lock is only for sharing the app between contract wrappers
+        #[allow(clippy::arc_with_non_send_sync)]
         Self {
-            inner: Arc::new(RwLock::new(TestAppInner::new()))
+            inner: Arc::new(RwLock::new(TestAppInner::new(channel_order)))
         }
     }
 
-    pub fn as_ref(&self) -> RwLockReadGuard<TestAppInner> {
+    pub fn as_ref(&self) -> RwLockReadGuard<'_, TestAppInner> {
         self.inner.read().unwrap()
     }
 
-    pub fn as_mut(&self) -> RwLockWriteGuard<TestAppInner> {
+    pub fn as_mut(&self) -> RwLockWriteGuard<'_, TestAppInner> {
         self.inner.write().unwrap()
     }
 
@@ -38,14 +62,22 @@ impl TestApp {
 pub struct TestAppInner {
     #[allow(dead_code)]
     code_ids: HashMap<ContractKind, u64>,
-    app: App,
+    app: MultiTestApp,
     pub client_contracts: Vec<Addr>,
     pub server_contract: Addr,
+    pub nois_proxy: Addr,
+    /// For gated rooms, every client sees it since multitest is a single chain
+    pub cw721_collection: Addr,
+    pub channel_order: ChannelOrder,
+    pub ibc: TestIbc,
 }
 
 impl TestAppInner {
-    pub fn new() -> Self {
-        let mut app = App::default();
+    pub fn new(channel_order: ChannelOrder) -> Self {
+        let ibc = TestIbc::default();
+        let mut app = AppBuilder::new()
+            .with_ibc(ibc.recorder.clone())
+            .build(no_init);
         let mut code_ids = HashMap::new();
 
         code_ids.insert(ContractKind::Server, app.store_code(Box::new(ContractWrapper::new(
@@ -58,7 +90,37 @@ impl TestAppInner {
             client::entry::execute, 
             client::entry::instantiate, 
             client::entry::query
-        ))));
+        ).with_reply(client::entry::reply).with_sudo(client::entry::sudo))));
+
+        let nois_code_id = app.store_code(Box::new(ContractWrapper::new(
+            crate::nois::execute,
+            crate::nois::instantiate,
+            crate::nois::query
+        )));
+
+        let nois_proxy = app.instantiate_contract(
+            nois_code_id,
+            Addr::unchecked("nois-admin"),
+            &Empty {},
+            &[],
+            "nois-proxy",
+            None,
+        ).unwrap();
+
+        let cw721_code_id = app.store_code(Box::new(ContractWrapper::new(
+            crate::cw721::execute,
+            crate::cw721::instantiate,
+            crate::cw721::query
+        )));
+
+        let cw721_collection = app.instantiate_contract(
+            cw721_code_id,
+            Addr::unchecked("cw721-admin"),
+            &Empty {},
+            &[],
+            "cw721-collection",
+            None,
+        ).unwrap();
 
         let mut client_contracts = Vec::with_capacity(4);
 
@@ -69,12 +131,14 @@ impl TestAppInner {
             NetworkId::Neutron,
         ];
 
-        for network_id in network_ids  {
+        for network_id in network_ids.clone() {
             client_contracts.push(app.instantiate_contract(
                 code_ids[&ContractKind::Client],
                 Addr::unchecked("client-admin"),
                 &msg::contract::client::InstantiateMsg {
-                    network_id
+                    network_id,
+                    channel_order: Some(channel_order),
+                    nois_proxy: Some(nois_proxy.to_string()),
                 },
                 &[],
                 "client",
@@ -85,17 +149,39 @@ impl TestAppInner {
         let server_contract = app.instantiate_contract(
             code_ids[&ContractKind::Server],
             Addr::unchecked("server-admin"),
-            &Empty {},
+            &msg::contract::server::InstantiateMsg {
+                channel_order: Some(channel_order),
+                // tests that want them turn them on, see tests/network_notices.rs
+                network_notices: None,
+                network_id: None,
+            },
             &[],
             "server",
             None,
         ).unwrap();
 
+        // the harness gives client i connection-i, see TestAppInner::ibc_connect_client
+        for (client_index, network_id) in network_ids.into_iter().enumerate() {
+            app.execute_contract(
+                Addr::unchecked("server-admin"),
+                server_contract.clone(),
+                &msg::contract::server::ExecuteMsg::SetClientConnection {
+                    connection_id: format!("connection-{}", client_index),
+                    network_id: Some(network_id),
+                },
+                &[],
+            ).unwrap();
+        }
+
         Self {
             app,
             code_ids,
             client_contracts,
-            server_contract
+            server_contract,
+            nois_proxy,
+            cw721_collection,
+            channel_order,
+            ibc,
         }
     }
 }
@@ -108,7 +194,7 @@ pub enum ContractKind {
 
 // yeah yeah, abusing this a bit :P
 impl Deref for TestAppInner {
-    type Target = App;
+    type Target = MultiTestApp;
     fn deref(&self) -> &Self::Target {
         &self.app
     }
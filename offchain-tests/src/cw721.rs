@@ -0,0 +1,57 @@
+/// A stand-in for a cw721 collection, just enough to gate rooms with
+/// anyone can mint, and only [Cw721QueryMsg::Tokens] is answered
+use anyhow::Result;
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{to_json_binary, Addr, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Order, Response, StdResult};
+use cw_multi_test::{AppResponse, Executor};
+use cw_storage_plus::{Bound, Map};
+use shared::msg::cw721::{Cw721QueryMsg, TokensResponse};
+
+use crate::app::TestAppInner;
+
+// (owner, token id)
+const TOKENS: Map<(&str, &str), Empty> = Map::new("tokens");
+
+#[cw_serde]
+pub enum MockCw721ExecuteMsg {
+    Mint {
+        token_id: String,
+        owner: String,
+    },
+}
+
+pub fn execute(deps: DepsMut, _env: Env, _info: MessageInfo, msg: MockCw721ExecuteMsg) -> StdResult<Response> {
+    match msg {
+        MockCw721ExecuteMsg::Mint { token_id, owner } => {
+            TOKENS.save(deps.storage, (&owner, &token_id), &Empty {})?;
+            Ok(Response::default())
+        }
+    }
+}
+
+pub fn instantiate(_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty) -> StdResult<Response> {
+    Ok(Response::default())
+}
+
+pub fn query(deps: Deps, _env: Env, msg: Cw721QueryMsg) -> StdResult<Binary> {
+    match msg {
+        Cw721QueryMsg::Tokens { owner, start_after, limit } => {
+            let tokens = TOKENS.prefix(&owner)
+                .keys(deps.storage, start_after.as_deref().map(Bound::exclusive), None, Order::Ascending)
+                .take(limit.unwrap_or(10) as usize)
+                .collect::<StdResult<Vec<_>>>()?;
+
+            to_json_binary(&TokensResponse { tokens })
+        }
+    }
+}
+
+impl TestAppInner {
+    pub fn cw721_mint(&mut self, token_id: &str, owner: &str) -> Result<AppResponse> {
+        let collection = self.cw721_collection.clone();
+        self.execute_contract(Addr::unchecked("cw721-minter"), collection, &MockCw721ExecuteMsg::Mint {
+            token_id: token_id.to_string(),
+            owner: owner.to_string(),
+        }, &[])
+    }
+}
@@ -0,0 +1,46 @@
+/// Setup the tests keep needing: a client contract used by some user, the server's admin,
+/// and what a client contract has stored
+use cosmwasm_std::{Addr, Order};
+use shared::msg::chat_message::ChatMessageWithIndex;
+use wallet::{prelude::*, signed::signer_address};
+
+use crate::{app::TestApp, multitest_contract_impls::{TestAppContractClient, TestAppContractServer}};
+
+/// multitest's addresses
+pub const PREFIX: &str = "cosmwasm";
+
+/// The client contract at `client_index`, used by the owner of `key`
+pub fn client_as(app: &TestApp, client_index: usize, key: &[u8]) -> TestAppContractClient {
+    let mut client = app.clone().into_contract_client();
+    client.id = client_index;
+    client.sender = Addr::unchecked(signer_address(key, PREFIX).unwrap());
+    client
+}
+
+/// User `n` on the client contract at `client_index`, i.e. the owner of the key `[n; 32]`, and their address
+pub fn user(app: &TestApp, client_index: usize, n: u8) -> (String, TestAppContractClient) {
+    let client = client_as(app, client_index, &[n; 32]);
+    (client.sender.to_string(), client)
+}
+
+/// The server contract, used by its admin
+pub fn server_admin(app: &TestApp) -> TestAppContractServer {
+    let mut server = app.clone().into_contract_server();
+    server.sender = Addr::unchecked("server-admin");
+    server
+}
+
+/// Everything the client contract at `client_index` has stored, oldest first
+pub async fn client_messages(app: &TestApp, client_index: usize) -> Vec<ChatMessageWithIndex> {
+    let mut client = app.clone().into_contract_client();
+    client.id = client_index;
+    client.query_chat_messages(None, Some(Order::Ascending)).await.unwrap().messages
+}
+
+/// Same as [client_messages], only the text
+pub async fn client_texts(app: &TestApp, client_index: usize) -> Vec<String> {
+    client_messages(app, client_index).await
+        .into_iter()
+        .map(|m| m.msg.message)
+        .collect()
+}
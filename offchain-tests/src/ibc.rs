@@ -0,0 +1,583 @@
+/// Multitest doesn't do IBC, so this is a tiny in-process relayer
+/// contracts send their IbcMsg's to the [IbcRecorder] module, and the [TestAppInner]
+/// methods here deliver them to the IBC entry points of the counterparty contract
+use std::{collections::{HashMap, VecDeque}, sync::{Arc, Mutex}};
+
+use anyhow::{anyhow, bail, Result};
+use cosmwasm_std::{
+    testing::MockQuerier, Addr, Api, BankMsg, Binary, Event, BlockInfo, Coin, ContractInfo, CosmosMsg, CustomMsg, CustomQuery, DepsMut, Empty, Env, IbcAcknowledgement, IbcBasicResponse, IbcChannel, IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcEndpoint, IbcMsg, IbcOrder, IbcPacket, IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcQuery, IbcTimeout, Querier, QuerierWrapper, Reply, ReplyOn, Storage, SubMsg, SubMsgResult
+};
+use cw_multi_test::{AppResponse, CosmosRouter, Executor, Ibc, Module};
+use serde::de::DeserializeOwned;
+use shared::{ibc::{IbcAck, IbcProtocolVersion, TIMEOUT_SECONDS}, msg::{contract::client::SudoMsg, ibc::IbcExecuteMsg, memo::{IbcCallbackMemo, IbcLifecycleComplete}}};
+
+use crate::app::{ContractKind, TestAppInner};
+
+/// Where ICS-20 transfers are escrowed, see [TestIbc::transfers]
+pub const TRANSFER_ESCROW: &str = "transfer-escrow";
+
+/// Records every IbcMsg sent by a contract, to be picked up by the relayer
+#[derive(Clone, Default)]
+pub struct IbcRecorder {
+    sent: Arc<Mutex<Vec<(Addr, IbcMsg)>>>,
+    // transfers get their sequence when sent, since the sender sees it in the send_packet event
+    transfers: Arc<Mutex<Vec<TestIbcTransfer>>>,
+    next_transfer_sequence: Arc<Mutex<HashMap<String, u64>>>,
+}
+
+impl IbcRecorder {
+    fn drain(&self) -> Vec<(Addr, IbcMsg)> {
+        self.sent.lock().unwrap().drain(..).collect()
+    }
+
+    fn drain_transfers(&self) -> Vec<TestIbcTransfer> {
+        self.transfers.lock().unwrap().drain(..).collect()
+    }
+}
+
+impl Module for IbcRecorder {
+    type ExecT = IbcMsg;
+    type QueryT = IbcQuery;
+    type SudoT = Empty;
+
+    fn execute<ExecC, QueryC>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+        sender: Addr,
+        msg: Self::ExecT,
+    ) -> Result<AppResponse>
+    where
+        ExecC: CustomMsg + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        // like the transfer module, the funds leave the sender's account right away
+        if let IbcMsg::Transfer { channel_id, to_address, amount, memo, .. } = msg {
+            router.execute(api, storage, block, sender.clone(), BankMsg::Send {
+                to_address: TRANSFER_ESCROW.to_string(),
+                amount: vec![amount.clone()],
+            }.into())?;
+
+            let sequence = {
+                let mut sequences = self.next_transfer_sequence.lock().unwrap();
+                let sequence = sequences.entry(channel_id.clone()).or_insert(0);
+                *sequence += 1;
+                *sequence
+            };
+            let event = Event::new("send_packet")
+                .add_attribute("packet_src_channel", &channel_id)
+                .add_attribute("packet_sequence", sequence.to_string());
+
+            self.transfers.lock().unwrap().push(TestIbcTransfer { sender, channel_id, sequence, to_address, amount, memo });
+            return Ok(AppResponse { events: vec![event], data: None });
+        }
+
+        self.sent.lock().unwrap().push((sender, msg));
+        Ok(AppResponse::default())
+    }
+
+    fn query(
+        &self,
+        _api: &dyn Api,
+        _storage: &dyn Storage,
+        _querier: &dyn Querier,
+        _block: &BlockInfo,
+        request: Self::QueryT,
+    ) -> Result<Binary> {
+        bail!("unsupported ibc query: {:?}", request)
+    }
+
+    fn sudo<ExecC, QueryC>(
+        &self,
+        _api: &dyn Api,
+        _storage: &mut dyn Storage,
+        _router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        _block: &BlockInfo,
+        msg: Self::SudoT,
+    ) -> Result<AppResponse>
+    where
+        ExecC: CustomMsg + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        bail!("unsupported ibc sudo: {:?}", msg)
+    }
+}
+
+impl Ibc for IbcRecorder {}
+
+/// A channel between the server and one of the clients
+#[derive(Clone, Debug)]
+pub struct TestIbcChannel {
+    pub client_index: usize,
+    pub server: IbcEndpoint,
+    pub client: IbcEndpoint,
+    pub order: IbcOrder,
+    pub version: String,
+    pub connection_id: String,
+    pub open: bool,
+}
+
+impl TestIbcChannel {
+    fn server_view(&self) -> IbcChannel {
+        IbcChannel::new(self.server.clone(), self.client.clone(), self.order.clone(), &self.version, &self.connection_id)
+    }
+
+    fn client_view(&self) -> IbcChannel {
+        IbcChannel::new(self.client.clone(), self.server.clone(), self.order.clone(), &self.version, &self.connection_id)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TestIbcPacket {
+    pub packet: IbcPacket,
+    pub sender: Addr,
+    pub receiver: Addr,
+}
+
+#[derive(Clone, Debug)]
+pub struct TestIbcTransfer {
+    pub sender: Addr,
+    pub channel_id: String,
+    pub sequence: u64,
+    pub to_address: String,
+    pub amount: Coin,
+    pub memo: Option<String>,
+}
+
+/// The relayer state
+#[derive(Default)]
+pub struct TestIbc {
+    pub recorder: IbcRecorder,
+    pub channels: Vec<TestIbcChannel>,
+    pub in_flight: VecDeque<TestIbcPacket>,
+    /// Packets on an ordered channel that closed, waiting for the relayer to time them out
+    pub stuck: Vec<TestIbcPacket>,
+    /// ICS-20 transfers sent by contracts, the funds are held by [TRANSFER_ESCROW]
+    /// there's no counterparty chain to deliver them to, see [TestAppInner::ibc_transfer_ack] to settle one
+    pub transfers: Vec<TestIbcTransfer>,
+    next_channel_id: u64,
+    next_sequence: HashMap<String, u64>,
+}
+
+enum IbcEntryResponse {
+    Basic(IbcBasicResponse),
+    Ack(Option<Binary>, Vec<SubMsg>),
+}
+
+impl TestAppInner {
+    /// Opens a channel between the server and every client, like the relayer setup does
+    pub fn ibc_connect_all(&mut self) -> Result<()> {
+        for client_index in 0..self.client_contracts.len() {
+            self.ibc_connect_client(client_index)?;
+        }
+        Ok(())
+    }
+
+    /// Runs the full handshake for a new channel, with the server as the initiator
+    /// the connection id is stable per client, so a reconnect looks like the same client coming back
+    pub fn ibc_connect_client(&mut self, client_index: usize) -> Result<()> {
+        self.ibc_connect_client_with_version(client_index, &IbcProtocolVersion::all_as_proposal())
+    }
+
+    pub fn ibc_connect_client_with_version(&mut self, client_index: usize, proposal: &str) -> Result<()> {
+        let server_addr = self.server_contract.clone();
+        let client_addr = self.client_contracts[client_index].clone();
+
+        let mut channel = TestIbcChannel {
+            client_index,
+            server: IbcEndpoint {
+                port_id: format!("wasm.{}", server_addr),
+                channel_id: self.next_channel_id(),
+            },
+            client: IbcEndpoint {
+                port_id: format!("wasm.{}", client_addr),
+                channel_id: self.next_channel_id(),
+            },
+            order: self.channel_order.into(),
+            version: proposal.to_string(),
+            connection_id: format!("connection-{}", client_index),
+            open: false,
+        };
+
+        // OpenInit on the server, which may rewrite the proposal
+        let proposal = self.ibc_channel_open(&server_addr, IbcChannelOpenMsg::new_init(channel.server_view()))?
+            .unwrap_or(proposal.to_string());
+        channel.version = proposal.clone();
+
+        // OpenTry on the client, which picks the version
+        let version = self.ibc_channel_open(&client_addr, IbcChannelOpenMsg::new_try(channel.client_view(), &proposal))?
+            .unwrap_or(proposal.clone());
+
+        // OpenAck on the server, which still sees its own proposal as the channel version
+        self.ibc_call(&server_addr, |kind, deps, env| match kind {
+            ContractKind::Server => server::entry::ibc_channel_connect(deps, env, IbcChannelConnectMsg::new_ack(channel.server_view(), &version)),
+            ContractKind::Client => client::entry::ibc_channel_connect(deps, env, IbcChannelConnectMsg::new_ack(channel.server_view(), &version)),
+        }.map(IbcEntryResponse::Basic))?;
+
+        // OpenConfirm on the client
+        channel.version = version;
+        self.ibc_call(&client_addr, |kind, deps, env| match kind {
+            ContractKind::Server => server::entry::ibc_channel_connect(deps, env, IbcChannelConnectMsg::new_confirm(channel.client_view())),
+            ContractKind::Client => client::entry::ibc_channel_connect(deps, env, IbcChannelConnectMsg::new_confirm(channel.client_view())),
+        }.map(IbcEntryResponse::Basic))?;
+
+        channel.open = true;
+        self.ibc.channels.push(channel.clone());
+        self.ibc_collect_packets()?;
+
+        // the client's announcement is part of getting connected, like the relayer would deliver it right away
+        // anything it sets off, e.g. the network notices, is left in flight
+        let announcement = self.ibc.in_flight.iter().position(|pending| {
+            pending.packet.src == channel.client
+                && matches!(IbcExecuteMsg::decode(channel.version.parse().unwrap_or(IbcProtocolVersion::V1), &pending.packet.data), Ok(IbcExecuteMsg::ClientConnected { .. }))
+        });
+        if let Some(announcement) = announcement.and_then(|index| self.ibc.in_flight.remove(index)) {
+            self.ibc_relay_packet(announcement, None)?;
+        }
+
+        Ok(())
+    }
+
+    /// Delivers every packet in flight (including the ones sent as a result) and their acks
+    /// returns the acks in the order they were delivered
+    pub fn ibc_relay_all(&mut self) -> Result<Vec<IbcAck>> {
+        let mut acks = Vec::new();
+
+        self.ibc_collect_packets()?;
+        while !self.ibc.in_flight.is_empty() {
+            acks.push(self.ibc_relay_next()?);
+        }
+
+        Ok(acks)
+    }
+
+    /// The packets waiting to be relayed, oldest first
+    pub fn ibc_in_flight(&mut self) -> Result<&VecDeque<TestIbcPacket>> {
+        self.ibc_collect_packets()?;
+        Ok(&self.ibc.in_flight)
+    }
+
+    /// The ICS-20 transfers sent so far, oldest first
+    pub fn ibc_transfers(&mut self) -> Result<&Vec<TestIbcTransfer>> {
+        self.ibc_collect_packets()?;
+        Ok(&self.ibc.transfers)
+    }
+
+    /// Settles a transfer from [TestIbc::transfers] the way ibc-hooks would, and takes it off the list
+    /// a failed one is refunded to the sender first, then the sender is told either way
+    pub fn ibc_transfer_ack(&mut self, index: usize, success: bool) -> Result<AppResponse> {
+        let transfer = self.ibc_take_transfer(index)?;
+        let complete = IbcLifecycleComplete::IbcAck {
+            channel: transfer.channel_id.clone(),
+            sequence: transfer.sequence,
+            ack: if success { "AQ==" } else { "transfer failed" }.to_string(),
+            success,
+        };
+        self.ibc_transfer_complete(transfer, !success, complete)
+    }
+
+    /// Times out a transfer from [TestIbc::transfers], refunding the sender
+    pub fn ibc_transfer_timeout(&mut self, index: usize) -> Result<AppResponse> {
+        let transfer = self.ibc_take_transfer(index)?;
+        let complete = IbcLifecycleComplete::IbcTimeout {
+            channel: transfer.channel_id.clone(),
+            sequence: transfer.sequence,
+        };
+        self.ibc_transfer_complete(transfer, true, complete)
+    }
+
+    fn ibc_take_transfer(&mut self, index: usize) -> Result<TestIbcTransfer> {
+        self.ibc_collect_packets()?;
+        if index >= self.ibc.transfers.len() {
+            bail!("no transfer {}", index);
+        }
+        Ok(self.ibc.transfers.remove(index))
+    }
+
+    fn ibc_transfer_complete(&mut self, transfer: TestIbcTransfer, refund: bool, complete: IbcLifecycleComplete) -> Result<AppResponse> {
+        if refund {
+            self.send_tokens(Addr::unchecked(TRANSFER_ESCROW), transfer.sender.clone(), &[transfer.amount])?;
+        }
+
+        // only if asked to by the memo, and ibc-hooks only calls back the sender
+        let callback = transfer.memo
+            .and_then(|memo| cosmwasm_std::from_json::<IbcCallbackMemo>(memo).ok())
+            .filter(|memo| memo.ibc_callback == transfer.sender.as_str());
+        match callback {
+            Some(_) => self.wasm_sudo(transfer.sender, &SudoMsg::IbcLifecycleComplete(complete)),
+            None => Ok(AppResponse::default()),
+        }
+    }
+
+    /// Delivers the oldest packet in flight and its ack
+    pub fn ibc_relay_next(&mut self) -> Result<IbcAck> {
+        self.ibc_collect_packets()?;
+        let next = self.ibc.in_flight.pop_front().ok_or_else(|| anyhow!("no packets in flight"))?;
+
+        self.ibc_relay_packet(next, None)
+    }
+
+    /// Like [Self::ibc_relay_next], but the sender gets `ack` instead of what the receiver wrote, e.g. a garbled one
+    pub fn ibc_relay_next_with_ack(&mut self, ack: Binary) -> Result<IbcAck> {
+        self.ibc_collect_packets()?;
+        let next = self.ibc.in_flight.pop_front().ok_or_else(|| anyhow!("no packets in flight"))?;
+
+        self.ibc_relay_packet(next, Some(ack))
+    }
+
+    fn ibc_relay_packet(&mut self, TestIbcPacket { packet, sender, receiver }: TestIbcPacket, ack_override: Option<Binary>) -> Result<IbcAck> {
+        let ack = self.ibc_call(&receiver, |kind, deps, env| {
+            let msg = IbcPacketReceiveMsg::new(packet.clone(), Addr::unchecked("relayer"));
+            match kind {
+                ContractKind::Server => server::entry::ibc_packet_receive(deps, env, msg),
+                ContractKind::Client => client::entry::ibc_packet_receive(deps, env, msg),
+            }.map(|resp| IbcEntryResponse::Ack(resp.acknowledgement, resp.messages))
+        })?.ok_or_else(|| anyhow!("no acknowledgement written"))?;
+        let ack = ack_override.unwrap_or(ack);
+
+        self.ibc_call(&sender, |kind, deps, env| {
+            let msg = IbcPacketAckMsg::new(IbcAcknowledgement::new(ack.clone()), packet.clone(), Addr::unchecked("relayer"));
+            match kind {
+                ContractKind::Server => server::entry::ibc_packet_ack(deps, env, msg),
+                ContractKind::Client => client::entry::ibc_packet_ack(deps, env, msg),
+            }.map(IbcEntryResponse::Basic)
+        })?;
+
+        self.ibc_collect_packets()?;
+
+        Ok(IbcAck::decode_or_error(&ack))
+    }
+
+    /// Puts a packet in flight as if the client contract had sent it
+    /// handy for messages the contract would never send by itself
+    pub fn ibc_send_from_client(&mut self, client_index: usize, msg: &IbcExecuteMsg) -> Result<()> {
+        let channel = self.ibc.channels
+            .iter()
+            .rev()
+            .find(|channel| channel.client_index == client_index && channel.open)
+            .ok_or_else(|| anyhow!("client {} has no open channel", client_index))?
+            .clone();
+
+        self.ibc.recorder.sent.lock().unwrap().push((self.client_contracts[client_index].clone(), IbcMsg::SendPacket {
+            channel_id: channel.client.channel_id,
+            data: msg.encode(channel.version.parse()?)?,
+            timeout: IbcTimeout::with_timestamp(self.block_info().time.plus_seconds(TIMEOUT_SECONDS)),
+        }));
+
+        self.ibc_collect_packets()
+    }
+
+    /// Times out the oldest packet in flight
+    /// on an ordered channel this closes the channel on both ends, and every other packet on it times out as well
+    pub fn ibc_timeout_next(&mut self) -> Result<()> {
+        self.ibc_timeout_next_alone()?;
+        self.ibc_timeout_stuck()
+    }
+
+    /// Like [Self::ibc_timeout_next], but the other packets on a closed ordered channel are only taken out of flight
+    /// as when the relayer gets to their timeouts later, with [Self::ibc_timeout_stuck]
+    pub fn ibc_timeout_next_alone(&mut self) -> Result<()> {
+        self.ibc_collect_packets()?;
+        let next = self.ibc.in_flight.pop_front().ok_or_else(|| anyhow!("no packets in flight"))?;
+
+        self.ibc_timeout_packet(&next)?;
+
+        if let Some(channel) = self.ibc.channels.iter_mut().find(|channel| channel.server == next.packet.src || channel.client == next.packet.src) {
+            if channel.order == IbcOrder::Ordered {
+                channel.open = false;
+                let channel = channel.clone();
+                let endpoints = [channel.server.clone(), channel.client.clone()];
+
+                let (stuck, rest):(Vec<_>, Vec<_>) = self.ibc.in_flight
+                    .drain(..)
+                    .partition(|pending| endpoints.contains(&pending.packet.src));
+
+                self.ibc.in_flight = rest.into();
+                self.ibc.stuck.extend(stuck);
+
+                // the relayer then proves the closed channel to the counterparty
+                let (receiver, counterparty_view) = if channel.server == next.packet.src {
+                    (self.client_contracts[channel.client_index].clone(), channel.client_view())
+                } else {
+                    (self.server_contract.clone(), channel.server_view())
+                };
+
+                self.ibc_call(&receiver, |kind, deps, env| {
+                    let msg = IbcChannelCloseMsg::new_confirm(counterparty_view);
+                    match kind {
+                        ContractKind::Server => server::entry::ibc_channel_close(deps, env, msg),
+                        ContractKind::Client => client::entry::ibc_channel_close(deps, env, msg),
+                    }.map(IbcEntryResponse::Basic)
+                })?;
+            }
+        }
+
+        self.ibc_collect_packets()
+    }
+
+    /// Times out the packets left on closed channels, oldest first
+    pub fn ibc_timeout_stuck(&mut self) -> Result<()> {
+        for pending in std::mem::take(&mut self.ibc.stuck) {
+            self.ibc_timeout_packet(&pending)?;
+        }
+
+        self.ibc_collect_packets()
+    }
+
+    /// Closes the client's channel from its end, e.g. the chain is going away
+    pub fn ibc_close_client(&mut self, client_index: usize) -> Result<()> {
+        let channel = self.ibc.channels
+            .iter_mut()
+            .rev()
+            .find(|channel| channel.client_index == client_index && channel.open)
+            .ok_or_else(|| anyhow!("client {} has no open channel", client_index))?;
+        channel.open = false;
+        let channel = channel.clone();
+
+        let client_addr = self.client_contracts[client_index].clone();
+        self.ibc_call(&client_addr, |kind, deps, env| {
+            let msg = IbcChannelCloseMsg::new_init(channel.client_view());
+            match kind {
+                ContractKind::Server => server::entry::ibc_channel_close(deps, env, msg),
+                ContractKind::Client => client::entry::ibc_channel_close(deps, env, msg),
+            }.map(IbcEntryResponse::Basic)
+        })?;
+
+        let server_addr = self.server_contract.clone();
+        self.ibc_call(&server_addr, |kind, deps, env| {
+            let msg = IbcChannelCloseMsg::new_confirm(channel.server_view());
+            match kind {
+                ContractKind::Server => server::entry::ibc_channel_close(deps, env, msg),
+                ContractKind::Client => client::entry::ibc_channel_close(deps, env, msg),
+            }.map(IbcEntryResponse::Basic)
+        })?;
+
+        self.ibc_collect_packets()
+    }
+
+    fn ibc_timeout_packet(&mut self, pending: &TestIbcPacket) -> Result<()> {
+        self.ibc_call(&pending.sender, |kind, deps, env| {
+            let msg = IbcPacketTimeoutMsg::new(pending.packet.clone(), Addr::unchecked("relayer"));
+            match kind {
+                ContractKind::Server => server::entry::ibc_packet_timeout(deps, env, msg),
+                ContractKind::Client => client::entry::ibc_packet_timeout(deps, env, msg),
+            }.map(IbcEntryResponse::Basic)
+        })?;
+
+        Ok(())
+    }
+
+    fn ibc_channel_open(&mut self, addr: &Addr, msg: IbcChannelOpenMsg) -> Result<Option<String>> {
+        let kind = self.contract_kind(addr);
+        let resp = self.with_contract_deps(addr, |deps, env| match kind {
+            ContractKind::Server => server::entry::ibc_channel_open(deps, env, msg),
+            ContractKind::Client => client::entry::ibc_channel_open(deps, env, msg),
+        })?;
+
+        Ok(resp.map(|resp| resp.version))
+    }
+
+    // calls an IBC entry point and feeds the resulting messages back into the app
+    // returns the acknowledgement, if the entry point was a packet receive
+    fn ibc_call(&mut self, addr: &Addr, f: impl FnOnce(ContractKind, DepsMut, Env) -> Result<IbcEntryResponse>) -> Result<Option<Binary>> {
+        let kind = self.contract_kind(addr);
+        let (ack, messages) = match self.with_contract_deps(addr, |deps, env| f(kind, deps, env))? {
+            IbcEntryResponse::Basic(resp) => (None, resp.messages),
+            IbcEntryResponse::Ack(ack, messages) => (ack, messages),
+        };
+
+        for SubMsg { msg, id, payload, reply_on, .. } in messages {
+            match msg {
+                CosmosMsg::Ibc(msg) => self.ibc.recorder.sent.lock().unwrap().push((addr.clone(), msg)),
+                msg => {
+                    // the only replies our contracts ask for are for failures (i.e. hooks)
+                    if let Err(err) = self.execute(addr.clone(), msg) {
+                        if !matches!(reply_on, ReplyOn::Error | ReplyOn::Always) {
+                            return Err(err);
+                        }
+
+                        let reply = Reply { id, payload, gas_used: 0, result: SubMsgResult::Err(err.to_string()) };
+                        self.with_contract_deps(addr, |deps, env| match kind {
+                            ContractKind::Server => bail!("server doesn't handle replies"),
+                            ContractKind::Client => client::entry::reply(deps, env, reply),
+                        })?;
+                    }
+                }
+            }
+        }
+
+        Ok(ack)
+    }
+
+    // turns the recorded IbcMsg's into packets in flight
+    fn ibc_collect_packets(&mut self) -> Result<()> {
+        for (sender, msg) in self.ibc.recorder.drain() {
+            match msg {
+                IbcMsg::SendPacket { channel_id, data, timeout } => {
+                    let port_id = format!("wasm.{}", sender);
+                    let channel = self.ibc.channels
+                        .iter()
+                        .find(|channel| {
+                            (channel.server.port_id == port_id && channel.server.channel_id == channel_id)
+                            || (channel.client.port_id == port_id && channel.client.channel_id == channel_id)
+                        })
+                        .ok_or_else(|| anyhow!("{} sent a packet on unknown channel {}", sender, channel_id))?
+                        .clone();
+
+                    if !channel.open {
+                        bail!("{} sent a packet on closed channel {}", sender, channel_id);
+                    }
+
+                    let (src, dest, receiver) = if channel.server.port_id == port_id {
+                        (channel.server, channel.client, self.client_contracts[channel.client_index].clone())
+                    } else {
+                        (channel.client, channel.server, self.server_contract.clone())
+                    };
+
+                    let sequence = self.ibc.next_sequence.entry(format!("{}/{}", src.port_id, src.channel_id)).or_insert(0);
+                    *sequence += 1;
+
+                    self.ibc.in_flight.push_back(TestIbcPacket {
+                        packet: IbcPacket::new(data, src, dest, *sequence, timeout),
+                        sender,
+                        receiver,
+                    });
+                },
+                msg => bail!("unsupported ibc message: {:?}", msg),
+            }
+        }
+        self.ibc.transfers.extend(self.ibc.recorder.drain_transfers());
+
+        Ok(())
+    }
+
+    fn next_channel_id(&mut self) -> String {
+        let id = self.ibc.next_channel_id;
+        self.ibc.next_channel_id += 1;
+        format!("channel-{}", id)
+    }
+
+    fn contract_kind(&self, addr: &Addr) -> ContractKind {
+        if *addr == self.server_contract {
+            ContractKind::Server
+        } else {
+            ContractKind::Client
+        }
+    }
+
+    // IBC entry points aren't reachable through multitest, so we call them directly on the contract's storage
+    fn with_contract_deps<T>(&mut self, addr: &Addr, f: impl FnOnce(DepsMut, Env) -> Result<T>) -> Result<T> {
+        let env = Env {
+            block: self.block_info(),
+            transaction: None,
+            contract: ContractInfo { address: addr.clone() },
+        };
+        let api = *self.api();
+        let querier = MockQuerier::<Empty>::new(&[]);
+        let mut storage = self.contract_storage_mut(addr);
+
+        f(DepsMut { storage: storage.as_mut(), api: &api, querier: QuerierWrapper::new(&querier) }, env)
+    }
+}
@@ -1,3 +1,8 @@
 pub mod app;
+pub mod ibc;
+pub mod ibc_hooks;
 pub mod multitest_contract_impls;
+pub mod nois;
+pub mod cw721;
+pub mod helpers;
 pub mod prelude;
\ No newline at end of file
@@ -6,7 +6,7 @@ use cosmwasm_std::{to_json_binary, Addr, Coin, CosmosMsg, WasmMsg};
 use serde::de::DeserializeOwned;
 use shared::msg::contract::{
     client::{ExecuteMsg as ClientExecuteMsg, QueryMsg as ClientQueryMsg},
-    server::QueryMsg as ServerQueryMsg,
+    server::{ExecuteMsg as ServerExecuteMsg, QueryMsg as ServerQueryMsg},
 };
 use cw_multi_test::{AppResponse, Executor};
 use anyhow::Result;
@@ -37,10 +37,11 @@ impl ContractAddress for TestAppContractClient {
 
 impl ContractQuery<ClientQueryMsg> for TestAppContractClient {
     async fn query<RESP: DeserializeOwned>(&mut self, msg: &ClientQueryMsg) -> Result<RESP> {
+        let address = self.address();
         self.app
-            .as_mut()
+            .as_ref()
             .wrap()
-            .query_wasm_smart(self.address(), &msg)
+            .query_wasm_smart(address, &msg)
             .map_err(|err| err.into())
     }
 }
@@ -79,7 +80,7 @@ pub struct TestAppContractServer {
 }
 
 // where it all gets tied together :)
-impl ContractServer for TestAppContractServer {}
+impl ContractServer<AppResponse> for TestAppContractServer {}
 
 impl TestAppContractServer {
     pub fn new(app: TestApp) -> Self {
@@ -95,10 +96,37 @@ impl ContractAddress for TestAppContractServer {
 
 impl ContractQuery<ServerQueryMsg> for TestAppContractServer {
     async fn query<RESP: DeserializeOwned>(&mut self, msg: &ServerQueryMsg) -> Result<RESP> {
+        let address = self.address();
         self.app
-            .as_mut()
+            .as_ref()
             .wrap()
-            .query_wasm_smart(self.address(), &msg)
+            .query_wasm_smart(address, &msg)
             .map_err(|err| err.into())
     }
-}
\ No newline at end of file
+}
+
+impl ContractExec<ServerExecuteMsg, AppResponse> for TestAppContractServer {
+    async fn exec(&mut self, msg: &ServerExecuteMsg) -> Result<AppResponse> {
+        let cosmos_msg = CosmosMsg::Wasm(
+            WasmMsg::Execute {
+                contract_addr: self.address(),
+                msg: to_json_binary(msg).unwrap(),
+                funds: vec![],
+            },
+        );
+
+        self.app.as_mut().execute(self.sender.clone(), cosmos_msg)
+    }
+
+    async fn exec_funds(&mut self, msg: &ServerExecuteMsg, funds: &[Coin]) -> Result<AppResponse> {
+        let cosmos_msg = CosmosMsg::Wasm(
+            WasmMsg::Execute {
+                contract_addr: self.address(),
+                msg: to_json_binary(msg).unwrap(),
+                funds: funds.to_vec(),
+            },
+        );
+
+        self.app.as_mut().execute(self.sender.clone(), cosmos_msg)
+    }
+}
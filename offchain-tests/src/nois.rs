@@ -0,0 +1,79 @@
+/// A stand-in for the Nois proxy, which on a real chain gets its randomness over IBC from the Nois chain
+/// requests are held until [TestAppInner::nois_fulfill_all], and round `n` is always `[n; 32]`
+/// so the outcome of a roll is known ahead of time
+use anyhow::Result;
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{from_json, to_json_binary, to_json_vec, wasm_execute, Addr, Binary, Deps, DepsMut, Empty, Env, HexBinary, MessageInfo, Response, StdResult};
+use cw_multi_test::{AppResponse, Executor};
+use shared::msg::{contract::client::ExecuteMsg as ClientExecuteMsg, nois::NoisCallback};
+
+use crate::app::TestAppInner;
+
+const PENDING_KEY: &[u8] = b"pending";
+const ROUND_KEY: &[u8] = b"round";
+
+/// A superset of [shared::msg::nois::NoisProxyExecuteMsg]
+#[cw_serde]
+pub enum MockNoisExecuteMsg {
+    GetNextRandomness {
+        job_id: String,
+    },
+    /// Delivers every pending request with the next round
+    FulfillAll { },
+}
+
+/// The randomness the mock publishes for the given round
+pub fn mock_nois_randomness(round: u8) -> HexBinary {
+    HexBinary::from(vec![round; 32])
+}
+
+pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: MockNoisExecuteMsg) -> StdResult<Response> {
+    let mut pending: Vec<(Addr, String)> = load(deps.as_ref(), PENDING_KEY)?;
+
+    match msg {
+        MockNoisExecuteMsg::GetNextRandomness { job_id } => {
+            pending.push((info.sender, job_id));
+            deps.storage.set(PENDING_KEY, &to_json_vec(&pending)?);
+
+            Ok(Response::default())
+        },
+        MockNoisExecuteMsg::FulfillAll {  } => {
+            let round = load::<u8>(deps.as_ref(), ROUND_KEY)? + 1;
+            deps.storage.set(ROUND_KEY, &to_json_vec(&round)?);
+            deps.storage.set(PENDING_KEY, &to_json_vec(&Vec::<(Addr, String)>::new())?);
+
+            let mut resp = Response::default();
+            for (requester, job_id) in pending {
+                resp = resp.add_message(wasm_execute(requester, &ClientExecuteMsg::NoisReceive {
+                    callback: NoisCallback {
+                        job_id,
+                        published: env.block.time,
+                        randomness: mock_nois_randomness(round),
+                    }
+                }, vec![])?);
+            }
+
+            Ok(resp)
+        }
+    }
+}
+
+pub fn instantiate(_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty) -> StdResult<Response> {
+    Ok(Response::default())
+}
+
+pub fn query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+    to_json_binary(&Empty {})
+}
+
+fn load<T: serde::de::DeserializeOwned + Default>(deps: Deps, key: &[u8]) -> StdResult<T> {
+    deps.storage.get(key).map_or(Ok(T::default()), |data| from_json(data))
+}
+
+impl TestAppInner {
+    /// Publishes the next round, settling every roll requested so far
+    pub fn nois_fulfill_all(&mut self) -> Result<AppResponse> {
+        let proxy = self.nois_proxy.clone();
+        self.execute_contract(Addr::unchecked("nois-relayer"), proxy, &MockNoisExecuteMsg::FulfillAll {}, &[])
+    }
+}
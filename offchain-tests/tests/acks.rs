@@ -0,0 +1,185 @@
+use app_tests::prelude::*;
+use cosmwasm_std::{Addr, Binary, Event, MemoryStorage, Order, Storage};
+use cw_utils::Expiration;
+use shared::{
+    ibc::{IbcAck, IbcAckErrorCode},
+    msg::{chat_message::{ChatMessage, ChatMessageKind, ChatMessageWithIndex}, content::MessageContent, ibc::{IbcAckSuccess, IbcExecuteMsg}, network::NetworkId},
+    response::ResponseBuilder,
+    storage::{transactional, StorageCache},
+};
+
+fn message(text: &str, kind: ChatMessageKind) -> IbcExecuteMsg {
+    IbcExecuteMsg::SendMessageToServer {
+        message: ChatMessageWithIndex {
+            msg: ChatMessage {
+                user: Addr::unchecked("sender"),
+                network_id: NetworkId::Kujira,
+                message: text.to_string(),
+                kind,
+                content: MessageContent::Text,
+                room: None,
+                attestation: None,
+                expires: Expiration::Never {},
+            },
+            index: 1u64.into(),
+        }
+    }
+}
+
+#[tokio::test]
+async fn acks_report_each_outcome() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+
+    app.as_mut().ibc_send_from_client(0, &message("gm", ChatMessageKind::Text)).unwrap();
+    let client_count = app.as_ref().client_contracts.len() as u32;
+    assert!(matches!(app.as_mut().ibc_relay_next().unwrap(), IbcAck::Success(IbcAckSuccess::MessageBroadcast { recipients, .. }) if recipients == client_count - 1));
+    app.as_mut().ibc_relay_all().unwrap();
+
+    // going the wrong way, and pretending to be the contracts
+    let wrong_way = match message("gm", ChatMessageKind::Text) {
+        IbcExecuteMsg::SendMessageToServer { message } => IbcExecuteMsg::SendMessageToClient { message: message.msg, id: None },
+        _ => unreachable!(),
+    };
+    app.as_mut().ibc_send_from_client(0, &wrong_way).unwrap();
+    app.as_mut().ibc_send_from_client(0, &message("<b>", ChatMessageKind::System)).unwrap();
+    let acks = app.as_mut().ibc_relay_all().unwrap();
+    assert_eq!(acks.len(), 2);
+    assert!(acks.iter().all(|ack| matches!(ack, IbcAck::Error(err) if err.code == IbcAckErrorCode::UnsupportedMessage)));
+    // and nothing went out for them
+    assert!(app.as_mut().ibc_in_flight().unwrap().is_empty());
+
+    // within a batch, only the item fails
+    app.as_mut().ibc_send_from_client(0, &IbcExecuteMsg::Batch {
+        msgs: vec![IbcExecuteMsg::Batch { msgs: vec![message("deep", ChatMessageKind::Text)] }],
+    }).unwrap();
+    assert!(matches!(app.as_mut().ibc_relay_next().unwrap(), IbcAck::Success(IbcAckSuccess::Batch { results }) if matches!(&results[..], [IbcAck::Error(_)])));
+    assert!(app.as_mut().ibc_in_flight().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn failed_batch_items_leave_nothing_behind() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+
+    app.as_mut().ibc_send_from_client(0, &IbcExecuteMsg::Batch {
+        msgs: vec![
+            message("one", ChatMessageKind::Text),
+            message("not yours to send", ChatMessageKind::System),
+            message("two", ChatMessageKind::Text),
+        ]
+    }).unwrap();
+
+    let ids = match app.as_mut().ibc_relay_next().unwrap() {
+        IbcAck::Success(IbcAckSuccess::Batch { results }) => results
+            .into_iter()
+            .map(|result| match result {
+                IbcAck::Success(IbcAckSuccess::MessageBroadcast { id, .. }) => Some(id.u64()),
+                _ => None,
+            })
+            .collect::<Vec<_>>(),
+        ack => panic!("expected a batch ack, got {:?}", ack),
+    };
+    // the failed one didn't take an id
+    assert_eq!(ids, vec![Some(1), None, Some(2)]);
+
+    app.as_mut().ibc_relay_all().unwrap();
+    let mut client = app.clone().into_contract_client();
+    client.id = 1;
+    let messages = client.query_chat_messages(None, Some(Order::Ascending)).await.unwrap().messages;
+    assert_eq!(messages.into_iter().map(|m| m.msg.message).collect::<Vec<_>>(), vec!["one", "two"]);
+}
+
+#[tokio::test]
+async fn garbled_acks_settle_as_errors() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    // the client's own message, the ack would have given it its id
+    let mut client = app.clone().into_contract_client();
+    client.exec_send_message("gm").await.unwrap();
+    let ack = app.as_mut().ibc_relay_next_with_ack(Binary::from(b"not an ack")).unwrap();
+    assert!(matches!(ack, IbcAck::Error(err) if err.code == IbcAckErrorCode::DecodeFailed));
+
+    // the server's fan-out is settled the same way
+    let ack = app.as_mut().ibc_relay_next_with_ack(Binary::from(b"{\"result\": 1}")).unwrap();
+    assert!(matches!(ack, IbcAck::Error(err) if err.code == IbcAckErrorCode::DecodeFailed));
+    app.as_mut().ibc_relay_all().unwrap();
+
+    // nothing is stuck, and the message is still there
+    assert!(app.as_mut().ibc_in_flight().unwrap().is_empty());
+    let messages = client.query_chat_messages(None, Some(Order::Ascending)).await.unwrap().messages;
+    assert_eq!(messages.into_iter().map(|m| m.msg.message).collect::<Vec<_>>(), vec!["gm"]);
+}
+
+// what every receive handler runs in, so a failure after a write can't leave it behind
+#[test]
+fn storage_cache_keeps_writes_to_itself() {
+    let mut storage = MemoryStorage::new();
+    storage.set(b"a", b"1");
+    storage.set(b"b", b"2");
+    storage.set(b"d", b"4");
+
+    let mut cache = StorageCache::new(&storage);
+    cache.set(b"c", b"3");
+    cache.set(b"a", b"10");
+    cache.remove(b"b");
+
+    assert_eq!(cache.get(b"a"), Some(b"10".to_vec()));
+    assert_eq!(cache.get(b"b"), None);
+    let keys = |order| cache.range(None, None, order).map(|(key, _)| key).collect::<Vec<_>>();
+    assert_eq!(keys(Order::Ascending), vec![b"a".to_vec(), b"c".to_vec(), b"d".to_vec()]);
+    assert_eq!(keys(Order::Descending), vec![b"d".to_vec(), b"c".to_vec(), b"a".to_vec()]);
+    assert_eq!(cache.range(Some(b"b"), Some(b"d"), Order::Ascending).count(), 1);
+    assert_eq!(cache.range(Some(b"d"), Some(b"b"), Order::Ascending).count(), 0);
+
+    // dropped, as when the handler fails
+    assert_eq!(storage.get(b"a"), Some(b"1".to_vec()));
+    assert_eq!(storage.get(b"c"), None);
+
+    // applied, as when it succeeds
+    let writes = cache.into_writes();
+    writes.apply(&mut storage);
+    assert_eq!(storage.get(b"a"), Some(b"10".to_vec()));
+    assert_eq!(storage.get(b"b"), None);
+    assert_eq!(storage.get(b"c"), Some(b"3".to_vec()));
+}
+
+#[test]
+fn transactional_keeps_only_what_succeeded() {
+    let mut storage = MemoryStorage::new();
+    let mut response = ResponseBuilder::new(cw2::ContractVersion { contract: "test".to_string(), version: "0".to_string() }, shared::contract_kind::ContractKind::Server);
+
+    transactional(&mut storage, &mut response, |store, response| {
+        store.set(b"a", b"1");
+        response.add_event(Event::new("kept"));
+        Ok(())
+    }).unwrap();
+    transactional::<()>(&mut storage, &mut response, |store, response| {
+        store.set(b"b", b"2");
+        response.add_event(Event::new("dropped"));
+        anyhow::bail!("failed")
+    }).unwrap_err();
+
+    assert_eq!(storage.get(b"a"), Some(b"1".to_vec()));
+    assert_eq!(storage.get(b"b"), None);
+    let events = response.into_response().events;
+    assert_eq!(events.iter().map(|e| e.ty.as_str()).collect::<Vec<_>>(), vec!["kept"]);
+}
+
+#[test]
+fn response_rolls_back_to_a_checkpoint() {
+    let mut response = ResponseBuilder::new(cw2::ContractVersion { contract: "test".to_string(), version: "0".to_string() }, shared::contract_kind::ContractKind::Server);
+    response.add_event(Event::new("kept"));
+
+    let checkpoint = response.checkpoint();
+    response.add_event(Event::new("kept"));
+    response.add_event(Event::new("dropped"));
+    response.rollback(checkpoint);
+
+    // the counter goes back too, so the next one is numbered as if nothing happened
+    response.add_event(Event::new("kept"));
+    let events = response.into_response().events;
+    assert_eq!(events.iter().map(|e| e.ty.as_str()).collect::<Vec<_>>(), vec!["kept", "kept-1"]);
+}
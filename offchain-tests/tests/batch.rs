@@ -0,0 +1,80 @@
+use app_tests::{helpers::client_texts, prelude::*};
+use cosmwasm_std::Addr;
+use cw_utils::Expiration;
+use shared::{
+    ibc::{IbcAck, IbcAckErrorCode},
+    msg::{chat_message::{ChatMessage, ChatMessageKind, ChatMessageWithIndex}, content::MessageContent, ibc::{IbcAckSuccess, IbcExecuteMsg}, network::NetworkId},
+};
+
+#[tokio::test]
+async fn buffered_messages_share_a_packet() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+
+    let mut client = app.clone().into_contract_client();
+    client.exec_buffer_message("one").await.unwrap();
+    client.exec_buffer_message("two").await.unwrap();
+    assert!(app.as_mut().ibc_in_flight().unwrap().is_empty());
+    assert_eq!(client.query_outbox().await.unwrap().buffered.len(), 2);
+
+    // sending flushes the buffer along with the new message, in order
+    client.exec_send_message("three").await.unwrap();
+    assert!(client.query_outbox().await.unwrap().buffered.is_empty());
+    assert_eq!(app.as_mut().ibc_in_flight().unwrap().len(), 1);
+
+    let ack = app.as_mut().ibc_relay_next().unwrap();
+    match ack {
+        IbcAck::Success(IbcAckSuccess::Batch { results }) => {
+            assert_eq!(results.len(), 3);
+            assert!(results.iter().all(|result| matches!(result, IbcAck::Success(IbcAckSuccess::MessageBroadcast { .. }))));
+        },
+        _ => panic!("expected a batch ack, got {:?}", ack),
+    }
+
+    // the server coalesced the fan-out, one packet per other client
+    let client_count = app.as_ref().client_contracts.len();
+    assert_eq!(app.as_mut().ibc_in_flight().unwrap().len(), client_count - 1);
+
+    app.as_mut().ibc_relay_all().unwrap();
+
+    for id in 1..client_count {
+        assert_eq!(client_texts(&app, id).await, vec!["one", "two", "three"]);
+    }
+}
+
+#[tokio::test]
+async fn batch_reports_partial_failure() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+
+    let message = ChatMessage {
+        user: Addr::unchecked("sender"),
+        network_id: NetworkId::Kujira,
+        message: "hello".to_string(),
+        kind: ChatMessageKind::Text,
+        content: MessageContent::Text,
+        room: None,
+        attestation: None,
+        expires: Expiration::Never {},
+    };
+
+    // the server never accepts messages meant for clients
+    app.as_mut().ibc_send_from_client(0, &IbcExecuteMsg::Batch {
+        msgs: vec![
+            IbcExecuteMsg::SendMessageToServer { message: ChatMessageWithIndex { msg: message.clone(), index: 1u64.into() } },
+            IbcExecuteMsg::SendMessageToClient { message, id: None },
+        ]
+    }).unwrap();
+
+    match app.as_mut().ibc_relay_next().unwrap() {
+        IbcAck::Success(IbcAckSuccess::Batch { results }) => {
+            assert!(matches!(results[0], IbcAck::Success(IbcAckSuccess::MessageBroadcast { .. })));
+            assert!(matches!(&results[1], IbcAck::Error(err) if err.code == IbcAckErrorCode::UnsupportedMessage));
+        },
+        ack => panic!("expected a batch ack, got {:?}", ack),
+    }
+
+    // the good item still went through
+    app.as_mut().ibc_relay_all().unwrap();
+    assert_eq!(client_texts(&app, 1).await, vec!["hello"]);
+}
@@ -0,0 +1,51 @@
+use app_tests::prelude::*;
+use cosmwasm_std::Addr;
+use shared::msg::network::NetworkId;
+
+#[tokio::test]
+async fn blocked_senders_are_hidden_from_the_viewer() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+
+    // the same address on another chain is someone else
+    let mut other = app.clone().into_contract_client();
+    other.id = 1;
+    other.exec_send_message("spam").await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    let mut client = app.clone().into_contract_client();
+    client.exec_send_message("hello").await.unwrap();
+
+    client.sender = Addr::unchecked("viewer");
+    client.exec_block(NetworkId::Stargaze, "sender").await.unwrap();
+    assert_eq!(client.query_blocked("viewer").await.unwrap().blocked.len(), 1);
+
+    let visible = client.query_chat_messages_for("viewer", None, None, None).await.unwrap().messages;
+    assert_eq!(visible.iter().map(|m| m.msg.message.as_str()).collect::<Vec<_>>(), vec!["hello"]);
+
+    // everyone else still sees everything
+    assert_eq!(client.query_chat_messages(None, None).await.unwrap().messages.len(), 2);
+
+    client.exec_unblock(NetworkId::Stargaze, "sender").await.unwrap();
+    assert_eq!(client.query_chat_messages_for("viewer", None, None, None).await.unwrap().messages.len(), 2);
+}
+
+#[tokio::test]
+async fn pages_stay_full() {
+    let app = TestApp::new();
+
+    let mut client = app.clone().into_contract_client();
+    for i in 0..6 {
+        client.sender = Addr::unchecked(if i % 2 == 0 { "spammer" } else { "friend" });
+        client.exec_send_message(format!("message {}", i)).await.unwrap();
+    }
+
+    client.sender = Addr::unchecked("viewer");
+    client.exec_block(NetworkId::Kujira, "spammer").await.unwrap();
+
+    let page = client.query_chat_messages_for("viewer", None, None, Some(2)).await.unwrap().messages;
+    assert_eq!(page.iter().map(|m| m.msg.message.as_str()).collect::<Vec<_>>(), vec!["message 1", "message 3"]);
+
+    let page = client.query_chat_messages_for("viewer", Some(page[1].index), None, Some(2)).await.unwrap().messages;
+    assert_eq!(page.iter().map(|m| m.msg.message.as_str()).collect::<Vec<_>>(), vec!["message 5"]);
+}
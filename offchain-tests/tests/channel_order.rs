@@ -0,0 +1,153 @@
+use app_tests::{helpers::client_texts, prelude::*};
+use shared::msg::misc::ChannelOrder;
+
+#[tokio::test]
+async fn unordered_timeout_loses_only_that_packet() {
+    let app = TestApp::new_with_order(ChannelOrder::Unordered);
+    app.as_mut().ibc_connect_all().unwrap();
+
+    let mut client = app.clone().into_contract_client();
+    client.exec_send_message("lost").await.unwrap();
+    app.as_mut().ibc_timeout_next().unwrap();
+
+    // channel is still usable
+    let info = client.query_info().await.unwrap();
+    assert!(info.server_channel.is_some());
+    assert!(info.dead_server_channel.is_none());
+
+    client.exec_send_message("delivered").await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    assert_eq!(client_texts(&app, 1).await, vec!["delivered"]);
+}
+
+#[tokio::test]
+async fn ordered_client_timeout_queues_until_reconnect() {
+    let app = TestApp::new_with_order(ChannelOrder::Ordered);
+    app.as_mut().ibc_connect_all().unwrap();
+
+    let mut client = app.clone().into_contract_client();
+    client.exec_send_message("first").await.unwrap();
+    app.as_mut().ibc_timeout_next().unwrap();
+
+    let info = client.query_info().await.unwrap();
+    assert!(info.server_channel.is_none());
+    assert!(info.dead_server_channel.is_some());
+
+    // accepted and queued while the channel is dead
+    client.exec_send_message("second").await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+    assert!(client_texts(&app, 1).await.is_empty());
+
+    // a new channel resumes in order
+    app.as_mut().ibc_connect_client(0).unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    assert_eq!(client_texts(&app, 1).await, vec!["first", "second"]);
+    assert_eq!(client_texts(&app, 2).await, vec!["first", "second"]);
+}
+
+#[tokio::test]
+async fn ordered_server_timeout_queues_for_that_client() {
+    let app = TestApp::new_with_order(ChannelOrder::Ordered);
+    app.as_mut().ibc_connect_all().unwrap();
+
+    let mut client = app.clone().into_contract_client();
+    client.exec_send_message("first").await.unwrap();
+
+    // the server receives it and fans out, the packet to client 1 times out
+    app.as_mut().ibc_relay_next().unwrap();
+    app.as_mut().ibc_timeout_next().unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    let info = app.clone().into_contract_server().query_info().await.unwrap();
+    assert_eq!(info.dead_client_channels.len(), 1);
+    assert!(client_texts(&app, 1).await.is_empty());
+    assert_eq!(client_texts(&app, 2).await, vec!["first"]);
+
+    client.exec_send_message("second").await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    app.as_mut().ibc_connect_client(1).unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    assert_eq!(client_texts(&app, 1).await, vec!["first", "second"]);
+    let info = app.clone().into_contract_server().query_info().await.unwrap();
+    assert!(info.dead_client_channels.is_empty());
+}
+
+#[tokio::test]
+async fn client_resends_timed_out_packets_before_later_ones() {
+    let app = TestApp::new_with_order(ChannelOrder::Ordered);
+    app.as_mut().ibc_connect_all().unwrap();
+
+    let mut client = app.clone().into_contract_client();
+    client.exec_send_message("first").await.unwrap();
+    client.exec_send_message("second").await.unwrap();
+
+    // "second" is still waiting for its timeout when "third" is queued
+    app.as_mut().ibc_timeout_next_alone().unwrap();
+    client.exec_send_message("third").await.unwrap();
+    app.as_mut().ibc_timeout_stuck().unwrap();
+
+    let outbox = client.query_outbox().await.unwrap().messages;
+    assert_eq!(outbox.into_iter().map(|m| m.msg.message).collect::<Vec<_>>(), vec!["first", "second", "third"]);
+
+    app.as_mut().ibc_connect_client(0).unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+    assert_eq!(client_texts(&app, 1).await, vec!["first", "second", "third"]);
+}
+
+#[tokio::test]
+async fn server_resends_timed_out_packets_before_later_ones() {
+    let app = TestApp::new_with_order(ChannelOrder::Ordered);
+    app.as_mut().ibc_connect_all().unwrap();
+
+    let mut client = app.clone().into_contract_client();
+    let mut other_client = app.clone().into_contract_client();
+    other_client.id = 2;
+    client.exec_send_message("first").await.unwrap();
+    other_client.exec_send_message("second").await.unwrap();
+
+    // the server fans both out, so two packets are on their way to client 1
+    app.as_mut().ibc_relay_next().unwrap();
+    app.as_mut().ibc_relay_next().unwrap();
+
+    // "second" is still waiting for its timeout when "third" is queued
+    app.as_mut().ibc_timeout_next_alone().unwrap();
+    client.exec_send_message("third").await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+    app.as_mut().ibc_timeout_stuck().unwrap();
+    assert!(client_texts(&app, 1).await.is_empty());
+
+    app.as_mut().ibc_connect_client(1).unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+    assert_eq!(client_texts(&app, 1).await, vec!["first", "second", "third"]);
+}
+
+#[tokio::test]
+async fn server_forgets_a_client_that_stays_away() {
+    let app = TestApp::new_with_order(ChannelOrder::Ordered);
+    app.as_mut().ibc_connect_all().unwrap();
+
+    let mut client = app.clone().into_contract_client();
+    client.exec_send_message("first").await.unwrap();
+    app.as_mut().ibc_relay_next().unwrap();
+    app.as_mut().ibc_timeout_next().unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    // one queued packet per message, one more than there's room for
+    for i in 0..=1000 {
+        client.exec_send_message(format!("while you were away {}", i)).await.unwrap();
+        app.as_mut().ibc_relay_all().unwrap();
+    }
+    let info = app.clone().into_contract_server().query_info().await.unwrap();
+    assert!(info.dead_client_channels.is_empty());
+
+    // back on a new channel, it only gets what comes after
+    app.as_mut().ibc_connect_client(1).unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+    client.exec_send_message("welcome back").await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+    assert_eq!(client_texts(&app, 1).await, vec!["welcome back"]);
+}
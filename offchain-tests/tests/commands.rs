@@ -0,0 +1,64 @@
+use app_tests::{helpers::client_messages, prelude::*};
+use shared::msg::chat_message::ChatMessageKind;
+
+#[tokio::test]
+async fn local_commands() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+
+    let mut client = app.clone().into_contract_client();
+    client.exec_send_message("/nick alice").await.unwrap();
+    client.exec_send_message("/topic rust and ibc").await.unwrap();
+    client.exec_send_message("/help").await.unwrap();
+
+    assert_eq!(client.query_profile("sender").await.unwrap().nickname.as_deref(), Some("alice"));
+    // the topic is the owner's
+    assert!(client.query_info().await.unwrap().topic.is_none());
+    client.sender = Addr::unchecked("client-admin");
+    client.exec_send_message("/topic rust and ibc").await.unwrap();
+    assert_eq!(client.query_info().await.unwrap().topic.as_deref(), Some("rust and ibc"));
+
+    let messages = client_messages(&app, 0).await;
+    assert_eq!(messages.len(), 4);
+    assert!(messages.iter().all(|m| m.msg.kind == ChatMessageKind::System && m.msg.user.as_str() == client.address()));
+    assert_eq!(messages[1].msg.message, "only the owner can set the topic");
+
+    // none of it went to the server
+    assert!(app.as_mut().ibc_in_flight().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn bad_commands_leave_a_notice() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+
+    let mut client = app.clone().into_contract_client();
+    client.exec_send_message("/dance").await.unwrap();
+    client.exec_send_message("/nick").await.unwrap();
+
+    let messages = client_messages(&app, 0).await;
+    assert_eq!(messages.len(), 2);
+    assert!(messages[0].msg.message.contains("unknown command /dance"));
+    assert!(messages[1].msg.message.contains("usage: /nick"));
+    assert!(client.query_profile("sender").await.unwrap().nickname.is_none());
+
+    assert!(app.as_mut().ibc_in_flight().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn me_is_broadcast_as_an_action() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+
+    let mut client = app.clone().into_contract_client();
+    client.exec_send_message("/me waves").await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    let client_count = app.as_ref().client_contracts.len();
+    for id in 0..client_count {
+        let messages = client_messages(&app, id).await;
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].msg.kind, ChatMessageKind::Action);
+        assert_eq!(messages[0].msg.message, "waves");
+    }
+}
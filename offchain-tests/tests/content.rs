@@ -0,0 +1,129 @@
+use app_tests::prelude::*;
+use cosmwasm_std::{from_json, to_json_string, Addr, Uint128};
+use cw_utils::Expiration;
+use shared::{
+    ibc::{IbcAck, IbcAckErrorCode, IbcProtocolVersion},
+    msg::{
+        chat_message::{ChatMessage, ChatMessageKind, ChatMessageWithIndex},
+        content::{LinkPreview, MessageContent},
+        ibc::IbcExecuteMsg,
+        network::NetworkId,
+    },
+};
+
+fn link() -> MessageContent {
+    MessageContent::Link {
+        url: "https://example.com/post/1".to_string(),
+        preview: Some(LinkPreview {
+            title: "A post".to_string(),
+            description: Some("about things".to_string()),
+            image: None,
+        }),
+    }
+}
+
+#[tokio::test]
+async fn content_reaches_every_client() {
+    let app = TestApp::new();
+    let client_count = app.as_ref().client_contracts.len();
+
+    // both encodings carry it
+    app.as_mut().ibc_connect_client_with_version(0, IbcProtocolVersion::V1.as_str()).unwrap();
+    for id in 1..client_count {
+        app.as_mut().ibc_connect_client(id).unwrap();
+    }
+
+    let attachment = MessageContent::Attachment {
+        cid: "bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi".to_string(),
+        mime: "image/png".to_string(),
+        size: 52_000,
+    };
+    let token = MessageContent::Token { denom: "ukuji".to_string(), amount: Some(Uint128::new(1_000_000)) };
+
+    let mut client = app.clone().into_contract_client();
+    client.exec_send_content_message(None, "check this", link()).await.unwrap();
+    client.id = 1;
+    client.exec_send_content_message(None, "the picture", attachment.clone()).await.unwrap();
+    client.exec_send_content_message(None, "**bold** claim", MessageContent::Markdown).await.unwrap();
+    client.exec_send_content_message(None, "price", token.clone()).await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    for id in 0..client_count {
+        client.id = id;
+        let mut contents = client.query_chat_messages(None, None).await.unwrap()
+            .messages
+            .into_iter()
+            .map(|m| m.msg.content)
+            .collect::<Vec<_>>();
+        contents.sort_by_key(|content| content.to_string());
+        assert_eq!(contents, vec![attachment.clone(), link(), MessageContent::Markdown, token.clone()]);
+    }
+}
+
+#[tokio::test]
+async fn each_content_has_rules() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    let mut client = app.clone().into_contract_client();
+
+    let bad = vec![
+        (MessageContent::Link { url: "javascript:alert(1)".to_string(), preview: None }, "https://"),
+        (MessageContent::Attachment { cid: "not-a-cid".to_string(), mime: "image/png".to_string(), size: 1 }, "invalid cid"),
+        (MessageContent::Attachment { cid: "QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG".to_string(), mime: "png".to_string(), size: 1 }, "invalid mime"),
+        (MessageContent::Token { denom: "ukuji".to_string(), amount: Some(Uint128::zero()) }, "instead of zero"),
+        (MessageContent::Token { denom: "$$".to_string(), amount: None }, "invalid denom"),
+        (MessageContent::Notice, "only the contract"),
+    ];
+    for (content, err) in bad {
+        let result = client.exec_send_content_message(None, "hi", content).await.unwrap_err();
+        assert!(format!("{:?}", result).contains(err), "{:?}", result);
+    }
+
+    let err = client.exec_send_content_message(None, "<script>hi</script>", MessageContent::Markdown).await.unwrap_err();
+    assert!(format!("{:?}", err).contains("HTML"));
+
+    // a markdown slash is just text
+    client.exec_send_content_message(None, "/nick *fancy*", MessageContent::Markdown).await.unwrap();
+    assert_eq!(client.query_profile("sender").await.unwrap().nickname, None);
+
+    // and the contract's own messages are notices
+    client.exec_send_message("/help").await.unwrap();
+    let messages = client.query_chat_messages(None, None).await.unwrap().messages;
+    assert_eq!(messages[0].msg.content, MessageContent::Markdown);
+    assert_eq!(messages[1].msg.content, MessageContent::Notice);
+}
+
+#[tokio::test]
+async fn server_checks_content() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+
+    // a client that didn't check
+    let message = ChatMessage {
+        user: Addr::unchecked("sender"),
+        network_id: NetworkId::Kujira,
+        message: "official".to_string(),
+        kind: ChatMessageKind::Text,
+        content: MessageContent::Notice,
+        room: None,
+        attestation: None,
+        expires: Expiration::Never {},
+    };
+    app.as_mut().ibc_send_from_client(0, &IbcExecuteMsg::SendMessageToServer {
+        message: ChatMessageWithIndex { msg: message, index: 1u64.into() },
+    }).unwrap();
+
+    let acks = app.as_mut().ibc_relay_all().unwrap();
+    assert_eq!(acks.len(), 1);
+    assert!(matches!(&acks[0], IbcAck::Error(err) if err.code == IbcAckErrorCode::Rejected));
+}
+
+#[test]
+fn old_messages_are_text() {
+    let old = r#"{"user":"sender","network_id":"kujira","message":"gm"}"#;
+    let message: ChatMessage = from_json(old.as_bytes()).unwrap();
+    assert_eq!(message.content, MessageContent::Text);
+
+    // and plain text still looks the same
+    assert_eq!(to_json_string(&message).unwrap(), old);
+}
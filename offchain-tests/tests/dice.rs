@@ -0,0 +1,90 @@
+use app_tests::{nois::mock_nois_randomness, prelude::*};
+use cosmwasm_std::{coins, Addr, HexBinary, Order, Timestamp};
+use cw_multi_test::BankSudo;
+use shared::msg::{chat_message::ChatMessageKind, contract::client::ExecuteMsg as ClientExecuteMsg, dice::Dice, nois::NoisCallback};
+
+async fn last_message(client: &mut impl ContractClient<cw_multi_test::AppResponse>) -> String {
+    client.query_chat_messages(None, Some(Order::Descending)).await.unwrap().messages.remove(0).msg.message
+}
+
+#[tokio::test]
+async fn roll_settles_with_nois_randomness() {
+    let app = TestApp::new();
+
+    let mut client = app.clone().into_contract_client();
+    client.exec_send_message("/roll 2d6").await.unwrap();
+
+    // nothing until the round is published
+    assert!(client.query_chat_messages(None, None).await.unwrap().messages.is_empty());
+
+    app.as_mut().nois_fulfill_all().unwrap();
+
+    let messages = client.query_chat_messages(None, None).await.unwrap().messages;
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].msg.kind, ChatMessageKind::System);
+
+    // the same randomness gives the same roll, anyone can check it
+    let randomness = mock_nois_randomness(1);
+    let values = Dice { count: 2, sides: 6 }.roll(randomness.as_slice()).unwrap();
+    let message = &messages[0].msg.message;
+    assert!(message.starts_with(&format!("sender rolled 2d6: {} + {} = {}", values[0], values[1], values[0] + values[1])));
+    assert!(message.contains(&randomness.to_hex()));
+}
+
+#[tokio::test]
+async fn bad_rolls_leave_a_notice() {
+    let app = TestApp::new();
+
+    let mut client = app.clone().into_contract_client();
+    client.exec_send_message("/roll 0d6").await.unwrap();
+    assert!(last_message(&mut client).await.contains("roll between 1 and"));
+
+    client.sender = Addr::unchecked("client-admin");
+    client.exec(&ClientExecuteMsg::SetNoisProxy { proxy: None }).await.unwrap();
+
+    // the fee sent along goes back
+    let roller = app.as_ref().api().addr_make("roller");
+    app.as_mut().sudo(BankSudo::Mint { to_address: roller.to_string(), amount: coins(100, "ukuji") }.into()).unwrap();
+    client.sender = roller.clone();
+    let send = |message: &str| ClientExecuteMsg::SendMessage { message: message.to_string(), room: None, expires: None, content: None };
+    client.exec_funds(&send("/roll"), &coins(10, "ukuji")).await.unwrap();
+    assert!(last_message(&mut client).await.contains("need a Nois proxy"));
+    assert_eq!(app.as_ref().wrap().query_balance(&roller, "ukuji").unwrap().amount.u128(), 100);
+
+    // same for a command that has no use for them
+    client.exec_funds(&send("/help"), &coins(10, "ukuji")).await.unwrap();
+    assert_eq!(app.as_ref().wrap().query_balance(&roller, "ukuji").unwrap().amount.u128(), 100);
+    assert!(app.as_ref().wrap().query_all_balances(app.as_ref().client_contracts[0].as_str()).unwrap().is_empty());
+}
+
+#[test]
+fn rolls_skip_biased_bytes() {
+    let d100 = Dice { count: 3, sides: 100 };
+    // 200 and up would make 1 to 56 more likely than the rest
+    let mut randomness = vec![199, 200, 255, 7, 0];
+    randomness.resize(32, 250);
+    assert_eq!(d100.roll(&randomness).unwrap(), vec![100, 8, 1]);
+
+    // with nothing usable in the round it goes on from its hash, the same way every time
+    let values = d100.roll(&[250; 32]).unwrap();
+    assert_eq!(values.len(), 3);
+    assert!(values.iter().all(|value| (1..=100).contains(value)));
+    assert_eq!(d100.roll(&[250; 32]).unwrap(), values);
+
+    assert!(d100.roll(&[0; 16]).is_err());
+}
+
+#[tokio::test]
+async fn only_the_proxy_delivers_randomness() {
+    let app = TestApp::new();
+
+    let mut client = app.clone().into_contract_client();
+    client.exec_send_message("/roll").await.unwrap();
+
+    let callback = NoisCallback {
+        job_id: "roll-1".to_string(),
+        published: Timestamp::from_seconds(1),
+        randomness: HexBinary::from(vec![0; 32]),
+    };
+    assert!(client.exec(&ClientExecuteMsg::NoisReceive { callback }).await.is_err());
+}
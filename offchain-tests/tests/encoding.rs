@@ -0,0 +1,99 @@
+use app_tests::prelude::*;
+use cosmwasm_std::{Addr, Order, Timestamp};
+use cw_utils::Expiration;
+use shared::{
+    ibc::IbcProtocolVersion,
+    msg::{chat_message::{ChatMessage, ChatMessageKind, ChatMessageWithIndex}, content::{LinkPreview, MessageContent}, contract::client::ChatMessagesResp, ibc::IbcExecuteMsg, network::NetworkId, room::RoomAttestation},
+};
+
+fn chat_message(message: &str) -> ChatMessage {
+    ChatMessage {
+        user: Addr::unchecked("stars1lfwqdsmzqjldsgnhpzzlm6uhr8x5ff6k6d6efvdvdqj7jhu4jq8s4eq03v"),
+        network_id: NetworkId::Stargaze,
+        message: message.to_string(),
+        kind: ChatMessageKind::Text,
+        content: MessageContent::Text,
+        room: None,
+        attestation: None,
+        expires: Expiration::Never {},
+    }
+}
+
+#[test]
+fn packet_sizes() {
+    let short = chat_message("gm");
+    let long = chat_message(&"the quick brown fox jumps over the lazy dog ".repeat(5));
+    let gated = ChatMessage {
+        room: Some("stargaze/punks".to_string()),
+        attestation: Some(RoomAttestation::Cw721 {
+            collection: Addr::unchecked("stars1kzqmtzlfq2qsnw0wmhsl6ky5cqtmlq3xvxwj2cmlpv5xhxmjmtgqmcjs8j"),
+            token_id: "1234".to_string(),
+            height: 12_345_678,
+        }),
+        expires: Expiration::AtTime(Timestamp::from_seconds(1_700_000_000)),
+        ..chat_message("gm holders")
+    };
+    let link = ChatMessage {
+        content: MessageContent::Link {
+            url: "https://stargaze.zone/m/punks/1234".to_string(),
+            preview: Some(LinkPreview { title: "Punk #1234".to_string(), description: None, image: None }),
+        },
+        ..chat_message("look at this one")
+    };
+
+    // the proto sizes are pinned, a change to them is a change to the wire format
+    let cases = vec![
+        (IbcExecuteMsg::SendMessageToServer { message: ChatMessageWithIndex { msg: short.clone(), index: 42u64.into() } }, 78),
+        (IbcExecuteMsg::SendMessageToClient { message: short.clone(), id: Some(1_234u64.into()) }, 79),
+        (IbcExecuteMsg::SendMessageToClient { message: short.clone(), id: None }, 74),
+        (IbcExecuteMsg::SendMessageToClient { message: long.clone(), id: Some(1_234u64.into()) }, 300),
+        (IbcExecuteMsg::SendMessageToClient { message: gated.clone(), id: Some(1_234u64.into()) }, 196),
+        (IbcExecuteMsg::SendMessageToClient { message: link.clone(), id: Some(1_234u64.into()) }, 149),
+        (IbcExecuteMsg::Batch { msgs: vec![IbcExecuteMsg::SendMessageToClient { message: short.clone(), id: Some(1_234u64.into()) }; 10] }, 813),
+    ];
+
+    for (msg, proto_len) in cases {
+        let json = msg.encode(IbcProtocolVersion::V1).unwrap();
+        let proto = msg.encode(IbcProtocolVersion::V2).unwrap();
+
+        // round trips both ways
+        assert_eq!(IbcExecuteMsg::decode(IbcProtocolVersion::V1, &json).unwrap(), msg);
+        assert_eq!(IbcExecuteMsg::decode(IbcProtocolVersion::V2, &proto).unwrap(), msg);
+
+        // and the compact one is actually compact
+        assert_eq!(proto.len(), proto_len);
+        assert!(proto.len() < json.len());
+    }
+}
+
+#[tokio::test]
+async fn json_fallback_for_older_peers() {
+    let app = TestApp::new();
+
+    let client_count = app.as_ref().client_contracts.len();
+
+    // client 0 only speaks json, the rest get the compact encoding
+    app.as_mut().ibc_connect_client_with_version(0, IbcProtocolVersion::V1.as_str()).unwrap();
+    for id in 1..client_count {
+        app.as_mut().ibc_connect_client(id).unwrap();
+    }
+
+    let mut client = app.clone().into_contract_client();
+    assert_eq!(client.query_info().await.unwrap().server_channel.unwrap().version, IbcProtocolVersion::V1.as_str());
+
+    client.id = 1;
+    assert_eq!(client.query_info().await.unwrap().server_channel.unwrap().version, IbcProtocolVersion::V2.as_str());
+
+    client.exec_send_message("compact").await.unwrap();
+    client.id = 0;
+    client.exec_send_message("json").await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    for id in 0..client_count {
+        client.id = id;
+        let ChatMessagesResp { messages, .. } = client.query_chat_messages(None, Some(Order::Ascending)).await.unwrap();
+        let mut texts = messages.into_iter().map(|m| m.msg.message).collect::<Vec<_>>();
+        texts.sort();
+        assert_eq!(texts, vec!["compact", "json"]);
+    }
+}
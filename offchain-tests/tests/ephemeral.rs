@@ -0,0 +1,79 @@
+use app_tests::{helpers::{client_texts, user}, prelude::*};
+use cosmwasm_std::coins;
+use cw_multi_test::BankSudo;
+use cw_utils::Expiration;
+
+#[tokio::test]
+async fn expired_messages_disappear_everywhere() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    let expires = Expiration::AtTime(app.as_ref().block_info().time.plus_seconds(60));
+
+    let mut client = app.clone().into_contract_client();
+    client.exec_send_expiring_message("invite code: 1234", expires).await.unwrap();
+    client.exec_send_message("hello").await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    let mut other = app.clone().into_contract_client();
+    other.id = 1;
+    let messages = other.query_chat_messages(None, None).await.unwrap().messages;
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0].msg.expires, expires);
+
+    app.as_mut().update_block(|block| block.time = block.time.plus_seconds(60));
+
+    for client in [&mut client, &mut other] {
+        let messages = client.query_chat_messages(None, None).await.unwrap().messages;
+        assert_eq!(messages.iter().map(|m| m.msg.message.as_str()).collect::<Vec<_>>(), vec!["hello"]);
+    }
+
+    // the next message cleans up, and doesn't take the old index
+    client.exec_send_message("later").await.unwrap();
+    let messages = client.query_chat_messages(None, None).await.unwrap().messages;
+    assert_eq!(messages.last().unwrap().index.u64(), 3);
+}
+
+#[tokio::test]
+async fn heights_stay_on_their_chain() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    let height = app.as_ref().block_info().height;
+
+    let mut client = app.clone().into_contract_client();
+    client.exec_send_expiring_message("soon", Expiration::AtHeight(height + 10)).await.unwrap();
+    assert!(app.as_mut().ibc_in_flight().unwrap().is_empty());
+    assert_eq!(client_texts(&app, 0).await, vec!["soon"]);
+    assert!(client_texts(&app, 1).await.is_empty());
+
+    app.as_mut().update_block(|block| block.height += 10);
+    assert!(client_texts(&app, 0).await.is_empty());
+
+    let height = app.as_ref().block_info().height;
+    assert!(client.exec_send_expiring_message("too late", Expiration::AtHeight(height)).await.is_err());
+    let now = app.as_ref().block_info().time;
+    assert!(client.exec_send_expiring_message("too late", Expiration::AtTime(now)).await.is_err());
+}
+
+#[tokio::test]
+async fn expired_messages_take_their_mentions_and_tips_along() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    let (bob, mut bob_client) = user(&app, 0, 1);
+    let (_, mut alice_client) = user(&app, 0, 2);
+    let (tipper, mut tipper_client) = user(&app, 0, 3);
+    app.as_mut().sudo(BankSudo::Mint { to_address: tipper, amount: coins(10, "ukuji") }.into()).unwrap();
+
+    let expires = Expiration::AtTime(app.as_ref().block_info().time.plus_seconds(60));
+    alice_client.exec_send_expiring_message(&format!("psst @{}", bob), expires).await.unwrap();
+    tipper_client.exec_tip(1u64.into(), &coins(10, "ukuji")).await.unwrap();
+    assert_eq!(bob_client.query_unread_mentions(&bob).await.unwrap().count, 1);
+    assert_eq!(bob_client.query_message_tips(1u64.into()).await.unwrap().total, coins(10, "ukuji"));
+
+    // the sweep comes with the next message
+    app.as_mut().update_block(|block| block.time = block.time.plus_seconds(60));
+    alice_client.exec_send_message("later").await.unwrap();
+
+    assert_eq!(bob_client.query_unread_mentions(&bob).await.unwrap().count, 0);
+    assert!(bob_client.query_mentions(&bob, false).await.unwrap().mentions.is_empty());
+    assert!(bob_client.query_message_tips(1u64.into()).await.unwrap().total.is_empty());
+}
@@ -0,0 +1,58 @@
+use cosmwasm_std::coins;
+use prost::Message;
+use serde_json::json;
+use wallet::feegrant::{
+    grant_allowance_msg, granted_fee, AllowedMsgAllowance, BasicAllowance, MsgGrantAllowance, ProtoCoin, ALLOWED_MSG_ALLOWANCE, BASIC_ALLOWANCE,
+};
+
+const EXECUTE: &str = "/cosmwasm.wasm.v1.MsgExecuteContract";
+
+#[test]
+fn grant_allowance_is_encoded_for_feegrant() {
+    let msg = grant_allowance_msg("stars1sponsor", "stars1newuser", &coins(1000, "ukuji"), Some(1_700_000_000), vec![EXECUTE.to_string()]).unwrap();
+
+    let msg = MsgGrantAllowance::decode(msg.encode_to_vec().as_slice()).unwrap();
+    assert_eq!(msg.granter, "stars1sponsor");
+    assert_eq!(msg.grantee, "stars1newuser");
+
+    // only for executes
+    let allowance = msg.allowance.unwrap();
+    assert_eq!(allowance.type_url, ALLOWED_MSG_ALLOWANCE);
+    let allowed = AllowedMsgAllowance::decode(allowance.value.as_slice()).unwrap();
+    assert_eq!(allowed.allowed_messages, vec![EXECUTE]);
+
+    // of a basic allowance, written out by hand so the field numbers are checked too
+    let basic = allowed.allowance.unwrap();
+    assert_eq!(basic.type_url, BASIC_ALLOWANCE);
+    let mut expected = vec![0x0a, 13, 0x0a, 5];
+    expected.extend_from_slice(b"ukuji");
+    expected.extend_from_slice(&[0x12, 4]);
+    expected.extend_from_slice(b"1000");
+    expected.extend_from_slice(&[0x12, 6, 0x08, 0x80, 0xe2, 0xcf, 0xaa, 0x06]);
+    assert_eq!(basic.value, expected);
+
+    let basic = BasicAllowance::decode(basic.value.as_slice()).unwrap();
+    assert_eq!(basic.spend_limit, vec![ProtoCoin { denom: "ukuji".to_string(), amount: "1000".to_string() }]);
+    assert_eq!(basic.expiration.unwrap().seconds, 1_700_000_000);
+
+    // no expiry, no field
+    let msg = grant_allowance_msg("stars1sponsor", "stars1newuser", &coins(1000, "ukuji"), None, vec![EXECUTE.to_string()]).unwrap();
+    let allowed = AllowedMsgAllowance::decode(msg.allowance.unwrap().value.as_slice()).unwrap();
+    assert!(BasicAllowance::decode(allowed.allowance.unwrap().value.as_slice()).unwrap().expiration.is_none());
+}
+
+#[test]
+fn granted_fee_names_the_granter() {
+    let fee = granted_fee(100_000, "0.025", "ustars", "stars1sponsor").unwrap();
+
+    // as cosmjs takes it, with the usual margin on the simulated gas
+    assert_eq!(serde_json::to_value(&fee).unwrap(), json!({
+        "amount": [{ "denom": "ustars", "amount": "3500" }],
+        "gas": "140000",
+        "granter": "stars1sponsor",
+    }));
+
+    // rounded up, so it's never short
+    assert_eq!(granted_fee(1, "0.025", "ustars", "stars1sponsor").unwrap().amount, coins(1, "ustars"));
+    assert!(granted_fee(1, "free", "ustars", "stars1sponsor").is_err());
+}
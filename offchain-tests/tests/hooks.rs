@@ -0,0 +1,116 @@
+use app_tests::prelude::*;
+use cosmwasm_std::{from_json, to_json_binary, to_json_vec, Addr, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdError, StdResult};
+use cw_multi_test::{ContractWrapper, Executor};
+use shared::msg::{chat_message::ChatMessageWithIndex, hook::{ChatHookMsg, HookFilter}};
+
+const RECEIVED_KEY: &[u8] = b"received";
+
+// a hook contract that keeps every message it's sent
+fn recorder_execute(deps: DepsMut, _env: Env, _info: MessageInfo, msg: ChatHookMsg) -> StdResult<Response> {
+    let ChatHookMsg::ChatHook { message } = msg;
+    let mut received = received(deps.as_ref())?;
+    received.push(message);
+    deps.storage.set(RECEIVED_KEY, &to_json_vec(&received)?);
+    Ok(Response::default())
+}
+
+// a hook contract that's broken
+fn failing_execute(_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: ChatHookMsg) -> StdResult<Response> {
+    Err(StdError::generic_err("hook is broken"))
+}
+
+fn hook_instantiate(_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty) -> StdResult<Response> {
+    Ok(Response::default())
+}
+
+fn hook_query(deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+    to_json_binary(&received(deps)?)
+}
+
+fn received(deps: Deps) -> StdResult<Vec<ChatMessageWithIndex>> {
+    deps.storage.get(RECEIVED_KEY).map_or(Ok(Vec::new()), from_json)
+}
+
+fn instantiate_hook(app: &TestApp, failing: bool) -> Addr {
+    let execute = if failing { failing_execute } else { recorder_execute };
+    let mut app = app.as_mut();
+    let code_id = app.store_code(Box::new(ContractWrapper::new(execute, hook_instantiate, hook_query)));
+    app.instantiate_contract(code_id, Addr::unchecked("hook-admin"), &Empty {}, &[], "hook", None).unwrap()
+}
+
+fn hook_messages(app: &TestApp, hook: &Addr) -> Vec<String> {
+    let received: Vec<ChatMessageWithIndex> = app.as_ref().wrap().query_wasm_smart(hook, &Empty {}).unwrap();
+    received.into_iter().map(|m| m.msg.message).collect()
+}
+
+#[tokio::test]
+async fn hooks_receive_local_and_remote_messages() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    let hook = instantiate_hook(&app, false);
+
+    let mut client = app.clone().into_contract_client();
+
+    // only the owner manages hooks
+    assert!(client.exec_set_hook(hook.to_string(), None).await.is_err());
+    client.sender = Addr::unchecked("client-admin");
+    client.exec_set_hook(hook.to_string(), None).await.unwrap();
+    assert_eq!(client.query_hooks().await.unwrap().hooks.len(), 1);
+
+    client.exec_send_message("local").await.unwrap();
+
+    let mut other = app.clone().into_contract_client();
+    other.id = 1;
+    other.exec_send_message("remote").await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    assert_eq!(hook_messages(&app, &hook), vec!["local", "remote"]);
+
+    client.exec_remove_hook(hook.to_string()).await.unwrap();
+    client.exec_send_message("unheard").await.unwrap();
+    assert_eq!(hook_messages(&app, &hook), vec!["local", "remote"]);
+}
+
+#[tokio::test]
+async fn hook_filters() {
+    let app = TestApp::new();
+    let hook = instantiate_hook(&app, false);
+
+    let mut client = app.clone().into_contract_client();
+    client.sender = Addr::unchecked("client-admin");
+    client.exec_set_hook(hook.to_string(), Some(HookFilter {
+        prefix: Some("!bot".to_string()),
+        ..Default::default()
+    })).await.unwrap();
+
+    client.exec_send_message("hello").await.unwrap();
+    client.exec_send_message("!bot roll").await.unwrap();
+
+    assert_eq!(hook_messages(&app, &hook), vec!["!bot roll"]);
+}
+
+#[tokio::test]
+async fn failing_hook_does_not_block_messages() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    let broken = instantiate_hook(&app, true);
+    let hook = instantiate_hook(&app, false);
+
+    let mut client = app.clone().into_contract_client();
+    client.sender = Addr::unchecked("client-admin");
+    client.exec_set_hook(broken.to_string(), None).await.unwrap();
+    client.exec_set_hook(hook.to_string(), None).await.unwrap();
+
+    let resp = client.exec_send_message("local").await.unwrap();
+    assert!(resp.events.iter().any(|e| e.ty == "wasm-chat-hook-failed"));
+
+    // the packet from the other chain is still acknowledged and stored
+    let mut other = app.clone().into_contract_client();
+    other.id = 1;
+    other.exec_send_message("remote").await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    let messages = client.query_chat_messages(None, None).await.unwrap().messages;
+    assert_eq!(messages.into_iter().map(|m| m.msg.message).collect::<Vec<_>>(), vec!["local", "remote"]);
+    assert_eq!(hook_messages(&app, &hook), vec!["local", "remote"]);
+}
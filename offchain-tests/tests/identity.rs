@@ -0,0 +1,125 @@
+use app_tests::{helpers::{client_as, server_admin, PREFIX}, prelude::*};
+use shared::{
+    ibc::{IbcAck, IbcAckErrorCode},
+    msg::{ibc::{IbcAckSuccess, IbcExecuteMsg}, identity::{identity_of_address, IdentityLinkSignature}},
+};
+use wallet::signed::{sign_identity_link, signer_address};
+
+const KEY_A: [u8; 32] = [1; 32];
+const KEY_B: [u8; 32] = [2; 32];
+const KEY_C: [u8; 32] = [3; 32];
+
+#[tokio::test]
+async fn same_key_same_identity() {
+    let kujira = signer_address(&KEY_A, "kujira").unwrap();
+    let stargaze = signer_address(&KEY_A, "stars").unwrap();
+
+    assert_ne!(kujira, stargaze);
+    assert_eq!(identity_of_address(&kujira).unwrap(), identity_of_address(&stargaze).unwrap());
+}
+
+#[tokio::test]
+async fn link_another_key() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+
+    let a = signer_address(&KEY_A, PREFIX).unwrap();
+    let b = signer_address(&KEY_B, PREFIX).unwrap();
+    let identity = identity_of_address(&a).unwrap();
+
+    let mut client = client_as(&app, 0, &KEY_A);
+    let proof = sign_identity_link(&KEY_B, PREFIX, &identity).unwrap();
+    client.exec_link_identity(proof).await.unwrap();
+
+    let acks = app.as_mut().ibc_relay_all().unwrap();
+    assert!(matches!(acks[0], IbcAck::Success(IbcAckSuccess::IdentityLinked { })));
+    assert!(acks[1..].iter().all(|ack| matches!(ack, IbcAck::Success(IbcAckSuccess::IdentityStored { }))));
+
+    // every client knows, the one it came from too
+    let client_count = app.as_ref().client_contracts.len();
+    for id in 0..client_count {
+        let mut client = app.clone().into_contract_client();
+        client.id = id;
+        let resp = client.query_identity(&b).await.unwrap();
+        assert_eq!(resp.identity, identity);
+        assert_eq!(resp.members, vec![identity_of_address(&b).unwrap()]);
+    }
+    let resp = app.clone().into_contract_server().query_identity(identity_of_address(&b).unwrap()).await.unwrap();
+    assert_eq!(resp.identity, identity);
+
+    // messages from both keys, on different chains, are one person's
+    client_as(&app, 0, &KEY_A).exec_send_message("gm from kujira").await.unwrap();
+    client_as(&app, 1, &KEY_B).exec_send_message("gm from stargaze").await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    let mut reader = app.clone().into_contract_client();
+    reader.id = 2;
+    let resp = reader.query_chat_messages(None, None).await.unwrap();
+    assert_eq!(resp.messages.len(), 2);
+    assert_eq!(resp.identities.len(), 2);
+    assert!(resp.identities.iter().all(|x| x.identity == identity));
+}
+
+#[tokio::test]
+async fn bad_links_are_refused() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+
+    let a = signer_address(&KEY_A, PREFIX).unwrap();
+    let c = signer_address(&KEY_C, PREFIX).unwrap();
+    let mut client = client_as(&app, 0, &KEY_A);
+
+    // signed for someone else's identity
+    let proof = sign_identity_link(&KEY_B, PREFIX, &identity_of_address(&c).unwrap()).unwrap();
+    let err = client.exec_link_identity(proof).await.unwrap_err();
+    assert!(format!("{:?}", err).contains("invalid signature"));
+
+    // B joins A
+    let proof = sign_identity_link(&KEY_B, PREFIX, &identity_of_address(&a).unwrap()).unwrap();
+    client.exec_link_identity(proof).await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    // then C tries to take B, from another chain, the server says no
+    let mut other = client_as(&app, 1, &KEY_C);
+    let proof = sign_identity_link(&KEY_B, PREFIX, &identity_of_address(&c).unwrap()).unwrap();
+    other.exec_link_identity(proof).await.unwrap();
+
+    let acks = app.as_mut().ibc_relay_all().unwrap();
+    assert!(matches!(&acks[0], IbcAck::Error(err) if err.code == IbcAckErrorCode::Rejected && err.message.contains("already joined")));
+}
+
+#[tokio::test]
+async fn the_server_checks_links_itself() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    let rejected = |acks: Vec<IbcAck>, reason: &str| matches!(&acks[..], [IbcAck::Error(err)] if err.code == IbcAckErrorCode::Rejected && err.message.contains(reason));
+
+    let a = signer_address(&KEY_A, PREFIX).unwrap();
+    let b = signer_address(&KEY_B, PREFIX).unwrap();
+    let c = signer_address(&KEY_C, PREFIX).unwrap();
+    let proof = sign_identity_link(&KEY_B, PREFIX, &identity_of_address(&a).unwrap()).unwrap();
+    let signature = |link_identity: &str| IdentityLinkSignature {
+        address: proof.address.clone(),
+        pubkey: proof.pubkey.clone(),
+        signature: proof.signature.clone(),
+        link_identity: link_identity.to_string(),
+    };
+
+    // a channel that never said which chain it's on, even with a good signature
+    app.as_mut().ibc_close_client(0).unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+    server_admin(&app).exec_set_client_connection("connection-0", None).await.unwrap();
+    app.as_mut().ibc_connect_client(0).unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+    let link = IbcExecuteMsg::LinkIdentity { signature: signature(&identity_of_address(&a).unwrap()) };
+    app.as_mut().ibc_send_from_client(0, &link).unwrap();
+    assert!(rejected(app.as_mut().ibc_relay_all().unwrap(), "registered client"));
+
+    // a registered client taking B's signature for C
+    let link = IbcExecuteMsg::LinkIdentity { signature: signature(&identity_of_address(&c).unwrap()) };
+    app.as_mut().ibc_send_from_client(1, &link).unwrap();
+    assert!(rejected(app.as_mut().ibc_relay_all().unwrap(), "invalid signature"));
+
+    let resp = app.clone().into_contract_server().query_identity(identity_of_address(&b).unwrap()).await.unwrap();
+    assert_eq!(resp.identity, identity_of_address(&b).unwrap());
+}
@@ -1,4 +1,6 @@
 use app_tests::prelude::*;
+use cosmwasm_std::{Addr, Order};
+use shared::msg::contract::client::ChatMessagesResp;
 
 #[tokio::test]
 async fn integration() {
@@ -7,11 +9,80 @@ async fn integration() {
     let mut client = app.clone().into_contract_client(); 
 
 
-    let resp = client.exec_send_message("hello world").await;
+    // we have not connected an ibc channel, so this waits in the outbox
+    client.exec_send_message("hello world").await.unwrap();
+    assert_eq!(client.query_outbox().await.unwrap().messages.len(), 1);
 
-    // TODO - we want to get IBC working so that this assertion can pass
-    // this should fail as we have not connected an ibc channel
-    assert!(resp.is_err());
+    // only the owner can purge it
+    assert!(client.exec_purge_outbox().await.is_err());
+    client.sender = Addr::unchecked("client-admin");
+    client.exec_purge_outbox().await.unwrap();
+    assert!(client.query_outbox().await.unwrap().messages.is_empty());
 
-    // TODO (after above TODO)- run same exact test as onchain-tests/src/runner.rs
-}
\ No newline at end of file
+    app.as_mut().ibc_connect_all().unwrap();
+
+    // same idea as onchain-tests/src/runner.rs: every client hears from every other client
+    let client_count = app.as_ref().client_contracts.len();
+    for id in 0..client_count {
+        let mut client = app.clone().into_contract_client();
+        client.id = id;
+        client.exec_send_message(format!("hello from {}", id)).await.unwrap();
+    }
+
+    app.as_mut().ibc_relay_all().unwrap();
+
+    for id in 0..client_count {
+        let mut client = app.clone().into_contract_client();
+        client.id = id;
+        let ChatMessagesResp { messages, .. } = client.query_chat_messages(None, Some(Order::Ascending)).await.unwrap();
+
+        let mut texts = messages.into_iter().map(|m| m.msg.message).collect::<Vec<_>>();
+        texts.sort();
+
+        // the purged message is still in the sender's local history
+        let mut expected = (0..client_count).map(|id| format!("hello from {}", id)).collect::<Vec<_>>();
+        if id == 0 {
+            expected.push("hello world".to_string());
+        }
+        expected.sort();
+
+        assert_eq!(texts, expected);
+    }
+}
+
+#[tokio::test]
+async fn version_negotiation() {
+    let app = TestApp::new();
+
+    // a newer counterparty proposes a version we don't know yet, alongside one we do
+    app.as_mut().ibc_connect_client_with_version(0, "ibc-chat-999,ibc-chat-001").unwrap();
+
+    let mut client = app.clone().into_contract_client();
+    let info = client.query_info().await.unwrap();
+    assert_eq!(info.server_channel.unwrap().version, "ibc-chat-001");
+
+    // nothing in common
+    assert!(app.as_mut().ibc_connect_client_with_version(1, "ibc-chat-999").is_err());
+}
+
+#[tokio::test]
+async fn outbox_flushes_on_connect() {
+    let app = TestApp::new();
+
+    let mut client = app.clone().into_contract_client();
+    client.exec_send_message("first").await.unwrap();
+    client.exec_send_message("second").await.unwrap();
+
+    let queued = client.query_outbox().await.unwrap().messages;
+    assert_eq!(queued.iter().map(|m| m.msg.message.as_str()).collect::<Vec<_>>(), vec!["first", "second"]);
+
+    app.as_mut().ibc_connect_all().unwrap();
+    assert!(client.query_outbox().await.unwrap().messages.is_empty());
+
+    app.as_mut().ibc_relay_all().unwrap();
+
+    let mut other = app.clone().into_contract_client();
+    other.id = 1;
+    let ChatMessagesResp { messages, .. } = other.query_chat_messages(None, Some(Order::Ascending)).await.unwrap();
+    assert_eq!(messages.into_iter().map(|m| m.msg.message).collect::<Vec<_>>(), vec!["first", "second"]);
+}
@@ -0,0 +1,124 @@
+use app_tests::prelude::*;
+use cosmwasm_std::{coins, to_json_string, Addr};
+use shared::{
+    ibc::IbcAck,
+    msg::{ibc::IbcAckSuccess, memo::{hook_intermediary_sender, TransferChatPost, TransferMemo}, network::NetworkId},
+};
+
+const CHANNEL: &str = "channel-7";
+const AUTHOR: &str = "stars1author";
+
+fn chat_memo(app: &TestApp, channel: &str, sender: &str, message: &str) -> String {
+    let server = app.as_ref().server_contract.to_string();
+    to_json_string(&TransferMemo::new(server, TransferChatPost {
+        channel: channel.to_string(),
+        sender: sender.to_string(),
+        message: message.to_string(),
+    })).unwrap()
+}
+
+async fn open_memo_channel(app: &TestApp) {
+    let mut server = app.clone().into_contract_server();
+    assert!(server.exec_set_memo_channel(CHANNEL, Some(NetworkId::Stargaze)).await.is_err());
+
+    server.sender = Addr::unchecked("server-admin");
+    server.exec_set_memo_channel(CHANNEL, Some(NetworkId::Stargaze)).await.unwrap();
+}
+
+#[tokio::test]
+async fn post_with_a_transfer() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    open_memo_channel(&app).await;
+
+    let memo = chat_memo(&app, CHANNEL, AUTHOR, "gm from a wallet");
+    app.as_mut().ibc_hook_transfer(CHANNEL, AUTHOR, &memo, coins(1, "ibc/stars")).unwrap();
+
+    // every client gets it, there's no source to skip
+    let acks = app.as_mut().ibc_relay_all().unwrap();
+    assert_eq!(acks.len(), app.as_ref().client_contracts.len());
+    assert!(acks.iter().all(|ack| matches!(ack, IbcAck::Success(IbcAckSuccess::MessageStored { .. }))));
+
+    let client_count = app.as_ref().client_contracts.len();
+    for id in 0..client_count {
+        let mut client = app.clone().into_contract_client();
+        client.id = id;
+        let message = client.query_chat_messages(None, None).await.unwrap().messages.remove(0).msg;
+        assert_eq!(message.user.as_str(), AUTHOR);
+        assert_eq!(message.network_id, NetworkId::Stargaze);
+        assert_eq!(message.message, "gm from a wallet");
+    }
+
+    // the transfer stays with the server
+    let server = app.as_ref().server_contract.clone();
+    assert_eq!(app.as_ref().wrap().query_balance(server, "ibc/stars").unwrap().amount.u128(), 1);
+}
+
+#[tokio::test]
+async fn only_the_hook_sender_can_post() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    open_memo_channel(&app).await;
+
+    // calling the server directly, as someone else
+    let mut server = app.clone().into_contract_server();
+    let post = TransferChatPost {
+        channel: CHANNEL.to_string(),
+        sender: AUTHOR.to_string(),
+        message: "not really me".to_string(),
+    };
+    let err = server.exec_post_from_transfer(post, &[]).await.unwrap_err();
+    assert!(format!("{:?}", err).contains("only the ibc-hooks sender"));
+
+    // a memo claiming another sender than the transfer's
+    let memo = chat_memo(&app, CHANNEL, AUTHOR, "not really me");
+    assert!(app.as_mut().ibc_hook_transfer(CHANNEL, "stars1other", &memo, coins(1, "ibc/stars")).is_err());
+
+    // or arriving on a channel nobody set up
+    let memo = chat_memo(&app, "channel-99", AUTHOR, "hello?");
+    let err = app.as_mut().ibc_hook_transfer("channel-99", AUTHOR, &memo, coins(1, "ibc/stars")).unwrap_err();
+    assert!(format!("{:?}", err).contains("can't be posted over channel-99"));
+
+    assert!(app.as_mut().ibc_relay_all().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn memo_posts_are_checked_like_other_messages() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    open_memo_channel(&app).await;
+
+    for message in ["", "  \n "] {
+        let memo = chat_memo(&app, CHANNEL, AUTHOR, message);
+        let err = app.as_mut().ibc_hook_transfer(CHANNEL, AUTHOR, &memo, coins(1, "ibc/stars")).unwrap_err();
+        assert!(format!("{:?}", err).contains("empty message"));
+    }
+
+    assert!(app.as_mut().ibc_relay_all().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn memo_channels_are_listed() {
+    let app = TestApp::new();
+    open_memo_channel(&app).await;
+
+    let mut server = app.clone().into_contract_server();
+    let info = server.query_info().await.unwrap();
+    assert_eq!(info.owner.as_str(), "server-admin");
+    assert_eq!(info.memo_channels.len(), 1);
+    assert_eq!(info.memo_channels[0].network_id, NetworkId::Stargaze);
+
+    server.sender = Addr::unchecked("server-admin");
+    server.exec_set_memo_channel(CHANNEL, None).await.unwrap();
+    assert!(server.query_info().await.unwrap().memo_channels.is_empty());
+}
+
+// from ibc-hooks' own tests, for transfers arriving on osmosis
+#[test]
+fn intermediary_sender_matches_ibc_hooks() {
+    let osmo = |canonical: Vec<u8>| bech32::encode::<bech32::Bech32>(bech32::Hrp::parse("osmo").unwrap(), &canonical).unwrap();
+    let sender = "cosmos1tfejvgp5yzd8ypvn9t0e2uv2kcjf2laa8upya8";
+
+    assert_eq!(osmo(hook_intermediary_sender("channel-0", sender)), "osmo1sguz3gtyl2tjsdulwxmtprd68xtd43yyep6g5c554utz642sr8rqcgw0q6");
+    assert_eq!(osmo(hook_intermediary_sender("channel-1", sender)), "osmo1svnare87kluww5hnltv24m4dg72hst0qqwm5xslsvnwd22gftcussaz5l7");
+}
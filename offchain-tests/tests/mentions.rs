@@ -0,0 +1,103 @@
+use app_tests::{helpers::{client_texts, user}, prelude::*};
+use shared::msg::{mention::{event::MentionEvent, parse_mentions}, network::NetworkId};
+
+#[tokio::test]
+async fn nicknames_resolve_on_their_home_chain() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    let (bob, mut bob_client) = user(&app, 0, 1);
+    let (_, mut alice_client) = user(&app, 1, 2);
+
+    bob_client.exec_send_message("/nick bob").await.unwrap();
+    alice_client.exec_send_message("hey @Bob, look").await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    // bob is only bob on kujira
+    assert_eq!(bob_client.query_unread_mentions(&bob).await.unwrap().count, 1);
+    let mentions = bob_client.query_mentions(&bob, false).await.unwrap().mentions;
+    assert_eq!(mentions.len(), 1);
+    assert_eq!(mentions[0].message.msg.message, "hey @Bob, look");
+    assert_eq!(mentions[0].message.msg.network_id, NetworkId::Stargaze);
+    assert!(!mentions[0].read);
+
+    assert_eq!(alice_client.query_unread_mentions(&bob).await.unwrap().count, 0);
+
+    // a new nickname frees the old one
+    bob_client.exec_send_message("/nick robert").await.unwrap();
+    alice_client.exec_send_message("@bob?").await.unwrap();
+    alice_client.exec_send_message("@robert!").await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+    assert_eq!(bob_client.query_unread_mentions(&bob).await.unwrap().count, 2);
+}
+
+#[tokio::test]
+async fn nicknames_cant_be_taken() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    let (bob, mut bob_client) = user(&app, 0, 1);
+    let (carol, mut carol_client) = user(&app, 0, 2);
+
+    bob_client.exec_send_message("/nick bob").await.unwrap();
+    carol_client.exec_send_message("/nick Bob").await.unwrap();
+    assert_eq!(client_texts(&app, 0).await.last().unwrap(), "Bob is taken");
+
+    // mentions still go to bob
+    let (_, mut alice_client) = user(&app, 1, 3);
+    alice_client.exec_send_message("hey @bob").await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+    assert_eq!(bob_client.query_unread_mentions(&bob).await.unwrap().count, 1);
+    assert_eq!(carol_client.query_unread_mentions(&carol).await.unwrap().count, 0);
+
+    // bob can change the case of their own, and once they let it go it's free
+    bob_client.exec_send_message("/nick BOB").await.unwrap();
+    assert_eq!(client_texts(&app, 0).await.last().unwrap(), &format!("{} is now known as BOB", bob));
+    bob_client.exec_send_message("/nick robert").await.unwrap();
+    carol_client.exec_send_message("/nick bob").await.unwrap();
+    assert_eq!(client_texts(&app, 0).await.last().unwrap(), &format!("{} is now known as bob", carol));
+}
+
+#[tokio::test]
+async fn marking_read() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    let (bob, mut bob_client) = user(&app, 0, 1);
+    let (_, mut alice_client) = user(&app, 0, 2);
+
+    let resp = alice_client.exec_send_message(format!("@{} one", bob)).await.unwrap();
+    let event = resp.events.into_iter().find_map(|e| MentionEvent::try_from(e).ok()).unwrap();
+    assert_eq!(event.user, Addr::unchecked(&bob));
+
+    // twice in one message is one mention
+    alice_client.exec_send_message(format!("@{} two @{}", bob, bob)).await.unwrap();
+    alice_client.exec_send_message(format!("(@{}) three", bob)).await.unwrap();
+    assert_eq!(bob_client.query_unread_mentions(&bob).await.unwrap().count, 3);
+
+    bob_client.exec_mark_read(Some(event.index)).await.unwrap();
+    assert_eq!(bob_client.query_unread_mentions(&bob).await.unwrap().count, 2);
+    let unread = bob_client.query_mentions(&bob, true).await.unwrap().mentions;
+    assert!(unread.iter().all(|mention| mention.message.index > event.index && !mention.read));
+
+    bob_client.exec_mark_read(None).await.unwrap();
+    assert_eq!(bob_client.query_unread_mentions(&bob).await.unwrap().count, 0);
+    let mentions = bob_client.query_mentions(&bob, false).await.unwrap().mentions;
+    assert_eq!(mentions.len(), 3);
+    assert!(mentions.iter().all(|mention| mention.read));
+}
+
+#[tokio::test]
+async fn not_every_at_is_a_mention() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    let (bob, mut bob_client) = user(&app, 0, 1);
+    let (alice, mut alice_client) = user(&app, 0, 2);
+
+    // themselves, and someone who blocked them
+    alice_client.exec_send_message(format!("note to self @{}", alice)).await.unwrap();
+    bob_client.exec_block(NetworkId::Kujira, &alice).await.unwrap();
+    alice_client.exec_send_message(format!("@{}", bob)).await.unwrap();
+    assert_eq!(alice_client.query_unread_mentions(&alice).await.unwrap().count, 0);
+    assert_eq!(bob_client.query_unread_mentions(&bob).await.unwrap().count, 0);
+
+    assert_eq!(parse_mentions("mail bob@example.com or @carol."), vec!["carol"]);
+    assert!(parse_mentions("@ alone, @@").is_empty());
+}
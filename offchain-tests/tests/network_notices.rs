@@ -0,0 +1,148 @@
+use app_tests::{helpers::server_admin, prelude::*};
+use cosmwasm_std::{Addr, Order};
+use cw_utils::Expiration;
+use shared::{
+    ibc::{IbcAck, IbcAckErrorCode},
+    msg::{
+        chat_message::{ChatMessage, ChatMessageKind, ChatMessageWithIndex},
+        content::MessageContent,
+        ibc::{IbcAckSuccess, IbcExecuteMsg},
+        network::NetworkId,
+    },
+};
+
+async fn enable_notices(app: &TestApp) {
+    let mut server = server_admin(app);
+    server.exec_set_network_notices(true).await.unwrap();
+    assert!(server.query_info().await.unwrap().network_notices);
+}
+
+async fn notices(app: &TestApp, client_index: usize) -> Vec<ChatMessage> {
+    let mut client = app.clone().into_contract_client();
+    client.id = client_index;
+    client.query_chat_messages(None, Some(Order::Ascending)).await.unwrap()
+        .messages
+        .into_iter()
+        .map(|m| m.msg)
+        .filter(|msg| msg.kind == ChatMessageKind::Network)
+        .collect()
+}
+
+#[tokio::test]
+async fn joins_are_announced_to_the_others() {
+    let app = TestApp::new();
+    enable_notices(&app).await;
+
+    // kujira is alone, stargaze has kujira to tell
+    app.as_mut().ibc_connect_client(0).unwrap();
+    assert!(app.as_mut().ibc_in_flight().unwrap().is_empty());
+    app.as_mut().ibc_connect_client(1).unwrap();
+    let acks = app.as_mut().ibc_relay_all().unwrap();
+    assert!(matches!(acks[..], [IbcAck::Success(IbcAckSuccess::MessageStored { .. })]));
+
+    let kujira = notices(&app, 0).await;
+    assert_eq!(kujira.len(), 1);
+    assert_eq!(kujira[0].network_id, NetworkId::Stargaze);
+    assert_eq!(kujira[0].message, "stargaze joined the chat");
+    assert_eq!(kujira[0].content, MessageContent::Notice);
+    assert_eq!(kujira[0].user, app.as_ref().server_contract);
+
+    // nobody hears about themselves
+    assert!(notices(&app, 1).await.is_empty());
+}
+
+#[tokio::test]
+async fn leaving_is_announced_to_the_rest() {
+    let app = TestApp::new();
+    enable_notices(&app).await;
+    app.as_mut().ibc_connect_all().unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    // nois
+    app.as_mut().ibc_close_client(2).unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    for id in [0, 1, 3] {
+        let last = notices(&app, id).await.pop().unwrap();
+        assert_eq!(last.message, "nois left the chat");
+        assert_eq!(last.network_id, NetworkId::Nois);
+    }
+    assert!(notices(&app, 2).await.iter().all(|msg| msg.network_id != NetworkId::Nois));
+}
+
+#[tokio::test]
+async fn notices_are_off_by_default() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    app.as_mut().ibc_close_client(2).unwrap();
+    assert!(app.as_mut().ibc_relay_all().unwrap().is_empty());
+
+    // and only the owner turns them on
+    let mut server = app.clone().into_contract_server();
+    assert!(server.exec_set_network_notices(true).await.is_err());
+}
+
+#[tokio::test]
+async fn clients_cant_announce_networks() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+
+    let message = ChatMessage {
+        user: Addr::unchecked("sender"),
+        network_id: NetworkId::Nois,
+        message: "nois left the chat".to_string(),
+        kind: ChatMessageKind::Network,
+        content: MessageContent::Notice,
+        room: None,
+        attestation: None,
+        expires: Expiration::Never {},
+    };
+    app.as_mut().ibc_send_from_client(0, &IbcExecuteMsg::SendMessageToServer {
+        message: ChatMessageWithIndex { msg: message, index: 1u64.into() },
+    }).unwrap();
+
+    let acks = app.as_mut().ibc_relay_all().unwrap();
+    assert!(matches!(&acks[..], [IbcAck::Error(err)] if err.code == IbcAckErrorCode::UnsupportedMessage));
+}
+
+#[tokio::test]
+async fn networks_come_from_the_owners_connections() {
+    let app = TestApp::new();
+    let mut server = server_admin(&app);
+    let connected = |network_id| IbcExecuteMsg::ClientConnected { network_id };
+    let rejected = |acks: Vec<IbcAck>, reason: &str| matches!(&acks[..], [IbcAck::Error(err)] if err.code == IbcAckErrorCode::Rejected && err.message.contains(reason));
+
+    // kujira's connection, as the owner set it up in the harness
+    let info = server.query_info().await.unwrap();
+    assert!(info.client_connections.iter().any(|c| c.connection_id == "connection-0" && c.network_id == NetworkId::Kujira));
+    assert!(app.clone().into_contract_server().exec_set_client_connection("connection-0", None).await.is_err());
+
+    // what the client says when it connects is taken, once
+    app.as_mut().ibc_connect_all().unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+    app.as_mut().ibc_send_from_client(0, &connected(NetworkId::Kujira)).unwrap();
+    assert!(rejected(app.as_mut().ibc_relay_all().unwrap(), "already said"));
+    app.as_mut().ibc_send_from_client(0, &connected(NetworkId::Stargaze)).unwrap();
+    assert!(rejected(app.as_mut().ibc_relay_all().unwrap(), "already said"));
+
+    // a new channel on the same connection can't claim another chain
+    app.as_mut().ibc_close_client(0).unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+    server.exec_set_client_connection("connection-0", Some(NetworkId::Stargaze)).await.unwrap();
+    app.as_mut().ibc_connect_client(0).unwrap();
+    app.as_mut().ibc_send_from_client(0, &connected(NetworkId::Kujira)).unwrap();
+    assert!(rejected(app.as_mut().ibc_relay_all().unwrap(), "connection-0 is for stargaze, not kujira"));
+
+    // nor take the place of one that's connected
+    app.as_mut().ibc_send_from_client(0, &connected(NetworkId::Stargaze)).unwrap();
+    assert!(rejected(app.as_mut().ibc_relay_all().unwrap(), "stargaze is already connected"));
+
+    // and nothing is taken over a connection that isn't set up
+    server.exec_set_client_connection("connection-0", None).await.unwrap();
+    app.as_mut().ibc_send_from_client(0, &connected(NetworkId::Kujira)).unwrap();
+    assert!(rejected(app.as_mut().ibc_relay_all().unwrap(), "isn't set up"));
+
+    server.exec_set_client_connection("connection-0", Some(NetworkId::Kujira)).await.unwrap();
+    app.as_mut().ibc_send_from_client(0, &connected(NetworkId::Kujira)).unwrap();
+    assert!(matches!(&app.as_mut().ibc_relay_all().unwrap()[..], [IbcAck::Success(IbcAckSuccess::ClientRegistered { .. })]));
+}
@@ -0,0 +1,171 @@
+use app_tests::{helpers::{server_admin, user}, prelude::*};
+use cw_utils::Expiration;
+use shared::{
+    ibc::{IbcAck, IbcAckErrorCode},
+    msg::{
+        chat_message::{ChatMessage, ChatMessageKind, ChatMessageWithIndex},
+        content::MessageContent,
+        ibc::{IbcAckSuccess, IbcExecuteMsg},
+        network::NetworkId,
+        pin::{event::PinUpdatedEvent, Pin},
+    },
+};
+
+#[tokio::test]
+async fn room_owner_pins_on_every_chain() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    let (_, mut owner) = user(&app, 0, 1);
+    let (_, mut alice) = user(&app, 0, 2);
+
+    owner.exec_create_room("news", None).await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+    alice.exec_send_room_message("kujira/news", "big news").await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+    let index = owner.query_chat_messages(None, None).await.unwrap().messages.last().unwrap().index;
+
+    // only the owner, and not before it's stored
+    let err = alice.exec_pin(index).await.unwrap_err();
+    assert!(format!("{:?}", err).contains("can pin messages in"));
+    owner.exec_pin(index).await.unwrap();
+    assert!(owner.query_pins(Some("kujira/news".to_string())).await.unwrap().pins.is_empty());
+
+    let acks = app.as_mut().ibc_relay_all().unwrap();
+    let client_count = app.as_ref().client_contracts.len() as u32;
+    assert!(matches!(acks[0], IbcAck::Success(IbcAckSuccess::PinBroadcast { recipients }) if recipients == client_count));
+
+    let mut id = None;
+    for client_index in 0..client_count as usize {
+        let (_, mut client) = user(&app, client_index, 3);
+        let pins = client.query_pins(Some("kujira/news".to_string())).await.unwrap().pins;
+        assert_eq!(pins.len(), 1);
+        assert_eq!(pins[0].message.as_ref().unwrap().msg.message, "big news");
+        assert!(id.is_none() || id == Some(pins[0].pin.id));
+        id = Some(pins[0].pin.id);
+
+        assert!(client.query_pins(None).await.unwrap().pins.is_empty());
+    }
+
+    owner.exec_unpin(Some("kujira/news".to_string()), id.unwrap()).await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+    for client_index in 0..client_count as usize {
+        let (_, mut client) = user(&app, client_index, 3);
+        assert!(client.query_pins(Some("kujira/news".to_string())).await.unwrap().pins.is_empty());
+    }
+}
+
+#[tokio::test]
+async fn pins_are_changed_at_home() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    let (_, mut owner) = user(&app, 0, 1);
+
+    owner.exec_send_message("gm").await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+    let index = owner.query_chat_messages(None, None).await.unwrap().messages[0].index;
+    let err = owner.exec_pin(index).await.unwrap_err();
+    assert!(format!("{:?}", err).contains("only the server's admin"));
+
+    // a client can't pin for a room on another chain
+    let pin = Pin { room: Some("stargaze/punks".to_string()), id: 1u64.into(), by: Addr::unchecked("sender") };
+    app.as_mut().ibc_send_from_client(0, &IbcExecuteMsg::UpdatePin { pin, pinned: true }).unwrap();
+    let acks = app.as_mut().ibc_relay_all().unwrap();
+    assert!(matches!(&acks[..], [IbcAck::Error(err)] if err.code == IbcAckErrorCode::Rejected));
+
+    let pin = Pin { room: None, id: 1u64.into(), by: Addr::unchecked("sender") };
+    app.as_mut().ibc_send_from_client(0, &IbcExecuteMsg::UpdatePin { pin, pinned: true }).unwrap();
+    let acks = app.as_mut().ibc_relay_all().unwrap();
+    assert!(matches!(&acks[..], [IbcAck::Error(err)] if err.code == IbcAckErrorCode::Rejected));
+}
+
+#[tokio::test]
+async fn admin_announcements() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+
+    let mut server = app.clone().into_contract_server();
+    assert!(server.exec_announce("not me", None).await.is_err());
+
+    let resp = server_admin(&app).exec_announce("maintenance at noon", Some(MessageContent::Markdown)).await.unwrap();
+    assert!(resp.events.iter().any(|e| e.ty.ends_with(PinUpdatedEvent::KEY)));
+    app.as_mut().ibc_relay_all().unwrap();
+
+    let client_count = app.as_ref().client_contracts.len();
+    for client_index in 0..client_count {
+        let (_, mut client) = user(&app, client_index, 1);
+        let messages = client.query_chat_messages(None, None).await.unwrap().messages;
+        let announcement = &messages.last().unwrap().msg;
+        assert_eq!(announcement.kind, ChatMessageKind::Announcement);
+        assert_eq!(announcement.network_id, NetworkId::Neutron);
+
+        let pins = client.query_pins(None).await.unwrap().pins;
+        assert_eq!(pins.len(), 1);
+        assert_eq!(pins[0].pin.by, Addr::unchecked("server-admin"));
+        assert_eq!(pins[0].message.as_ref().unwrap().msg.message, "maintenance at noon");
+    }
+
+    // the admin unpins it like any other
+    let id = {
+        let (_, mut client) = user(&app, 0, 1);
+        client.query_pins(None).await.unwrap().pins[0].pin.id
+    };
+    server_admin(&app).exec_unpin(None, id).await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+    let (_, mut client) = user(&app, 0, 1);
+    assert!(client.query_pins(None).await.unwrap().pins.is_empty());
+}
+
+#[tokio::test]
+async fn clients_cant_announce() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+
+    let message = ChatMessage {
+        user: Addr::unchecked("sender"),
+        network_id: NetworkId::Kujira,
+        message: "free tokens".to_string(),
+        kind: ChatMessageKind::Announcement,
+        content: MessageContent::Text,
+        room: None,
+        attestation: None,
+        expires: Expiration::Never {},
+    };
+    app.as_mut().ibc_send_from_client(0, &IbcExecuteMsg::SendMessageToServer {
+        message: ChatMessageWithIndex { msg: message, index: 1u64.into() },
+    }).unwrap();
+
+    let acks = app.as_mut().ibc_relay_all().unwrap();
+    assert!(matches!(&acks[..], [IbcAck::Error(err)] if err.code == IbcAckErrorCode::UnsupportedMessage));
+}
+
+#[tokio::test]
+async fn clients_cant_pin_for_another_network() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+    let pin = Pin { room: Some("stargaze/punks".to_string()), id: 1u64.into(), by: Addr::unchecked("sender") };
+    let rejected = |acks: Vec<IbcAck>| matches!(&acks[..], [IbcAck::Error(err)] if err.code == IbcAckErrorCode::Rejected);
+
+    // kujira claims to be stargaze now, and then pins for it
+    app.as_mut().ibc_send_from_client(0, &IbcExecuteMsg::ClientConnected { network_id: NetworkId::Stargaze }).unwrap();
+    assert!(rejected(app.as_mut().ibc_relay_all().unwrap()));
+    app.as_mut().ibc_send_from_client(0, &IbcExecuteMsg::UpdatePin { pin: pin.clone(), pinned: true }).unwrap();
+    assert!(rejected(app.as_mut().ibc_relay_all().unwrap()));
+
+    // same on a new channel
+    app.as_mut().ibc_close_client(0).unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+    app.as_mut().ibc_connect_client(0).unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+    app.as_mut().ibc_send_from_client(0, &IbcExecuteMsg::UpdatePin { pin: pin.clone(), pinned: true }).unwrap();
+    assert!(rejected(app.as_mut().ibc_relay_all().unwrap()));
+
+    let (_, mut client) = user(&app, 2, 1);
+    assert!(client.query_pins(Some("stargaze/punks".to_string())).await.unwrap().pins.is_empty());
+
+    // only from stargaze itself
+    app.as_mut().ibc_send_from_client(1, &IbcExecuteMsg::UpdatePin { pin, pinned: true }).unwrap();
+    let acks = app.as_mut().ibc_relay_all().unwrap();
+    assert!(matches!(acks[0], IbcAck::Success(IbcAckSuccess::PinBroadcast { .. })));
+    assert_eq!(client.query_pins(Some("stargaze/punks".to_string())).await.unwrap().pins.len(), 1);
+}
@@ -0,0 +1,131 @@
+use app_tests::prelude::*;
+use shared::{ibc::{IbcAck, IbcAckErrorCode}, msg::{ibc::{IbcAckSuccess, IbcExecuteMsg}, network::NetworkId, poll::Voter}};
+
+fn options() -> Vec<String> {
+    vec!["yes".to_string(), "no".to_string()]
+}
+
+#[tokio::test]
+async fn poll_tally_across_chains() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    let closes_at = app.as_ref().block_info().time.plus_seconds(100);
+
+    let mut client = app.clone().into_contract_client();
+    client.exec_create_poll("ship it?", options(), closes_at).await.unwrap();
+
+    let acks = app.as_mut().ibc_relay_all().unwrap();
+    assert!(matches!(acks[0], IbcAck::Success(IbcAckSuccess::PollCreated { .. })));
+
+    // every client heard about it, including the creator
+    let client_count = app.as_ref().client_contracts.len();
+    for id in 0..client_count {
+        let mut client = app.clone().into_contract_client();
+        client.id = id;
+        let polls = client.query_polls().await.unwrap().polls;
+        assert_eq!(polls.len(), 1);
+        assert_eq!(polls[0].poll.question, "ship it?");
+    }
+    let poll_id = client.query_polls().await.unwrap().polls[0].poll.id;
+
+    // same address on another chain is another voter
+    client.exec_vote(poll_id, 0).await.unwrap();
+    let mut other = app.clone().into_contract_client();
+    other.id = 1;
+    other.exec_vote(poll_id, 1).await.unwrap();
+    let acks = app.as_mut().ibc_relay_all().unwrap();
+    assert!(acks.iter().all(|ack| matches!(ack, IbcAck::Success(IbcAckSuccess::VoteCounted { }))));
+
+    // but only one vote each
+    client.exec_vote(poll_id, 1).await.unwrap();
+    match &app.as_mut().ibc_relay_all().unwrap()[0] {
+        IbcAck::Error(err) => assert_eq!(err.code, IbcAckErrorCode::Rejected),
+        ack => panic!("expected the vote to be rejected, got {:?}", ack),
+    }
+
+    let mut server = app.clone().into_contract_server();
+    let tally = server.query_poll(poll_id).await.unwrap().tally;
+    assert_eq!(tally.votes, vec![1, 1]);
+    assert!(!tally.closed);
+
+    // nothing to close yet
+    server.exec_close_polls().await.unwrap();
+    assert!(app.as_mut().ibc_in_flight().unwrap().is_empty());
+
+    app.as_mut().update_block(|block| block.time = block.time.plus_seconds(100));
+    assert!(client.exec_vote(poll_id, 0).await.is_err());
+
+    server.exec_close_polls().await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    for id in 0..client_count {
+        let mut client = app.clone().into_contract_client();
+        client.id = id;
+        let tally = &client.query_polls().await.unwrap().polls[0];
+        assert!(tally.closed);
+        assert_eq!(tally.votes, vec![1, 1]);
+    }
+}
+
+#[tokio::test]
+async fn polls_close_on_traffic() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    let closes_at = app.as_ref().block_info().time.plus_seconds(100);
+
+    let mut client = app.clone().into_contract_client();
+    client.exec_create_poll("lunch?", options(), closes_at).await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    app.as_mut().update_block(|block| block.time = block.time.plus_seconds(100));
+    client.exec_send_message("anyone?").await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    let mut other = app.clone().into_contract_client();
+    other.id = 2;
+    assert!(other.query_polls().await.unwrap().polls[0].closed);
+}
+
+#[tokio::test]
+async fn invalid_polls_are_refused() {
+    let app = TestApp::new();
+    let now = app.as_ref().block_info().time;
+
+    let mut client = app.clone().into_contract_client();
+    assert!(client.exec_create_poll("", options(), now.plus_seconds(100)).await.is_err());
+    assert!(client.exec_create_poll("one option?", vec!["yes".to_string()], now.plus_seconds(100)).await.is_err());
+    assert!(client.exec_create_poll("too late?", options(), now).await.is_err());
+}
+
+#[tokio::test]
+async fn clients_only_vote_for_their_own_users() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    let closes_at = app.as_ref().block_info().time.plus_seconds(100);
+    let rejected = |acks: Vec<IbcAck>| matches!(&acks[..], [IbcAck::Error(err)] if err.code == IbcAckErrorCode::Rejected && err.message.contains("isn't on"));
+
+    let mut client = app.clone().into_contract_client();
+    client.exec_create_poll("ship it?", options(), closes_at).await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+    let poll_id = client.query_polls().await.unwrap().polls[0].poll.id;
+
+    // kujira votes as made up users on the other chains
+    for network_id in [NetworkId::Stargaze, NetworkId::Nois, NetworkId::Neutron] {
+        let voter = Voter { network_id, user: Addr::unchecked("sender") };
+        app.as_mut().ibc_send_from_client(0, &IbcExecuteMsg::Vote { poll_id, option: 0, voter }).unwrap();
+        assert!(rejected(app.as_mut().ibc_relay_all().unwrap()));
+    }
+
+    // or starts a poll as one
+    let creator = Voter { network_id: NetworkId::Stargaze, user: Addr::unchecked("sender") };
+    app.as_mut().ibc_send_from_client(0, &IbcExecuteMsg::CreatePoll { question: "who?".to_string(), options: options(), closes_at, creator }).unwrap();
+    assert!(rejected(app.as_mut().ibc_relay_all().unwrap()));
+
+    let mut server = app.clone().into_contract_server();
+    assert_eq!(server.query_poll(poll_id).await.unwrap().tally.votes, vec![0, 0]);
+
+    // its own users still vote
+    client.exec_vote(poll_id, 0).await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+    assert_eq!(server.query_poll(poll_id).await.unwrap().tally.votes, vec![1, 0]);
+}
@@ -0,0 +1,176 @@
+use app_tests::{multitest_contract_impls::TestAppContractClient, prelude::*};
+use shared::{
+    ibc::IbcAck,
+    msg::{encryption::{EncryptedMessage, WrappedRoomKey}, ibc::IbcAckSuccess, room::RoomAccess},
+};
+use wallet::{
+    encryption::{decrypt_message, encryption_pubkey, new_room_key, unwrap_room_key, wrap_room_key, RoomKey},
+    signed::signer_address,
+};
+
+// multitest's addresses
+const PREFIX: &str = "cosmwasm";
+const ROOM: &str = "kujira/secret";
+
+// an account, and the secret behind its published encryption key
+struct Member {
+    address: String,
+    secret: [u8; 32],
+}
+
+impl Member {
+    fn new(n: u8) -> Self {
+        Self {
+            address: signer_address(&[n; 32], PREFIX).unwrap(),
+            secret: [n + 100; 32],
+        }
+    }
+
+    fn client(&self, app: &TestApp) -> TestAppContractClient {
+        let mut client = app.clone().into_contract_client();
+        client.id = 0;
+        client.sender = Addr::unchecked(&self.address);
+        client
+    }
+
+    async fn publish_key(&self, app: &TestApp) {
+        self.client(app).exec_set_encryption_key(Some(encryption_pubkey(&self.secret).unwrap())).await.unwrap();
+    }
+
+    // the newest room key the member was given
+    async fn room_key(&self, app: &TestApp) -> (u32, RoomKey) {
+        let keys = self.client(app).query_room_keys(ROOM, &self.address).await.unwrap().keys;
+        let key = keys.last().unwrap();
+        (key.epoch, unwrap_room_key(&self.secret, &key.key).unwrap())
+    }
+}
+
+fn wrap_for(room_key: &RoomKey, members: &[&Member]) -> Vec<WrappedRoomKey> {
+    members.iter()
+        .map(|member| wrap_room_key(room_key, &member.address, encryption_pubkey(&member.secret).unwrap().as_slice()).unwrap())
+        .collect()
+}
+
+async fn create_room(app: &TestApp, owner: &Member, members: &[&Member]) -> RoomKey {
+    for member in members {
+        member.publish_key(app).await;
+    }
+
+    let room_key = new_room_key();
+    owner.client(app).exec_create_private_room("secret", wrap_for(&room_key, members)).await.unwrap();
+
+    let acks = app.as_mut().ibc_relay_all().unwrap();
+    assert!(matches!(acks[0], IbcAck::Success(IbcAckSuccess::RoomBroadcast { .. })));
+
+    room_key
+}
+
+#[tokio::test]
+async fn members_read_each_other() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    let (alice, bob) = (Member::new(1), Member::new(2));
+    create_room(&app, &alice, &[&alice, &bob]).await;
+
+    // membership is public, every client knows it
+    let mut other = app.clone().into_contract_client();
+    other.id = 1;
+    let rooms = other.query_rooms().await.unwrap().rooms;
+    assert!(matches!(&rooms[0].access, RoomAccess::Private { members, epoch: 0 } if members.len() == 2));
+
+    let (epoch, key) = alice.room_key(&app).await;
+    alice.client(&app).exec_send_encrypted_message(ROOM, &key, epoch, "the plan").await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    // the contracts only see ciphertext
+    let message = other.query_chat_messages(None, None).await.unwrap().messages.remove(0).msg;
+    assert!(!message.message.contains("the plan"));
+
+    let (_, bobs_key) = bob.room_key(&app).await;
+    let encrypted = EncryptedMessage::from_message_text(&message.message).unwrap();
+    assert_eq!(decrypt_message(&bobs_key, &encrypted).unwrap(), "the plan");
+}
+
+#[tokio::test]
+async fn only_members_post_ciphertext() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    let (alice, bob, eve) = (Member::new(1), Member::new(2), Member::new(3));
+    create_room(&app, &alice, &[&alice, &bob]).await;
+    let (epoch, key) = alice.room_key(&app).await;
+
+    let err = eve.client(&app).exec_send_encrypted_message(ROOM, &key, epoch, "hi").await.unwrap_err();
+    assert!(format!("{:?}", err).contains("only for its members"));
+
+    let err = bob.client(&app).exec_send_room_message(ROOM, "in the clear").await.unwrap_err();
+    assert!(format!("{:?}", err).contains("must be encrypted"));
+
+    // a key needs somewhere to go
+    let err = alice.client(&app).exec_add_room_member(ROOM, wrap_room_key(&key, &eve.address, &[0; 32]).unwrap()).await.unwrap_err();
+    assert!(format!("{:?}", err).contains("hasn't published an encryption key"));
+
+    // and private rooms can't be opened up
+    let err = alice.client(&app).exec_set_room_access(ROOM, RoomAccess::Open).await.unwrap_err();
+    assert!(format!("{:?}", err).contains("stays that way"));
+}
+
+#[tokio::test]
+async fn leaving_needs_a_new_key() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    let (alice, bob, carol) = (Member::new(1), Member::new(2), Member::new(3));
+    create_room(&app, &alice, &[&alice, &bob, &carol]).await;
+    let (old_epoch, old_key) = alice.room_key(&app).await;
+
+    carol.client(&app).exec_leave_room(ROOM).await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    // carol still has the key, so nobody posts with it
+    let err = bob.client(&app).exec_send_encrypted_message(ROOM, &old_key, old_epoch, "hi").await.unwrap_err();
+    assert!(format!("{:?}", err).contains("has to rotate the key first"));
+
+    // only the ones still in the room get the new key
+    let err = alice.client(&app).exec_rotate_room_key(ROOM, wrap_for(&new_room_key(), &[&alice, &bob, &carol])).await.unwrap_err();
+    assert!(format!("{:?}", err).contains("exactly the members"));
+    alice.client(&app).exec_rotate_room_key(ROOM, wrap_for(&new_room_key(), &[&alice, &bob])).await.unwrap();
+
+    let err = bob.client(&app).exec_send_encrypted_message(ROOM, &old_key, old_epoch, "hi").await.unwrap_err();
+    assert!(format!("{:?}", err).contains("is at key epoch 1"));
+
+    let (epoch, key) = bob.room_key(&app).await;
+    assert_eq!(epoch, 1);
+    bob.client(&app).exec_send_encrypted_message(ROOM, &key, epoch, "hi").await.unwrap();
+
+    // the old messages stay readable with the old keys
+    let keys = bob.client(&app).query_room_keys(ROOM, &bob.address).await.unwrap();
+    assert_eq!(keys.epoch, 1);
+    assert_eq!(keys.keys.len(), 2);
+    assert_eq!(carol.client(&app).query_room_keys(ROOM, &carol.address).await.unwrap().keys.len(), 1);
+}
+
+#[tokio::test]
+async fn removing_rotates_right_away() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    let (alice, bob, carol) = (Member::new(1), Member::new(2), Member::new(3));
+    create_room(&app, &alice, &[&alice, &bob]).await;
+
+    // joining later gets the current key
+    carol.publish_key(&app).await;
+    let (_, key) = alice.room_key(&app).await;
+    alice.client(&app).exec_add_room_member(ROOM, wrap_for(&key, &[&carol]).remove(0)).await.unwrap();
+    assert_eq!(carol.room_key(&app).await.1, key);
+
+    let err = bob.client(&app).exec_remove_room_member(ROOM, &carol.address, wrap_for(&new_room_key(), &[&alice, &bob])).await.unwrap_err();
+    assert!(format!("{:?}", err).contains("can change"));
+
+    alice.client(&app).exec_remove_room_member(ROOM, &carol.address, wrap_for(&new_room_key(), &[&alice, &bob])).await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    let (epoch, key) = bob.room_key(&app).await;
+    assert_eq!(epoch, 1);
+    bob.client(&app).exec_send_encrypted_message(ROOM, &key, epoch, "carol is out").await.unwrap();
+
+    let err = carol.client(&app).exec_send_encrypted_message(ROOM, &key, epoch, "hi").await.unwrap_err();
+    assert!(format!("{:?}", err).contains("only for its members"));
+}
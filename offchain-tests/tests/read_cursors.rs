@@ -0,0 +1,85 @@
+use app_tests::{helpers::user, multitest_contract_impls::TestAppContractClient, prelude::*};
+use shared::msg::contract::client::RoomUnread;
+
+async fn unread(client: &mut TestAppContractClient, user: &str, room: Option<&str>) -> RoomUnread {
+    client.query_unread_counts(user).await.unwrap()
+        .rooms
+        .into_iter()
+        .find(|x| x.room.as_deref() == room)
+        .unwrap()
+}
+
+#[tokio::test]
+async fn counts_after_the_cursor() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    let (bob, mut bob_client) = user(&app, 0, 1);
+    let (_, mut alice_client) = user(&app, 1, 2);
+
+    for text in ["one", "two", "three"] {
+        alice_client.exec_send_message(text).await.unwrap();
+    }
+    app.as_mut().ibc_relay_all().unwrap();
+
+    // nothing read yet, and your own messages are never unread
+    bob_client.exec_send_message("mine").await.unwrap();
+    let main = unread(&mut bob_client, &bob, None).await;
+    assert_eq!((main.cursor, main.unread), (None, 3));
+
+    let messages = bob_client.query_chat_messages(None, None).await.unwrap().messages;
+    bob_client.exec_set_read_cursor(None, messages[1].index).await.unwrap();
+    let main = unread(&mut bob_client, &bob, None).await;
+    assert_eq!(main.unread, 1);
+    assert!(main.cursor.unwrap().id.is_some());
+
+    // and back again
+    bob_client.exec_set_read_cursor(None, messages[0].index).await.unwrap();
+    assert_eq!(unread(&mut bob_client, &bob, None).await.unread, 2);
+
+    let err = bob_client.exec_set_read_cursor(None, 100u64.into()).await.unwrap_err();
+    assert!(format!("{:?}", err).contains("no message"));
+}
+
+#[tokio::test]
+async fn cursors_share_the_server_id() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    let (bob, mut bob_client) = user(&app, 0, 1);
+    let (_, mut bob_elsewhere) = user(&app, 1, 1);
+
+    // the sender learns the id from the ack, the others with the message
+    bob_client.exec_send_message("gm").await.unwrap();
+    bob_client.exec_set_read_cursor(None, 1u64.into()).await.unwrap();
+    assert_eq!(unread(&mut bob_client, &bob, None).await.cursor.unwrap().id, None);
+    app.as_mut().ibc_relay_all().unwrap();
+
+    bob_elsewhere.exec_set_read_cursor(None, 1u64.into()).await.unwrap();
+    let here = unread(&mut bob_client, &bob, None).await.cursor.unwrap();
+    let there = unread(&mut bob_elsewhere, &bob, None).await.cursor.unwrap();
+    assert!(here.id.is_some());
+    assert_eq!(here.id, there.id);
+}
+
+#[tokio::test]
+async fn one_cursor_per_room() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    let (bob, mut bob_client) = user(&app, 0, 1);
+    let (_, mut alice_client) = user(&app, 0, 2);
+
+    alice_client.exec_create_room("general", None).await.unwrap();
+    alice_client.exec_send_message("main").await.unwrap();
+    alice_client.exec_send_room_message("kujira/general", "in the room").await.unwrap();
+    alice_client.exec_send_room_message("kujira/general", "again").await.unwrap();
+
+    let counts = bob_client.query_unread_counts(&bob).await.unwrap().rooms;
+    assert_eq!(counts.iter().map(|x| (x.room.as_deref(), x.unread)).collect::<Vec<_>>(), vec![(None, 1), (Some("kujira/general"), 2)]);
+
+    // the message has to be in that room
+    let err = bob_client.exec_set_read_cursor(None, 2u64.into()).await.unwrap_err();
+    assert!(format!("{:?}", err).contains("isn't in the main chat"));
+
+    bob_client.exec_set_read_cursor(Some("kujira/general".to_string()), 2u64.into()).await.unwrap();
+    assert_eq!(unread(&mut bob_client, &bob, Some("kujira/general")).await.unread, 1);
+    assert_eq!(unread(&mut bob_client, &bob, None).await.unread, 1);
+}
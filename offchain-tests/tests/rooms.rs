@@ -0,0 +1,245 @@
+use app_tests::prelude::*;
+use cosmwasm_std::{coins, Addr, Uint128};
+use cw_utils::Expiration;
+use cw_multi_test::BankSudo;
+use shared::{
+    ibc::{IbcAck, IbcAckErrorCode},
+    msg::{chat_message::{ChatMessage, ChatMessageKind, ChatMessageWithIndex}, content::MessageContent, ibc::{IbcAckSuccess, IbcExecuteMsg}, network::NetworkId, room::{Room, RoomAccess, RoomAttestation, RoomDenom}},
+};
+
+// client 1 is on stargaze
+const STARGAZE: usize = 1;
+
+async fn create_punks_room(app: &TestApp) {
+    let collection = app.as_ref().cw721_collection.clone();
+
+    let mut client = app.clone().into_contract_client();
+    client.id = STARGAZE;
+    client.exec_create_room("punks", Some(RoomAccess::Cw721 { collection })).await.unwrap();
+
+    let acks = app.as_mut().ibc_relay_all().unwrap();
+    assert!(matches!(acks[0], IbcAck::Success(IbcAckSuccess::RoomBroadcast { recipients: 3 })));
+}
+
+#[tokio::test]
+async fn holders_only_room() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    create_punks_room(&app).await;
+
+    // every client knows the rule
+    let client_count = app.as_ref().client_contracts.len();
+    for id in 0..client_count {
+        let mut client = app.clone().into_contract_client();
+        client.id = id;
+        let rooms = client.query_rooms().await.unwrap().rooms;
+        assert_eq!(rooms.len(), 1);
+        assert_eq!(rooms[0].id, "stargaze/punks");
+    }
+
+    let mut client = app.clone().into_contract_client();
+    client.id = STARGAZE;
+    let err = client.exec_send_room_message("stargaze/punks", "gm").await.unwrap_err();
+    assert!(format!("{:?}", err).contains("is for holders of a token"));
+
+    app.as_mut().cw721_mint("42", "sender").unwrap();
+    client.exec_send_room_message("stargaze/punks", "gm").await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    // the attestation travels with the message
+    let mut other = app.clone().into_contract_client();
+    other.id = 0;
+    let message = other.query_chat_messages(None, None).await.unwrap().messages.remove(0).msg;
+    assert_eq!(message.room.as_deref(), Some("stargaze/punks"));
+    assert!(matches!(message.attestation, Some(RoomAttestation::Cw721 { token_id, .. }) if token_id == "42"));
+
+    // holding the token doesn't help on another chain, it can't be checked from there
+    let err = other.exec_send_room_message("stargaze/punks", "gm").await.unwrap_err();
+    assert!(format!("{:?}", err).contains("post to it from stargaze"));
+}
+
+#[tokio::test]
+async fn unattested_messages_are_rejected() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    create_punks_room(&app).await;
+
+    let message = ChatMessage {
+        user: Addr::unchecked("sender"),
+        network_id: NetworkId::Kujira,
+        message: "let me in".to_string(),
+        kind: ChatMessageKind::Text,
+        content: MessageContent::Text,
+        room: Some("stargaze/punks".to_string()),
+        attestation: None,
+        expires: Expiration::Never {},
+    };
+
+    app.as_mut().ibc_send_from_client(0, &IbcExecuteMsg::SendMessageToServer {
+        message: ChatMessageWithIndex { msg: message, index: 1u64.into() },
+    }).unwrap();
+
+    // the server doesn't know the rules, the receiving clients do
+    let acks = app.as_mut().ibc_relay_all().unwrap();
+    assert!(matches!(acks[0], IbcAck::Success(IbcAckSuccess::MessageBroadcast { .. })));
+    assert!(acks[1..].iter().all(|ack| matches!(ack, IbcAck::Error(err) if err.code == IbcAckErrorCode::Rejected)));
+
+    let mut client = app.clone().into_contract_client();
+    client.id = STARGAZE;
+    assert!(client.query_chat_messages(None, None).await.unwrap().messages.is_empty());
+}
+
+#[tokio::test]
+async fn rooms_and_attestations_come_from_home() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    create_punks_room(&app).await;
+    let rejected = |acks: Vec<IbcAck>, reason: &str| matches!(&acks[..], [IbcAck::Error(err)] if err.code == IbcAckErrorCode::Rejected && err.message.contains(reason));
+
+    let collection = app.as_ref().cw721_collection.clone();
+    let attested = |network_id| IbcExecuteMsg::SendMessageToServer {
+        message: ChatMessageWithIndex {
+            msg: ChatMessage {
+                user: Addr::unchecked("sender"),
+                network_id,
+                message: "let me in".to_string(),
+                kind: ChatMessageKind::Text,
+                content: MessageContent::Text,
+                room: Some("stargaze/punks".to_string()),
+                attestation: Some(RoomAttestation::Cw721 { collection: collection.clone(), token_id: "1".to_string(), height: 1 }),
+                expires: Expiration::Never {},
+            },
+            index: 1u64.into(),
+        },
+    };
+
+    // kujira can't pass for stargaze, nor attest for its rooms as itself
+    app.as_mut().ibc_send_from_client(0, &attested(NetworkId::Stargaze)).unwrap();
+    assert!(rejected(app.as_mut().ibc_relay_all().unwrap(), "isn't on stargaze"));
+    app.as_mut().ibc_send_from_client(0, &attested(NetworkId::Kujira)).unwrap();
+    assert!(rejected(app.as_mut().ibc_relay_all().unwrap(), "home chain"));
+
+    // or take a room id before stargaze does
+    let squat = |home| Room { id: "stargaze/squat".to_string(), name: "squat".to_string(), home, access: RoomAccess::Open, creator: Addr::unchecked("sender") };
+    app.as_mut().ibc_send_from_client(0, &IbcExecuteMsg::RoomCreated { room: squat(NetworkId::Stargaze) }).unwrap();
+    assert!(rejected(app.as_mut().ibc_relay_all().unwrap(), "isn't on stargaze"));
+    app.as_mut().ibc_send_from_client(0, &IbcExecuteMsg::RoomCreated { room: squat(NetworkId::Kujira) }).unwrap();
+    assert!(rejected(app.as_mut().ibc_relay_all().unwrap(), "isn't a room of kujira"));
+
+    let mut client = app.clone().into_contract_client();
+    client.id = STARGAZE;
+    assert!(client.query_chat_messages(None, None).await.unwrap().messages.is_empty());
+    client.exec_create_room("squat", None).await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    let mut other = app.clone().into_contract_client();
+    other.id = 2;
+    let rooms = other.query_rooms().await.unwrap().rooms;
+    assert!(rooms.iter().any(|room| room.id == "stargaze/squat" && room.creator == client.sender));
+}
+
+#[tokio::test]
+async fn room_updates_only_change_the_rule() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    create_punks_room(&app).await;
+
+    let mut other = app.clone().into_contract_client();
+    other.id = 2;
+    let punks = other.query_rooms().await.unwrap().rooms.remove(0);
+    let taken_over = Room { creator: Addr::unchecked("thief"), access: RoomAccess::Open, ..punks.clone() };
+
+    // only from the room's home chain
+    app.as_mut().ibc_send_from_client(0, &IbcExecuteMsg::RoomUpdated { room: taken_over.clone() }).unwrap();
+    let acks = app.as_mut().ibc_relay_all().unwrap();
+    assert!(matches!(&acks[..], [IbcAck::Error(err)] if err.code == IbcAckErrorCode::Rejected));
+
+    // and even from there, the clients only take a new rule for a room they know
+    let nowhere = Room { id: "stargaze/nowhere".to_string(), name: "nowhere".to_string(), ..punks.clone() };
+    for room in [taken_over, nowhere] {
+        app.as_mut().ibc_send_from_client(STARGAZE, &IbcExecuteMsg::RoomUpdated { room }).unwrap();
+        let acks = app.as_mut().ibc_relay_all().unwrap();
+        assert!(matches!(acks[0], IbcAck::Success(IbcAckSuccess::RoomBroadcast { .. })));
+        assert!(acks[1..].iter().all(|ack| matches!(ack, IbcAck::Error(err) if err.code == IbcAckErrorCode::Rejected)));
+    }
+    assert_eq!(other.query_rooms().await.unwrap().rooms, vec![punks.clone()]);
+
+    let opened = Room { access: RoomAccess::Open, ..punks.clone() };
+    app.as_mut().ibc_send_from_client(STARGAZE, &IbcExecuteMsg::RoomUpdated { room: opened.clone() }).unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+    assert_eq!(other.query_rooms().await.unwrap().rooms, vec![opened]);
+}
+
+#[tokio::test]
+async fn open_rooms_from_anywhere() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+
+    let mut client = app.clone().into_contract_client();
+    client.exec_create_room("general", None).await.unwrap();
+    assert!(client.exec_create_room("general", None).await.is_err());
+    assert!(client.exec_send_room_message("kujira/nowhere", "hello?").await.is_err());
+    app.as_mut().ibc_relay_all().unwrap();
+
+    let mut other = app.clone().into_contract_client();
+    other.id = STARGAZE;
+    other.exec_send_room_message("kujira/general", "hi").await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    let message = client.query_chat_messages(None, None).await.unwrap().messages.remove(0).msg;
+    assert_eq!(message.room.as_deref(), Some("kujira/general"));
+    assert!(message.attestation.is_none());
+}
+
+#[tokio::test]
+async fn balance_gated_room() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+
+    let access = RoomAccess::Balance {
+        denom: RoomDenom::Native { denom: "ustars".to_string() },
+        min: Uint128::new(100),
+    };
+
+    // bank balances need a real address
+    let holder = app.as_ref().api().addr_make("holder");
+
+    let mut client = app.clone().into_contract_client();
+    client.id = STARGAZE;
+    client.sender = Addr::unchecked("owner");
+    client.exec_create_room("whales", Some(access)).await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+    client.sender = holder.clone();
+
+    let resp = client.query_room_access("stargaze/whales", holder.as_str()).await.unwrap();
+    assert!(!resp.qualifies);
+    let reason = resp.reason.unwrap();
+    assert!(reason.contains("at least 100 ustars, you have 0"), "{}", reason);
+
+    app.as_mut().sudo(BankSudo::Mint { to_address: holder.to_string(), amount: coins(150, "ustars") }.into()).unwrap();
+    assert!(client.query_room_access("stargaze/whales", holder.as_str()).await.unwrap().qualifies);
+    client.exec_send_room_message("stargaze/whales", "gm").await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    // only the room's owner can raise the bar, and every chain hears about it
+    let stricter = RoomAccess::Balance {
+        denom: RoomDenom::Native { denom: "ustars".to_string() },
+        min: Uint128::new(1000),
+    };
+    assert!(client.exec_set_room_access("stargaze/whales", stricter.clone()).await.is_err());
+
+    client.sender = Addr::unchecked("owner");
+    client.exec_set_room_access("stargaze/whales", stricter.clone()).await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    let mut other = app.clone().into_contract_client();
+    other.id = 0;
+    assert_eq!(other.query_rooms().await.unwrap().rooms[0].access, stricter);
+
+    // the message from before still went through with its attestation
+    let message = other.query_chat_messages(None, None).await.unwrap().messages.remove(0).msg;
+    assert!(matches!(message.attestation, Some(RoomAttestation::Balance { amount, .. }) if amount.u128() == 150));
+
+    client.sender = holder.clone();
+    assert!(client.exec_send_room_message("stargaze/whales", "gm").await.is_err());
+}
@@ -0,0 +1,73 @@
+use app_tests::prelude::*;
+use cosmwasm_std::Binary;
+use shared::msg::{content::MessageContent, signed::SignedPayload};
+use wallet::signed::{sign_chat_payload, signer_address};
+
+// multitest's addresses, and a key nobody uses
+const PREFIX: &str = "cosmwasm";
+const SECRET_KEY: [u8; 32] = [7; 32];
+
+fn payload(client: &impl ContractAddress, nonce: u64, message: &str) -> SignedPayload {
+    SignedPayload {
+        contract: client.address(),
+        nonce,
+        message: message.to_string(),
+        room: None,
+        content: MessageContent::Text,
+    }
+}
+
+#[tokio::test]
+async fn relayer_pays_signer_posts() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    let signer = signer_address(&SECRET_KEY, PREFIX).unwrap();
+
+    // submitted by someone else entirely
+    let mut client = app.clone().into_contract_client();
+    client.sender = Addr::unchecked("relayer");
+    let signed = sign_chat_payload(&SECRET_KEY, PREFIX, &payload(&client, 0, "gm, no gas here")).unwrap();
+    client.exec_send_signed_message(signed).await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    let mut other = app.clone().into_contract_client();
+    other.id = 1;
+    let message = other.query_chat_messages(None, None).await.unwrap().messages.remove(0).msg;
+    assert_eq!(message.user.as_str(), signer);
+    assert_eq!(message.message, "gm, no gas here");
+
+    assert_eq!(client.query_nonce(&signer).await.unwrap().nonce, 1);
+}
+
+#[tokio::test]
+async fn signatures_cant_be_replayed() {
+    let app = TestApp::new();
+    let mut client = app.clone().into_contract_client();
+
+    let signed = sign_chat_payload(&SECRET_KEY, PREFIX, &payload(&client, 0, "once")).unwrap();
+    client.exec_send_signed_message(signed.clone()).await.unwrap();
+    let err = client.exec_send_signed_message(signed).await.unwrap_err();
+    assert!(format!("{:?}", err).contains("expected nonce 1"));
+
+    // nor used on another client
+    let mut other = app.clone().into_contract_client();
+    other.id = 1;
+    let signed = sign_chat_payload(&SECRET_KEY, PREFIX, &payload(&client, 1, "elsewhere")).unwrap();
+    let err = other.exec_send_signed_message(signed).await.unwrap_err();
+    assert!(format!("{:?}", err).contains("was signed for"));
+
+    assert_eq!(client.query_chat_messages(None, None).await.unwrap().messages.len(), 1);
+}
+
+#[tokio::test]
+async fn tampered_payloads_are_rejected() {
+    let app = TestApp::new();
+    let mut client = app.clone().into_contract_client();
+
+    let mut signed = sign_chat_payload(&SECRET_KEY, PREFIX, &payload(&client, 0, "pay alice")).unwrap();
+    let tampered = String::from_utf8(signed.payload.to_vec()).unwrap().replace("alice", "mallory");
+    signed.payload = Binary::from(tampered.into_bytes());
+
+    let err = client.exec_send_signed_message(signed).await.unwrap_err();
+    assert!(format!("{:?}", err).contains("invalid signature"));
+}
@@ -0,0 +1,113 @@
+use app_tests::prelude::*;
+use cosmwasm_std::{coin, coins, Addr, Order};
+use cw_multi_test::BankSudo;
+use shared::msg::{contract::client::SudoMsg, memo::IbcLifecycleComplete, network::NetworkId, tip::event::TipSettledEvent};
+
+#[tokio::test]
+async fn tip_on_the_same_chain() {
+    let app = TestApp::new();
+    let author = app.as_ref().api().addr_make("author");
+    let tipper = app.as_ref().api().addr_make("tipper");
+    app.as_mut().sudo(BankSudo::Mint { to_address: tipper.to_string(), amount: coins(100, "ukuji") }.into()).unwrap();
+
+    let mut client = app.clone().into_contract_client();
+    client.sender = author.clone();
+    client.exec_send_message("gm").await.unwrap();
+
+    client.sender = tipper.clone();
+    client.exec_tip(1u64.into(), &coins(10, "ukuji")).await.unwrap();
+    client.exec_tip(1u64.into(), &coins(5, "ukuji")).await.unwrap();
+
+    assert_eq!(app.as_ref().wrap().query_balance(&author, "ukuji").unwrap().amount.u128(), 15);
+    assert_eq!(client.query_message_tips(1u64.into()).await.unwrap().total, coins(15, "ukuji"));
+    assert_eq!(client.query_user_tips(NetworkId::Kujira, author.as_str()).await.unwrap().total, coins(15, "ukuji"));
+
+    // everyone hears about it
+    let last = client.query_chat_messages(None, Some(Order::Descending)).await.unwrap().messages.remove(0).msg;
+    assert_eq!(last.message, format!("tipped {} 5ukuji", author));
+}
+
+#[tokio::test]
+async fn tip_across_chains() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    let tipper = app.as_ref().api().addr_make("tipper");
+    app.as_mut().sudo(BankSudo::Mint { to_address: tipper.to_string(), amount: vec![coin(100, "ustars"), coin(100, "uatom")] }.into()).unwrap();
+
+    // written on kujira, tipped from stargaze
+    let mut client = app.clone().into_contract_client();
+    client.exec_send_message("gm").await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    let mut other = app.clone().into_contract_client();
+    other.id = 1;
+    other.sender = tipper.clone();
+    let tip = vec![coin(10, "uatom"), coin(20, "ustars")];
+    assert!(other.exec_tip(1u64.into(), &tip).await.is_err());
+
+    other.sender = Addr::unchecked("client-admin");
+    other.exec_set_transfer_channel(NetworkId::Kujira, Some("channel-transfer".to_string())).await.unwrap();
+    other.sender = tipper.clone();
+    other.exec_tip(1u64.into(), &tip).await.unwrap();
+
+    // one transfer per denom, to the author's address on kujira
+    let transfers = app.as_mut().ibc_transfers().unwrap().clone();
+    assert_eq!(transfers.len(), 2);
+    assert!(transfers.iter().all(|transfer| transfer.channel_id == "channel-transfer" && transfer.to_address == "sender"));
+    // the funds went into escrow, not to the client contract
+    assert_eq!(app.as_ref().wrap().query_balance(&tipper, "ustars").unwrap().amount.u128(), 80);
+    assert!(app.as_ref().wrap().query_all_balances(app.as_ref().client_contracts[1].as_str()).unwrap().is_empty());
+
+    // and ask to hear back
+    let contract = app.as_ref().client_contracts[1].clone();
+    assert!(transfers.iter().all(|transfer| transfer.memo.as_deref().is_some_and(|memo| memo.contains(contract.as_str()))));
+
+    // nothing counts until it's delivered
+    assert!(other.query_user_tips(NetworkId::Kujira, "sender").await.unwrap().total.is_empty());
+    let resp = app.as_mut().ibc_transfer_ack(0, true).unwrap();
+    assert!(resp.events.iter().any(|e| e.ty.ends_with(TipSettledEvent::KEY)));
+    app.as_mut().ibc_transfer_ack(0, true).unwrap();
+    assert_eq!(other.query_user_tips(NetworkId::Kujira, "sender").await.unwrap().total, tip);
+    assert_eq!(other.query_message_tips(1u64.into()).await.unwrap().total, tip);
+
+    // and the announcement goes out with the chat packet
+    app.as_mut().ibc_relay_all().unwrap();
+    let last = client.query_chat_messages(None, Some(Order::Descending)).await.unwrap().messages.remove(0).msg;
+    assert_eq!(last.message, "tipped sender 10uatom, 20ustars");
+}
+
+#[tokio::test]
+async fn failed_tips_go_back_to_the_tipper() {
+    let app = TestApp::new();
+    app.as_mut().ibc_connect_all().unwrap();
+    let tipper = app.as_ref().api().addr_make("tipper");
+    app.as_mut().sudo(BankSudo::Mint { to_address: tipper.to_string(), amount: vec![coin(100, "ustars"), coin(100, "uatom")] }.into()).unwrap();
+
+    let mut client = app.clone().into_contract_client();
+    client.exec_send_message("gm").await.unwrap();
+    app.as_mut().ibc_relay_all().unwrap();
+
+    let mut other = app.clone().into_contract_client();
+    other.id = 1;
+    other.sender = Addr::unchecked("client-admin");
+    other.exec_set_transfer_channel(NetworkId::Kujira, Some("channel-transfer".to_string())).await.unwrap();
+    other.sender = tipper.clone();
+    other.exec_tip(1u64.into(), &[coin(10, "uatom"), coin(20, "ustars")]).await.unwrap();
+
+    // one is rejected on the other side, the other never gets there
+    app.as_mut().ibc_transfer_ack(0, false).unwrap();
+    app.as_mut().ibc_transfer_timeout(0).unwrap();
+
+    assert_eq!(app.as_ref().wrap().query_balance(&tipper, "uatom").unwrap().amount.u128(), 100);
+    assert_eq!(app.as_ref().wrap().query_balance(&tipper, "ustars").unwrap().amount.u128(), 100);
+    assert!(app.as_ref().wrap().query_all_balances(app.as_ref().client_contracts[1].as_str()).unwrap().is_empty());
+    assert!(other.query_user_tips(NetworkId::Kujira, "sender").await.unwrap().total.is_empty());
+    assert!(other.query_message_tips(1u64.into()).await.unwrap().total.is_empty());
+
+    // and each is settled once
+    let contract = app.as_ref().client_contracts[1].clone();
+    assert!(app.as_mut().wasm_sudo(
+        contract,
+        &SudoMsg::IbcLifecycleComplete(IbcLifecycleComplete::IbcTimeout { channel: "channel-transfer".to_string(), sequence: 2 }),
+    ).is_err());
+}
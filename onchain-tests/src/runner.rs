@@ -65,7 +65,7 @@ async fn test_message_broadcast() -> Result<()> {
 
         let mut client_contract = wallet.into_contract_client();
 
-        let ChatMessagesResp{messages: messages_before}  = client_contract.query_chat_messages(None, Some(Order::Descending)).await?;
+        let ChatMessagesResp{messages: messages_before, ..}  = client_contract.query_chat_messages(None, Some(Order::Descending)).await?;
 
         let mut message_cursor = messages_before.first().map(|m| m.index);
         let mut waiting_network_ids = HashSet::new();
@@ -91,7 +91,7 @@ async fn test_message_broadcast() -> Result<()> {
             let new_messages = try_until_ibc_timeout(client_contract.clone(), |client_contract| async move {
                 let mut client_contract = client_contract;
 
-                let ChatMessagesResp{messages: messages_after}  = client_contract.query_chat_messages(message_cursor, Some(Order::Ascending)).await.unwrap();
+                let ChatMessagesResp{messages: messages_after, ..}  = client_contract.query_chat_messages(message_cursor, Some(Order::Ascending)).await.unwrap();
                 if messages_after.is_empty() {
                     log::info!("No new messages on {}", network_id);
                 }
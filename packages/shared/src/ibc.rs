@@ -1,55 +1,286 @@
 //! Ibc helpers
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{to_json_binary, Binary, IbcChannel, IbcOrder};
-use anyhow::{Result, bail};
+use cosmwasm_std::{from_json, to_json_binary, to_json_string, Binary, IbcChannel, IbcOrder, StdAck};
+use anyhow::{Context, Result, bail};
+
+use crate::msg::{ibc::IbcAckSuccess, misc::ChannelOrder};
 
 /// Timeout in seconds for IBC packets
 pub const TIMEOUT_SECONDS: u64 = 60 * 2; // 2 minutes
 
-pub fn validate_ibc_channel_order_and_version(
-    channel: &IbcChannel,
-    counterparty_version: Option<&str>,
-) -> Result<()> {
-    // We expect an unordered channel here. Ordered channels have the
-    // property that if a message is lost the entire channel will stop
-    // working until you start it again.
-    if channel.order != IbcOrder::Unordered {
-        bail!("Only unordered channels are supported for this demo");
+/// Most messages we'll put in a single batch packet
+pub const MAX_BATCH_SIZE: usize = 32;
+
+/// The versions of the chat protocol that can be spoken over a channel, oldest first
+///
+/// The channel version string is either a single version (e.g. `ibc-chat-001`)
+/// or, when proposing during `OpenInit`, a comma-separated list of versions
+/// the initiator is willing to speak. The highest version supported by both sides wins.
+#[cw_serde]
+#[derive(Copy, Eq, PartialOrd, Ord, Hash)]
+pub enum IbcProtocolVersion {
+    /// JSON-encoded [IbcExecuteMsg](crate::msg::ibc::IbcExecuteMsg)
+    V1,
+    /// Protobuf-encoded [IbcExecuteMsg](crate::msg::ibc::IbcExecuteMsg), with numeric network ids
+    /// much smaller on the wire, JSON is still there as the fallback for older peers
+    V2,
+}
+
+impl IbcProtocolVersion {
+    pub fn all() -> &'static [Self] {
+        &[IbcProtocolVersion::V1, IbcProtocolVersion::V2]
     }
 
-    // Make sure that we're talking with a counterparty who speaks the
-    // same "protocol" as us.
-    //
-    // For a connection between chain A and chain B being established
-    // by chain A, chain B knows counterparty information during
-    // `OpenTry` and chain A knows counterparty information during
-    // `OpenAck`. We verify it when we have it but when we don't it's
-    // alright.
-    if let Some(counterparty_version) = counterparty_version {
-        if counterparty_version != channel.version {
-            bail!(
-                "wrong ibc counterparty version, expected {} got {}",
-                channel.version,
-                counterparty_version,
-            );
+    pub fn latest() -> Self {
+        *Self::all().last().unwrap()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IbcProtocolVersion::V1 => "ibc-chat-001",
+            IbcProtocolVersion::V2 => "ibc-chat-002",
         }
     }
 
+    /// The channel version string proposing every version we support
+    pub fn all_as_proposal() -> String {
+        Self::all()
+            .iter()
+            .map(|version| version.as_str())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Parse a (possibly comma-separated) channel version string
+    /// unknown entries are skipped, so that newer counterparties can still propose to older ones
+    pub fn parse_proposal(s: &str) -> Vec<Self> {
+        s.split(',')
+            .filter_map(|version| version.trim().parse().ok())
+            .collect()
+    }
+}
+
+impl std::fmt::Display for IbcProtocolVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for IbcProtocolVersion {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "ibc-chat-001" => Ok(IbcProtocolVersion::V1),
+            "ibc-chat-002" => Ok(IbcProtocolVersion::V2),
+            _ => bail!("Unknown ibc protocol version: {}", s),
+        }
+    }
+}
+
+/// Get the agreed protocol version of an established channel
+pub fn channel_protocol_version(channel: &IbcChannel) -> Result<IbcProtocolVersion> {
+    channel.version.parse()
+}
+
+fn validate_ibc_channel_order(channel: &IbcChannel, order: ChannelOrder) -> Result<()> {
+    // The ordering is a deployment option. Unordered is the default, ordered channels
+    // have the property that if a message is lost the entire channel will stop
+    // working until you start it again (see the timeout handlers)
+    if channel.order != order.into() {
+        bail!("this contract is deployed for {} channels, got {:?}", order, channel.order);
+    }
+
     Ok(())
 }
 
+/// Handles the version part of `OpenInit` and `OpenTry`
+/// returns the version string that the channel should be opened with
+pub fn negotiate_ibc_channel_open(
+    channel: &IbcChannel,
+    counterparty_version: Option<&str>,
+    order: ChannelOrder,
+) -> Result<String> {
+    validate_ibc_channel_order(channel, order)?;
+
+    match counterparty_version {
+        // OpenTry: the counterparty proposed a set of versions, pick the highest one we share
+        Some(counterparty_version) => {
+            let version = IbcProtocolVersion::parse_proposal(counterparty_version)
+                .into_iter()
+                .filter(|version| IbcProtocolVersion::all().contains(version))
+                .max()
+                .with_context(|| format!("no common ibc protocol version, we support {} got {}", IbcProtocolVersion::all_as_proposal(), counterparty_version))?;
+
+            Ok(version.to_string())
+        },
+        // OpenInit: we are proposing, an empty version means "everything we support"
+        None => {
+            if channel.version.is_empty() {
+                return Ok(IbcProtocolVersion::all_as_proposal());
+            }
+
+            if IbcProtocolVersion::parse_proposal(&channel.version).is_empty() {
+                bail!("no supported ibc protocol version in proposal {}", channel.version);
+            }
+
+            Ok(channel.version.clone())
+        }
+    }
+}
+
+/// Handles the version part of `OpenAck` and `OpenConfirm`
+/// returns the protocol version that was agreed on for this channel
+pub fn negotiate_ibc_channel_connect(
+    channel: &IbcChannel,
+    counterparty_version: Option<&str>,
+    order: ChannelOrder,
+) -> Result<IbcProtocolVersion> {
+    validate_ibc_channel_order(channel, order)?;
+
+    // For a connection between chain A and chain B being established
+    // by chain A, chain B picks the version during `OpenTry` and chain A
+    // learns about it during `OpenAck`. By `OpenConfirm` the channel
+    // version on chain B is already the agreed one.
+    match counterparty_version {
+        // OpenAck: the counterparty must have picked something from our proposal
+        Some(counterparty_version) => {
+            let version:IbcProtocolVersion = counterparty_version.parse()?;
+            let proposal = if channel.version.is_empty() {
+                IbcProtocolVersion::all().to_vec()
+            } else {
+                IbcProtocolVersion::parse_proposal(&channel.version)
+            };
+
+            if !proposal.contains(&version) {
+                bail!(
+                    "wrong ibc counterparty version, expected one of {} got {}",
+                    channel.version,
+                    counterparty_version,
+                );
+            }
+
+            Ok(version)
+        },
+        // OpenConfirm
+        None => channel_protocol_version(channel)
+    }
+}
+
 /// IBC ACK. See:
 /// https://github.com/cosmos/cosmos-sdk/blob/f999b1ff05a4db4a338a855713864497bedd4396/proto/ibc/core/channel/v1/channel.proto#L141-L147
+///
+/// On the wire this is always a standard [StdAck], the typed payload is JSON inside of it
+/// so that relayers and explorers still see a regular result/error acknowledgement
 #[cw_serde]
 pub enum IbcAck {
-    Success,
-    // error must be a string
-    Error(String),
+    Success(IbcAckSuccess),
+    Error(IbcAckError),
+}
+
+impl IbcAck {
+    /// Encode into the standard acknowledgement envelope
+    pub fn to_std_ack(&self) -> Result<StdAck> {
+        Ok(match self {
+            IbcAck::Success(success) => StdAck::success(to_json_binary(success)?),
+            IbcAck::Error(error) => StdAck::error(to_json_string(error)?),
+        })
+    }
+
+    /// Decode the raw acknowledgement data as received in `ibc_packet_ack`
+    pub fn decode(data: &Binary) -> Result<Self> {
+        Ok(match from_json::<StdAck>(data)? {
+            StdAck::Success(data) => IbcAck::Success(
+                // peers that predate typed acks just send `\x01`
+                from_json(&data).unwrap_or(IbcAckSuccess::Unspecified)
+            ),
+            StdAck::Error(error) => IbcAck::Error(
+                from_json(error.as_bytes()).unwrap_or(IbcAckError {
+                    code: IbcAckErrorCode::Unknown,
+                    message: error,
+                })
+            ),
+        })
+    }
+}
+
+impl IbcAck {
+    /// Like [IbcAck::decode], but an ack that doesn't decode is an error ack
+    /// the packet still has to be settled, or the ack would fail on every relay
+    pub fn decode_or_error(data: &Binary) -> Self {
+        Self::decode(data).unwrap_or_else(|err| IbcAck::Error(IbcAckError::new(
+            IbcAckErrorCode::DecodeFailed,
+            format!("couldn't decode the acknowledgement: {}", err),
+        )))
+    }
+}
+
+impl From<anyhow::Error> for IbcAck {
+    fn from(err: anyhow::Error) -> Self {
+        IbcAck::Error(match err.downcast::<IbcAckError>() {
+            Ok(error) => error,
+            Err(err) => IbcAckError {
+                code: IbcAckErrorCode::Internal,
+                message: err.to_string(),
+            },
+        })
+    }
+}
+
+/// Failure reported back to the sender of a packet
+/// handlers can `bail!` with this directly to pick a specific code
+#[cw_serde]
+#[derive(thiserror::Error)]
+#[error("{code}: {message}")]
+pub struct IbcAckError {
+    pub code: IbcAckErrorCode,
+    pub message: String,
+}
+
+impl IbcAckError {
+    pub fn new(code: IbcAckErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+#[cw_serde]
+#[derive(Copy, Eq, Hash)]
+pub enum IbcAckErrorCode {
+    /// The packet data could not be decoded
+    DecodeFailed,
+    /// The packet arrived on a channel the receiver doesn't know about
+    UnknownChannel,
+    /// The message is valid but not accepted by this side of the channel
+    UnsupportedMessage,
+    /// The message is understood but breaks a rule, e.g. a second vote in the same poll
+    Rejected,
+    /// Any other error while handling the packet
+    Internal,
+    /// The counterparty sent an error without a code
+    Unknown,
+}
+
+impl std::fmt::Display for IbcAckErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IbcAckErrorCode::DecodeFailed => write!(f, "decode-failed"),
+            IbcAckErrorCode::UnknownChannel => write!(f, "unknown-channel"),
+            IbcAckErrorCode::UnsupportedMessage => write!(f, "unsupported-message"),
+            IbcAckErrorCode::Rejected => write!(f, "rejected"),
+            IbcAckErrorCode::Internal => write!(f, "internal"),
+            IbcAckErrorCode::Unknown => write!(f, "unknown"),
+        }
+    }
 }
 
 /// Common IBC Events
 pub mod event {
-    use cosmwasm_std::{Event, IbcChannel};
+    use cosmwasm_std::{to_json_string, Event, IbcChannel, IbcPacket};
+
+    use super::IbcAck;
 
     /// IBC Channel Connect Event
     #[derive(Debug)]
@@ -77,6 +308,47 @@ pub mod event {
         }
     }
 
+    /// A client whose channel died stayed away too long, what was queued for it is dropped
+    #[derive(Debug)]
+    pub struct DeadClientForgottenEvent<'a> {
+        /// The dead channel
+        pub channel: &'a IbcChannel,
+    }
+
+    impl<'a> From<DeadClientForgottenEvent<'a>> for Event {
+        fn from(src: DeadClientForgottenEvent) -> Self {
+            mixin_ibc_channel(Event::new("dead-client-forgotten"), src.channel)
+        }
+    }
+
+    /// IBC Packet Ack Event, emitted by the sender once it learns the outcome
+    #[derive(Debug)]
+    pub struct IbcPacketAckEvent<'a> {
+        /// The original packet
+        pub packet: &'a IbcPacket,
+        /// The decoded acknowledgement
+        pub ack: &'a IbcAck,
+    }
+
+    impl<'a> From<IbcPacketAckEvent<'a>> for Event {
+        fn from(src: IbcPacketAckEvent) -> Self {
+            let event = Event::new("ibc-packet-ack")
+                .add_attribute("sequence", src.packet.sequence.to_string())
+                .add_attribute("src-channel-id", &src.packet.src.channel_id)
+                .add_attribute("dest-channel-id", &src.packet.dest.channel_id);
+
+            match src.ack {
+                IbcAck::Success(success) => event
+                    .add_attribute("outcome", "success")
+                    .add_attribute("result", to_json_string(success).unwrap_or_default()),
+                IbcAck::Error(error) => event
+                    .add_attribute("outcome", "error")
+                    .add_attribute("error-code", error.code.to_string())
+                    .add_attribute("error", &error.message),
+            }
+        }
+    }
+
     fn mixin_ibc_channel(event: Event, channel: &IbcChannel) -> Event {
         event
             .add_attribute("endpoint-id", &channel.endpoint.channel_id)
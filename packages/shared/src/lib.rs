@@ -5,4 +5,5 @@ pub mod response;
 pub mod ibc;
 pub mod tx;
 pub mod event;
-pub mod contract_kind;
\ No newline at end of file
+pub mod contract_kind;
+pub mod storage;
\ No newline at end of file
@@ -2,13 +2,94 @@ use cosmwasm_schema::{QueryResponses, cw_serde};
 use cosmwasm_std::{Addr, Binary, BlockInfo, IbcChannel, Uint64};
 use cw_utils::Expiration;
 
-use crate::msg::{misc::Order, network::NetworkId};
+use crate::msg::{content::MessageContent, misc::Order, network::NetworkId, room::{RoomAttestation, RoomId}};
 
 #[cw_serde]
 pub struct ChatMessage {
     pub user: Addr,
     pub network_id: NetworkId,
     pub message: String,
+    /// left out of the JSON for plain text, so older contracts still understand it
+    #[serde(default, skip_serializing_if = "ChatMessageKind::is_text")]
+    pub kind: ChatMessageKind,
+    /// Also left out of the JSON for plain text, so old messages read as text
+    #[serde(default, skip_serializing_if = "MessageContent::is_text")]
+    pub content: MessageContent,
+    /// `None` is the main chat, also left out of the JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room: Option<RoomId>,
+    /// Set by the home client of a gated room, see [crate::msg::room::Room::honors]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attestation: Option<RoomAttestation>,
+    /// Hidden from queries once expired, and deleted along the way, on every chain it's on
+    /// a height only means something on its own chain, so those messages stay there, see [validate_expiration]
+    #[serde(default, skip_serializing_if = "never_expires")]
+    pub expires: Expiration,
+}
+
+fn never_expires(expires: &Expiration) -> bool {
+    *expires == Expiration::Never {}
+}
+
+/// For a message that goes to other chains
+/// block heights differ from chain to chain, so only a time means the same thing everywhere
+pub fn validate_expiration(expires: &Expiration) -> anyhow::Result<()> {
+    match expires {
+        Expiration::AtHeight(_) => anyhow::bail!("block heights differ between chains, only messages kept on their own chain expire at a height"),
+        Expiration::AtTime(_) | Expiration::Never {} => Ok(()),
+    }
+}
+
+#[cw_serde]
+#[derive(Eq, Copy, Default)]
+pub enum ChatMessageKind {
+    /// Written by the user
+    #[default]
+    Text,
+    /// `/me waves`, shown as "* user waves"
+    Action,
+    /// Written by the contract itself, e.g. the result of a command
+    /// the user is the contract, and it's never sent to the server
+    System,
+    /// A chain joined or left the chat, written by the server
+    /// the user is the server contract and the network is the chain in question
+    Network,
+    /// From the server's admin to every chain, shown above the rest
+    /// the user is the server contract
+    Announcement,
+}
+
+impl ChatMessageKind {
+    pub fn is_text(&self) -> bool {
+        *self == ChatMessageKind::Text
+    }
+}
+
+impl std::fmt::Display for ChatMessageKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChatMessageKind::Text => write!(f, "text"),
+            ChatMessageKind::Action => write!(f, "action"),
+            ChatMessageKind::System => write!(f, "system"),
+            ChatMessageKind::Network => write!(f, "network"),
+            ChatMessageKind::Announcement => write!(f, "announcement"),
+        }
+    }
+}
+
+impl std::str::FromStr for ChatMessageKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "text" => Ok(ChatMessageKind::Text),
+            "action" => Ok(ChatMessageKind::Action),
+            "system" => Ok(ChatMessageKind::System),
+            "network" => Ok(ChatMessageKind::Network),
+            "announcement" => Ok(ChatMessageKind::Announcement),
+            _ => Err(anyhow::anyhow!("Unknown message kind: {}", s)),
+        }
+    }
 }
 
 #[cw_serde]
@@ -21,9 +102,15 @@ pub struct ChatMessageWithIndex {
 /// it's merely the per-client index of the message to help with pagination
 pub type ChatMessageIndex = Uint64;
 
+/// This id IS globally unique, it's assigned by the server
+/// when the message passes through it on the way to the other clients
+pub type ChatMessageId = Uint64;
+
 pub mod event {
-    use cosmwasm_std::{Addr, Event};
+    use cosmwasm_std::{Addr, Event, Timestamp};
+    use cw_utils::Expiration;
     use anyhow::{Error, anyhow};
+    use cosmwasm_std::{from_json, to_json_string};
     use crate::event::CosmwasmEventExt;
 
     use super::{ChatMessage, ChatMessageWithIndex};
@@ -45,8 +132,24 @@ pub mod event {
                 ("user", src.message.msg.user.to_string()),
                 ("network-id", src.message.msg.network_id.to_string()),
                 ("message", src.message.msg.message),
+                ("kind", src.message.msg.kind.to_string()),
             ]);
 
+            if !src.message.msg.content.is_text() {
+                // the JSON, the content's fields don't fit in one attribute otherwise
+                if let Ok(content) = to_json_string(&src.message.msg.content) {
+                    event = event.add_attribute("content", content);
+                }
+            }
+
+            if let Some(room) = src.message.msg.room {
+                event = event.add_attribute("room", room);
+            }
+
+            if let Expiration::AtTime(time) = src.message.msg.expires {
+                event = event.add_attribute("expires", time.nanos().to_string());
+            }
+
             event
         }
     }
@@ -65,6 +168,15 @@ pub mod event {
                         user: Addr::unchecked(evt.string_attr("user")?),
                         network_id: evt.string_attr("network-id")?.parse()?,
                         message: evt.string_attr("message")?,
+                        kind: evt.try_map_attr("kind", |s| s.parse()).transpose()?.unwrap_or_default(),
+                        content: evt.try_map_attr("content", |s| from_json(s.as_bytes())).transpose()?.unwrap_or_default(),
+                        room: evt.try_map_attr("room", |s| s.to_string()),
+                        // too big for an attribute, the stored message has it
+                        attestation: None,
+                        expires: evt.try_map_attr("expires", |s| s.parse::<u64>())
+                            .transpose()?
+                            .map(|nanos| Expiration::AtTime(Timestamp::from_nanos(nanos)))
+                            .unwrap_or_default(),
                     },
                     index: evt.u64_attr("index")?.into(),
                 }
@@ -0,0 +1,72 @@
+use anyhow::{anyhow, bail, Result};
+
+use super::dice::Dice;
+
+/// A chat message starting with `/` is a command, e.g. `/nick alice`
+/// it's handled by the client contract instead of being stored as-is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChatCommand {
+    /// `/nick <name>`, sets the sender's nickname on this chain
+    Nick { name: String },
+    /// `/me <action>`, sent to everyone as an action message
+    Me { action: String },
+    /// `/topic <topic>`, sets the topic of the chat on this chain
+    Topic { topic: String },
+    /// `/roll [dice]`, e.g. `/roll 2d6`, rolled with Nois randomness
+    Roll { dice: Dice },
+    /// `/help`, lists the commands
+    Help,
+}
+
+impl ChatCommand {
+    pub const PREFIX: char = '/';
+
+    pub const MAX_NICK_LEN: usize = 32;
+
+    /// One line per command, for `/help`
+    pub const HELP: &'static [&'static str] = &[
+        "/nick <name> - set your nickname, if nobody here has it",
+        "/me <action> - say what you're doing",
+        "/topic <topic> - set the topic, only the owner can",
+        "/roll [dice] - roll dice with Nois randomness, e.g. /roll 2d6",
+        "/help - show this list",
+    ];
+
+    /// `None` if the text isn't a command at all
+    /// otherwise the parsed command, or why it couldn't be parsed
+    pub fn parse(text: &str) -> Option<Result<Self>> {
+        let text = text.strip_prefix(Self::PREFIX)?;
+
+        let (name, args) = match text.split_once(char::is_whitespace) {
+            Some((name, args)) => (name, args.trim()),
+            None => (text, ""),
+        };
+
+        Some(Self::parse_args(name, args))
+    }
+
+    fn parse_args(name: &str, args: &str) -> Result<Self> {
+        let required = |usage: &str| match args {
+            "" => Err(anyhow!("usage: {}", usage)),
+            args => Ok(args.to_string()),
+        };
+
+        match name {
+            "nick" => {
+                let name = required("/nick <name>")?;
+                if name.chars().count() > Self::MAX_NICK_LEN || name.contains(char::is_whitespace) {
+                    bail!("a nickname is a single word of at most {} characters", Self::MAX_NICK_LEN);
+                }
+                Ok(ChatCommand::Nick { name })
+            },
+            "me" => Ok(ChatCommand::Me { action: required("/me <action>")? }),
+            "topic" => Ok(ChatCommand::Topic { topic: required("/topic <topic>")? }),
+            "roll" => match args {
+                "" => Ok(ChatCommand::Roll { dice: Dice::default() }),
+                args => Ok(ChatCommand::Roll { dice: args.parse()? }),
+            },
+            "help" => Ok(ChatCommand::Help),
+            name => Err(anyhow!("unknown command /{}, try /help", name)),
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! What a chat message is, beyond plain text
+//!
+//! The text itself always stays in [ChatMessage::message](crate::msg::chat_message::ChatMessage::message),
+//! as the markdown source, the caption of a link or attachment, or the notice.
+//! Anything that doesn't know about content, e.g. an older contract, still shows that
+use anyhow::{bail, Result};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::Uint128;
+
+use super::chat_message::ChatMessageKind;
+
+pub const MAX_URL_LEN: usize = 2048;
+pub const MAX_PREVIEW_TITLE_LEN: usize = 256;
+pub const MAX_PREVIEW_DESCRIPTION_LEN: usize = 1024;
+pub const MAX_MIME_LEN: usize = 128;
+pub const MAX_CID_LEN: usize = 128;
+
+#[cw_serde]
+#[derive(Default)]
+pub enum MessageContent {
+    /// Shown as-is
+    #[default]
+    Text,
+    /// The text is markdown, without any HTML
+    Markdown,
+    /// The text is a caption for the link
+    Link {
+        url: String,
+        /// Looked up by the sender, nobody checks it
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        preview: Option<LinkPreview>,
+    },
+    /// A file on IPFS or anything else content-addressed, the text is a caption
+    Attachment {
+        cid: String,
+        mime: String,
+        /// In bytes
+        size: u64,
+    },
+    /// An amount of a token, or just the token when there's no amount
+    Token {
+        denom: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        amount: Option<Uint128>,
+    },
+    /// Only in [ChatMessageKind::System] and [ChatMessageKind::Network] messages, written by the contracts
+    Notice,
+}
+
+#[cw_serde]
+pub struct LinkPreview {
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+}
+
+impl MessageContent {
+    pub fn is_text(&self) -> bool {
+        *self == MessageContent::Text
+    }
+
+    pub fn is_notice(&self) -> bool {
+        *self == MessageContent::Notice
+    }
+
+    /// The rules for each kind of content, `text` is the message text that goes with it
+    pub fn validate(&self, kind: ChatMessageKind, text: &str) -> Result<()> {
+        match (self, kind) {
+            (MessageContent::Notice, ChatMessageKind::System | ChatMessageKind::Network) => {},
+            (MessageContent::Notice, _) => bail!("only the contract writes notices"),
+            (_, ChatMessageKind::System | ChatMessageKind::Network) => bail!("system messages are notices"),
+            (MessageContent::Text | MessageContent::Markdown, ChatMessageKind::Announcement) => {},
+            (_, ChatMessageKind::Announcement) => bail!("an announcement is text or markdown"),
+            (MessageContent::Text | MessageContent::Markdown, ChatMessageKind::Action) => {},
+            (_, ChatMessageKind::Action) => bail!("an action is only text"),
+            (_, ChatMessageKind::Text) => {},
+        }
+
+        match self {
+            MessageContent::Text | MessageContent::Notice => {},
+            MessageContent::Markdown => {
+                if text.trim().is_empty() {
+                    bail!("empty markdown");
+                }
+                if has_html_tag(text) {
+                    bail!("markdown can't have HTML in it");
+                }
+            },
+            MessageContent::Link { url, preview } => {
+                validate_url(url)?;
+                if let Some(LinkPreview { title, description, image }) = preview {
+                    if title.is_empty() || title.chars().count() > MAX_PREVIEW_TITLE_LEN {
+                        bail!("a preview title has between 1 and {} characters", MAX_PREVIEW_TITLE_LEN);
+                    }
+                    if description.as_ref().is_some_and(|x| x.chars().count() > MAX_PREVIEW_DESCRIPTION_LEN) {
+                        bail!("a preview description has at most {} characters", MAX_PREVIEW_DESCRIPTION_LEN);
+                    }
+                    if let Some(image) = image {
+                        validate_url(image)?;
+                    }
+                }
+            },
+            MessageContent::Attachment { cid, mime, size } => {
+                validate_cid(cid)?;
+                validate_mime(mime)?;
+                if *size == 0 {
+                    bail!("an attachment can't be empty");
+                }
+            },
+            MessageContent::Token { denom, amount } => {
+                validate_denom(denom)?;
+                if amount.is_some_and(|x| x.is_zero()) {
+                    bail!("leave the amount out instead of zero");
+                }
+            },
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for MessageContent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MessageContent::Text => write!(f, "text"),
+            MessageContent::Markdown => write!(f, "markdown"),
+            MessageContent::Link { .. } => write!(f, "link"),
+            MessageContent::Attachment { .. } => write!(f, "attachment"),
+            MessageContent::Token { .. } => write!(f, "token"),
+            MessageContent::Notice => write!(f, "notice"),
+        }
+    }
+}
+
+/// Only http(s), and nothing that could break out of an attribute
+pub fn validate_url(url: &str) -> Result<()> {
+    if !(url.starts_with("https://") || url.starts_with("http://")) {
+        bail!("a url starts with https:// or http://");
+    }
+    if url.len() > MAX_URL_LEN {
+        bail!("a url has at most {} characters", MAX_URL_LEN);
+    }
+    if url.chars().any(|c| c.is_whitespace() || c.is_control() || c == '"' || c == '<' || c == '>') {
+        bail!("invalid url: {}", url);
+    }
+
+    Ok(())
+}
+
+/// A CIDv0 (`Qm…`, base58) or a base32 CIDv1 (`b…`)
+pub fn validate_cid(cid: &str) -> Result<()> {
+    const BASE58: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+    let valid = if cid.starts_with("Qm") {
+        cid.len() == 46 && cid.chars().all(|c| BASE58.contains(c))
+    } else if let Some(rest) = cid.strip_prefix('b') {
+        (8..MAX_CID_LEN).contains(&rest.len()) && rest.chars().all(|c| c.is_ascii_lowercase() || ('2'..='7').contains(&c))
+    } else {
+        false
+    };
+
+    if !valid {
+        bail!("invalid cid: {}", cid);
+    }
+
+    Ok(())
+}
+
+/// `type/subtype`, with an optional `;` and parameters
+pub fn validate_mime(mime: &str) -> Result<()> {
+    fn is_token(s: &str) -> bool {
+        !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || "!#$&^_.+-".contains(c))
+    }
+
+    let essence = mime.split(';').next().unwrap_or_default();
+    let valid = mime.len() <= MAX_MIME_LEN && match essence.split_once('/') {
+        Some((ty, subtype)) => is_token(ty) && is_token(subtype),
+        None => false,
+    };
+
+    if !valid {
+        bail!("invalid mime type: {}", mime);
+    }
+
+    Ok(())
+}
+
+/// Like the Cosmos SDK's denom rule, so cw20 addresses and ibc/… denoms fit too
+pub fn validate_denom(denom: &str) -> Result<()> {
+    let valid = (3..=128).contains(&denom.len())
+        && denom.starts_with(|c: char| c.is_ascii_alphabetic())
+        && denom.chars().all(|c| c.is_ascii_alphanumeric() || "/:._-".contains(c));
+
+    if !valid {
+        bail!("invalid denom: {}", denom);
+    }
+
+    Ok(())
+}
+
+// `<` followed by a tag name or `/`, a plain `a < b` is fine
+fn has_html_tag(text: &str) -> bool {
+    text.as_bytes()
+        .windows(2)
+        .any(|w| w[0] == b'<' && (w[1].is_ascii_alphabetic() || w[1] == b'/' || w[1] == b'!'))
+}
@@ -1,18 +1,227 @@
 use cosmwasm_schema::{QueryResponses, cw_serde};
-use cosmwasm_std::{Addr, Binary, BlockInfo, IbcChannel, Uint64};
+use cosmwasm_std::{Addr, Binary, BlockInfo, Coin, IbcChannel, Timestamp, Uint64};
 use cw_utils::Expiration;
 
-use crate::msg::{chat_message::{ChatMessageIndex, ChatMessageWithIndex}, misc::Order, network::NetworkId};
+use crate::msg::{chat_message::{ChatMessageId, ChatMessageIndex, ChatMessageWithIndex}, content::MessageContent, encryption::WrappedRoomKey, hook::HookFilter, identity::IdentityId, memo::IbcLifecycleComplete, mention::Mention, misc::{ChannelOrder, Order}, network::NetworkId, nois::NoisCallback, pin::PinnedMessage, poll::{PollId, PollTally}, room::{Room, RoomAccess, RoomId}};
 
 #[cw_serde]
 pub struct InstantiateMsg {
-    pub network_id: NetworkId
+    pub network_id: NetworkId,
+    // default is [ChannelOrder::Unordered]
+    pub channel_order: Option<ChannelOrder>,
+    /// The Nois proxy on this chain, needed for `/roll`
+    pub nois_proxy: Option<String>,
+}
+
+/// Only called by the chain
+#[cw_serde]
+pub enum SudoMsg {
+    /// A tip's transfer is done, see [crate::msg::tip::PendingTip]
+    IbcLifecycleComplete(IbcLifecycleComplete),
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
+    /// A message starting with `/` is a command, see [crate::msg::command::ChatCommand]
     SendMessage {
-        message: String
+        message: String,
+        // default is the main chat
+        room: Option<RoomId>,
+        /// A height keeps the message on this chain, see [crate::msg::chat_message::validate_expiration]
+        // default is [Expiration::Never]
+        expires: Option<Expiration>,
+        /// What the message is, see [MessageContent::validate]
+        // default is plain text
+        #[serde(default)]
+        content: Option<MessageContent>,
+    },
+
+    /// Like [ExecuteMsg::SendMessage], but held back so that several messages
+    /// go to the server in a single packet. Sent on the next [ExecuteMsg::FlushBuffer]
+    /// or [ExecuteMsg::SendMessage], or when the buffer is full
+    BufferMessage {
+        message: String,
+        // default is the main chat
+        room: Option<RoomId>,
+        // default is [Expiration::Never]
+        expires: Option<Expiration>,
+        // default is plain text
+        #[serde(default)]
+        content: Option<MessageContent>,
+    },
+
+    /// A message signed off-chain, see [crate::msg::signed]
+    /// anyone can submit it and pay the gas, it's from the signer
+    SendSignedMessage {
+        /// The JSON of a [crate::msg::signed::SignedPayload]
+        payload: Binary,
+        pubkey: Binary,
+        signature: Binary,
+    },
+
+    /// Joins the key behind `address` to the sender's identity, see [crate::msg::identity]
+    /// the signature is an ADR-036 one by `address` over an [crate::msg::identity::IdentityLinkStatement]
+    LinkIdentity {
+        /// On any chain
+        address: String,
+        pubkey: Binary,
+        signature: Binary,
+    },
+
+    /// Sends all buffered messages in one packet
+    FlushBuffer { },
+
+    /// Drops every message that's still waiting for a server channel
+    /// owner only
+    PurgeOutbox { },
+
+    /// Creates a room with this chain as its home, every other chain hears about it through the server
+    /// the sender owns the room
+    CreateRoom {
+        name: String,
+        // default is [RoomAccess::Open]
+        access: Option<RoomAccess>,
+    },
+
+    /// Changes who may post in a room, the other chains hear about it through the server
+    /// room owner only, on the room's home chain
+    SetRoomAccess {
+        room: RoomId,
+        access: RoomAccess,
+    },
+
+    /// Publishes the sender's x25519 public key, for private rooms, see [crate::msg::encryption]
+    SetEncryptionKey {
+        key: Option<Binary>,
+    },
+
+    /// Creates an end-to-end encrypted room with this chain as its home
+    /// the members are the ones the room key is wrapped for, the sender included
+    CreatePrivateRoom {
+        name: String,
+        keys: Vec<WrappedRoomKey>,
+    },
+
+    /// Adds a member to a private room, with the current room key wrapped for them
+    /// room owner only
+    AddRoomMember {
+        room: RoomId,
+        key: WrappedRoomKey,
+    },
+
+    /// Removes a member from a private room, along with a new room key for everyone else
+    /// room owner only
+    RemoveRoomMember {
+        room: RoomId,
+        member: String,
+        keys: Vec<WrappedRoomKey>,
+    },
+
+    /// Leaves a private room, nobody can post until the owner rotates the key
+    LeaveRoom {
+        room: RoomId,
+    },
+
+    /// A new room key for every member of a private room
+    /// room owner only
+    RotateRoomKey {
+        room: RoomId,
+        keys: Vec<WrappedRoomKey>,
+    },
+
+    /// Starts a poll on every chain, the server assigns the id and keeps the tally
+    CreatePoll {
+        question: String,
+        options: Vec<String>,
+        closes_at: Timestamp,
+    },
+
+    /// One vote per user per poll, the option is the index into the poll's options
+    Vote {
+        poll_id: PollId,
+        option: u32,
+    },
+
+    /// Tips the author of a message with the funds sent along
+    /// a bank send on this chain, otherwise an ICS-20 transfer to the author's chain
+    /// everyone hears about it with an action message
+    Tip {
+        index: ChatMessageIndex,
+    },
+
+    /// Sets or unsets the ICS-20 channel that tips to another chain go through
+    /// owner only
+    SetTransferChannel {
+        network_id: NetworkId,
+        channel_id: Option<String>,
+    },
+
+    /// Sets or unsets the Nois proxy used for `/roll`
+    /// owner only
+    SetNoisProxy {
+        proxy: Option<String>,
+    },
+
+    /// Randomness delivered by the Nois proxy, settles a pending `/roll`
+    /// proxy only
+    NoisReceive {
+        callback: NoisCallback,
+    },
+
+    /// Hides a sender's messages from the caller's view, see the `viewer` of [QueryMsg::ChatMessages]
+    /// the address is on the given network, so it's not checked here
+    Block {
+        network_id: NetworkId,
+        address: String,
+    },
+
+    Unblock {
+        network_id: NetworkId,
+        address: String,
+    },
+
+    /// Pins a message to the top of its room on every chain, see [crate::msg::pin]
+    /// room owner only, on the room's home chain, and only once the server gave the message its id
+    Pin {
+        index: ChatMessageIndex,
+    },
+
+    /// room owner only, on the room's home chain
+    Unpin {
+        room: Option<RoomId>,
+        /// The server's id, as in [QueryMsg::Pins]
+        id: ChatMessageId,
+    },
+
+    /// Remembers the last message the sender read in a room, see [QueryMsg::UnreadCounts]
+    /// it can go back, to mark messages unread again
+    SetReadCursor {
+        // default is the main chat
+        room: Option<RoomId>,
+        /// The local index of a message in that room
+        index: ChatMessageIndex,
+    },
+
+    /// Marks the sender's mentions read, see [QueryMsg::Mentions]
+    MarkRead {
+        /// Everything up to and including this message
+        // default is every mention
+        up_to: Option<ChatMessageIndex>,
+    },
+
+    /// Subscribes a contract to new chat messages, local or from other chains
+    /// it gets a [crate::msg::hook::ChatHookMsg] for every message that matches the filter
+    /// setting it again replaces the filter
+    /// owner only
+    SetHook {
+        contract: String,
+        // default is [HookFilter::default], i.e. every message
+        filter: Option<HookFilter>,
+    },
+
+    /// owner only
+    RemoveHook {
+        contract: String,
     }
 }
 
@@ -24,9 +233,129 @@ pub enum QueryMsg {
     ChatMessages {
         after_index: Option<ChatMessageIndex>,
         // default is [Order::Ascending]
-        order: Option<Order>
+        order: Option<Order>,
+        // default is everything
+        limit: Option<u32>,
+        /// Leaves out messages from senders the viewer blocked
+        viewer: Option<String>,
+    },
+
+    /// Messages that were accepted but haven't been sent to the server yet
+    /// * returns [OutboxResp]
+    #[returns(OutboxResp)]
+    Outbox { },
+
+    /// What's known about a user on this chain
+    /// * returns [ProfileResp]
+    #[returns(ProfileResp)]
+    Profile {
+        user: String,
+    },
+
+    /// Tips sent from this chain for a message, by its local index
+    /// * returns [TipsResp]
+    #[returns(TipsResp)]
+    MessageTips {
+        index: ChatMessageIndex,
+    },
+
+    /// Tips sent from this chain to a user, the address is on the given network
+    /// * returns [TipsResp]
+    #[returns(TipsResp)]
+    UserTips {
+        network_id: NetworkId,
+        address: String,
+    },
+
+    /// Polls this client heard about from the server
+    /// the votes are only final once a poll is closed, the live tally is on the server
+    /// * returns [PollsResp]
+    #[returns(PollsResp)]
+    Polls { },
+
+    /// Rooms created here or heard about from the server
+    /// * returns [RoomsResp]
+    #[returns(RoomsResp)]
+    Rooms { },
+
+    /// Whether the user could post in the room from this chain right now
+    /// * returns [RoomAccessResp]
+    #[returns(RoomAccessResp)]
+    RoomAccess {
+        room: RoomId,
+        user: String,
+    },
+
+    /// The keys of a private room wrapped for the member, oldest epoch first
+    /// * returns [RoomKeysResp]
+    #[returns(RoomKeysResp)]
+    RoomKeys {
+        room: RoomId,
+        member: String,
+    },
+
+    /// Messages that mention the user, see [crate::msg::mention]
+    /// * returns [MentionsResp]
+    #[returns(MentionsResp)]
+    Mentions {
+        user: String,
+        after_index: Option<ChatMessageIndex>,
+        // default is [Order::Ascending]
+        order: Option<Order>,
+        // default is everything
+        limit: Option<u32>,
+        // default is false
+        unread_only: Option<bool>,
+    },
+
+    /// How many of the user's mentions are unread
+    /// * returns [UnreadMentionsResp]
+    #[returns(UnreadMentionsResp)]
+    UnreadMentions {
+        user: String,
+    },
+
+    /// The pinned messages of the main chat or a room
+    /// * returns [PinsResp]
+    #[returns(PinsResp)]
+    Pins {
+        // default is the main chat
+        room: Option<RoomId>,
+    },
+
+    /// How many messages the user hasn't read in the main chat and each room they can read
+    /// * returns [UnreadCountsResp]
+    #[returns(UnreadCountsResp)]
+    UnreadCounts {
+        user: String,
+    },
+
+    /// The viewer's block list
+    /// * returns [BlockedResp]
+    #[returns(BlockedResp)]
+    Blocked {
+        viewer: String,
+    },
+
+    /// The identity an address belongs to, and every key in it
+    /// * returns [IdentityResp]
+    #[returns(IdentityResp)]
+    Identity {
+        address: String,
     },
 
+    /// The nonce the user's next signed message must have
+    /// * returns [NonceResp]
+    #[returns(NonceResp)]
+    Nonce {
+        user: String,
+    },
+
+    /// Contracts subscribed to new chat messages
+    /// * returns [HooksResp]
+    #[returns(HooksResp)]
+    Hooks { },
+
     /// Get general information about the contract 
     #[returns(InfoResp)]
     Info { }
@@ -35,7 +364,22 @@ pub enum QueryMsg {
 #[cw_serde]
 pub struct InfoResp {
     pub server_channel: Option<IbcChannel>,
+    /// An ordered server channel that was closed by a timeout, messages are queued until a new one connects
+    pub dead_server_channel: Option<IbcChannel>,
+    pub network_id: NetworkId,
+    pub channel_order: ChannelOrder,
+    pub owner: Addr,
+    /// Set with `/topic`
+    pub topic: Option<String>,
+    pub nois_proxy: Option<Addr>,
+    /// For tips to other chains
+    pub transfer_channels: Vec<TransferChannel>,
+}
+
+#[cw_serde]
+pub struct TransferChannel {
     pub network_id: NetworkId,
+    pub channel_id: String,
 }
 
 /// Placeholder migration message
@@ -46,4 +390,157 @@ pub struct MigrateMsg {}
 #[cw_serde]
 pub struct ChatMessagesResp {
     pub messages: Vec<ChatMessageWithIndex>,
+    /// The identity of each author, to group their messages from different chains
+    pub identities: Vec<UserIdentity>,
+}
+
+#[cw_serde]
+pub struct UserIdentity {
+    pub user: Addr,
+    pub identity: IdentityId,
+}
+
+/// Response for [QueryMsg::Identity]
+#[cw_serde]
+pub struct IdentityResp {
+    pub identity: IdentityId,
+    /// The other keys that joined it
+    pub members: Vec<IdentityId>,
+}
+
+/// Response for [QueryMsg::Outbox]
+#[cw_serde]
+pub struct OutboxResp {
+    /// Oldest first, these are also in the local chat history already
+    pub messages: Vec<ChatMessageWithIndex>,
+    /// Buffered messages that haven't been flushed yet
+    pub buffered: Vec<ChatMessageWithIndex>,
+}
+
+/// Response for [QueryMsg::Profile]
+#[cw_serde]
+pub struct ProfileResp {
+    /// Set with `/nick`
+    pub nickname: Option<String>,
+    /// For private rooms, see [ExecuteMsg::SetEncryptionKey]
+    pub encryption_key: Option<Binary>,
+}
+
+/// Response for [QueryMsg::RoomKeys]
+#[cw_serde]
+pub struct RoomKeysResp {
+    /// The room's current epoch
+    pub epoch: u32,
+    pub keys: Vec<EpochRoomKey>,
+}
+
+#[cw_serde]
+pub struct EpochRoomKey {
+    pub epoch: u32,
+    pub key: WrappedRoomKey,
+}
+
+/// Response for [QueryMsg::MessageTips] and [QueryMsg::UserTips]
+#[cw_serde]
+pub struct TipsResp {
+    /// One entry per denom
+    pub total: Vec<Coin>,
+}
+
+/// Response for [QueryMsg::Polls]
+#[cw_serde]
+pub struct PollsResp {
+    pub polls: Vec<PollTally>,
+}
+
+/// Response for [QueryMsg::Rooms]
+#[cw_serde]
+pub struct RoomsResp {
+    pub rooms: Vec<Room>,
+}
+
+/// Response for [QueryMsg::RoomAccess]
+#[cw_serde]
+pub struct RoomAccessResp {
+    pub qualifies: bool,
+    /// Why not, e.g. the balance is below the room's minimum
+    pub reason: Option<String>,
+}
+
+/// Response for [QueryMsg::Nonce]
+#[cw_serde]
+pub struct NonceResp {
+    pub nonce: u64,
+}
+
+/// Response for [QueryMsg::Mentions]
+#[cw_serde]
+pub struct MentionsResp {
+    /// Expired messages are left out
+    pub mentions: Vec<Mention>,
+}
+
+/// Response for [QueryMsg::UnreadMentions]
+#[cw_serde]
+pub struct UnreadMentionsResp {
+    pub count: u32,
+}
+
+/// Response for [QueryMsg::Pins]
+#[cw_serde]
+pub struct PinsResp {
+    pub pins: Vec<PinnedMessage>,
+}
+
+/// Counts stop here
+pub const MAX_UNREAD_COUNT: u32 = 100;
+
+/// Response for [QueryMsg::UnreadCounts]
+#[cw_serde]
+pub struct UnreadCountsResp {
+    pub rooms: Vec<RoomUnread>,
+}
+
+#[cw_serde]
+pub struct RoomUnread {
+    /// `None` is the main chat
+    pub room: Option<RoomId>,
+    /// Not set yet means nothing was read
+    pub cursor: Option<ReadCursor>,
+    /// Messages after the cursor by anyone else, at most [MAX_UNREAD_COUNT]
+    pub unread: u32,
+}
+
+/// Where a user stopped reading
+#[cw_serde]
+pub struct ReadCursor {
+    /// Of the message on this chain
+    pub index: ChatMessageIndex,
+    /// The server's id of the same message, the same on every chain
+    /// not known for a message of ours the server hasn't acknowledged yet
+    pub id: Option<ChatMessageId>,
+}
+
+/// Response for [QueryMsg::Blocked]
+#[cw_serde]
+pub struct BlockedResp {
+    pub blocked: Vec<BlockedUser>,
+}
+
+#[cw_serde]
+pub struct BlockedUser {
+    pub network_id: NetworkId,
+    pub address: String,
+}
+
+/// Response for [QueryMsg::Hooks]
+#[cw_serde]
+pub struct HooksResp {
+    pub hooks: Vec<Hook>,
+}
+
+#[cw_serde]
+pub struct Hook {
+    pub contract: Addr,
+    pub filter: HookFilter,
 }
@@ -1,16 +1,153 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::{Addr, IbcChannel, Uint128};
 
+use crate::msg::{chat_message::ChatMessageId, content::MessageContent, identity::IdentityId, memo::TransferChatPost, misc::ChannelOrder, network::NetworkId, poll::{PollId, PollTally}, room::RoomId};
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    // default is [ChannelOrder::Unordered]
+    pub channel_order: Option<ChannelOrder>,
+    /// Tell the clients when a chain joins or leaves, see [ExecuteMsg::SetNetworkNotices]
+    // default is off
+    #[serde(default)]
+    pub network_notices: Option<bool>,
+    /// The chain the server is on, for its announcements
+    // default is [NetworkId::Neutron]
+    #[serde(default)]
+    pub network_id: Option<NetworkId>,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Closes every poll whose time is up and sends the results to the clients
+    /// this also happens on the next packet from any client, anyone can call it
+    ClosePolls { },
+
+    /// A chat message that came with an ICS-20 transfer, see [crate::msg::memo]
+    /// only the ibc-hooks intermediary for the post's channel and sender can call it
+    PostFromTransfer {
+        post: TransferChatPost,
+    },
+
+    /// Sets or unsets the network that transfers arriving on a channel come from
+    /// posts from channels without one are refused
+    /// owner only
+    SetMemoChannel {
+        channel_id: String,
+        network_id: Option<NetworkId>,
+    },
+
+    /// Sets or unsets the network of the client on the other end of an IBC connection
+    /// a client's [crate::msg::ibc::IbcExecuteMsg::ClientConnected] is only taken from a connection set up for its network
+    /// owner only
+    SetClientConnection {
+        connection_id: String,
+        network_id: Option<NetworkId>,
+    },
+
+    /// Turns the notices for chains joining and leaving on or off
+    /// owner only
+    SetNetworkNotices {
+        enabled: bool,
+    },
+
+    /// A message from the admin to every chain, pinned to the top of the main chat
+    /// see [crate::msg::chat_message::ChatMessageKind::Announcement]
+    /// owner only
+    Announce {
+        message: String,
+        /// Text or markdown
+        // default is plain text
+        #[serde(default)]
+        content: Option<MessageContent>,
+    },
+
+    /// Pins a message on every chain, in the main chat or any room, see [crate::msg::pin]
+    /// owner only
+    Pin {
+        room: Option<RoomId>,
+        id: ChatMessageId,
+    },
+
+    /// owner only
+    Unpin {
+        room: Option<RoomId>,
+        id: ChatMessageId,
+    },
+}
+
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
     /// Get general information about the contract 
     #[returns(InfoResp)]
-    Info { }
+    Info { },
+
+    /// The live tally of a poll
+    /// * returns [PollResp]
+    #[returns(PollResp)]
+    Poll {
+        poll_id: PollId,
+    },
+
+    /// Every poll, oldest first
+    /// * returns [PollsResp]
+    #[returns(PollsResp)]
+    Polls { },
+
+    /// The identity a key belongs to, and every key in it
+    /// * returns [IdentityResp]
+    #[returns(IdentityResp)]
+    Identity {
+        identity: IdentityId,
+    },
 }
 
 
 #[cw_serde]
 pub struct InfoResp {
-    pub client_channels: Vec<IbcChannel>
-}
\ No newline at end of file
+    pub client_channels: Vec<IbcChannel>,
+    /// Ordered client channels that were closed by a timeout, messages are queued until the client reconnects
+    pub dead_client_channels: Vec<IbcChannel>,
+    pub channel_order: ChannelOrder,
+    pub owner: Addr,
+    /// Transfer channels chat messages can be posted over
+    pub memo_channels: Vec<MemoChannel>,
+    /// Connections clients are expected on, and their networks
+    pub client_connections: Vec<ClientConnection>,
+    /// Whether the clients are told when a chain joins or leaves
+    pub network_notices: bool,
+    pub network_id: NetworkId,
+}
+
+#[cw_serde]
+pub struct MemoChannel {
+    pub channel_id: String,
+    pub network_id: NetworkId,
+}
+
+#[cw_serde]
+pub struct ClientConnection {
+    pub connection_id: String,
+    pub network_id: NetworkId,
+}
+
+/// Response for [QueryMsg::Poll]
+#[cw_serde]
+pub struct PollResp {
+    pub tally: PollTally,
+}
+
+/// Response for [QueryMsg::Polls]
+#[cw_serde]
+pub struct PollsResp {
+    pub polls: Vec<PollTally>,
+}
+
+/// Response for [QueryMsg::Identity]
+#[cw_serde]
+pub struct IdentityResp {
+    pub identity: IdentityId,
+    /// The other keys that joined it
+    pub members: Vec<IdentityId>,
+}
@@ -0,0 +1,17 @@
+//! The parts of the cw20 query interface we use
+//! kept here instead of depending on the `cw20` crate, the JSON is the same
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::Uint128;
+
+#[cw_serde]
+pub enum Cw20QueryMsg {
+    /// returns [BalanceResponse]
+    Balance {
+        address: String,
+    },
+}
+
+#[cw_serde]
+pub struct BalanceResponse {
+    pub balance: Uint128,
+}
@@ -0,0 +1,19 @@
+//! The parts of the cw721 query interface we use
+//! kept here instead of depending on the `cw721` crate, the JSON is the same
+use cosmwasm_schema::cw_serde;
+
+#[cw_serde]
+pub enum Cw721QueryMsg {
+    /// Token ids owned by the given address
+    /// returns [TokensResponse]
+    Tokens {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct TokensResponse {
+    pub tokens: Vec<String>,
+}
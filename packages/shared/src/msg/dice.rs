@@ -0,0 +1,115 @@
+use anyhow::{anyhow, bail, Result};
+use cosmwasm_schema::cw_serde;
+use sha2::{Digest, Sha256};
+
+/// e.g. `2d6` is two six-sided dice
+#[cw_serde]
+#[derive(Eq, Copy)]
+pub struct Dice {
+    pub count: u8,
+    pub sides: u8,
+}
+
+impl Dice {
+    pub const MAX_COUNT: u8 = 10;
+    pub const MIN_SIDES: u8 = 2;
+    pub const MAX_SIDES: u8 = 100;
+
+    /// One value per die, from 1 to the number of sides
+    /// the same randomness always gives the same roll, which is what makes it verifiable
+    pub fn roll(&self, randomness: &[u8]) -> Result<Vec<u8>> {
+        if randomness.len() < 32 {
+            bail!("{} bytes of randomness is not enough, a Nois round has 32", randomness.len());
+        }
+
+        // one byte per die, but bytes past the last whole multiple of `sides` would favor the low values, so they're skipped
+        let limit = 256 - 256 % self.sides as u16;
+
+        Ok(random_bytes(randomness)
+            .filter(|byte| (*byte as u16) < limit)
+            .map(|byte| byte % self.sides + 1)
+            .take(self.count as usize)
+            .collect())
+    }
+}
+
+// the randomness itself, and if that runs out, sha256(randomness || n) for n = 0, 1, ...
+fn random_bytes(randomness: &[u8]) -> impl Iterator<Item = u8> + '_ {
+    let more = (0u32..).flat_map(move |n| Sha256::new()
+        .chain_update(randomness)
+        .chain_update(n.to_be_bytes())
+        .finalize()
+        .to_vec());
+
+    randomness.iter().copied().chain(more)
+}
+
+impl Default for Dice {
+    fn default() -> Self {
+        Self { count: 1, sides: 6 }
+    }
+}
+
+impl std::fmt::Display for Dice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}d{}", self.count, self.sides)
+    }
+}
+
+impl std::str::FromStr for Dice {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (count, sides) = s.split_once('d').ok_or_else(|| anyhow!("dice look like 2d6, not {}", s))?;
+
+        let dice = Dice {
+            // `d20` is one die
+            count: if count.is_empty() { 1 } else { count.parse().map_err(|_| anyhow!("bad dice count: {}", count))? },
+            sides: sides.parse().map_err(|_| anyhow!("bad dice sides: {}", sides))?,
+        };
+
+        if dice.count == 0 || dice.count > Self::MAX_COUNT {
+            bail!("roll between 1 and {} dice", Self::MAX_COUNT);
+        }
+
+        if dice.sides < Self::MIN_SIDES || dice.sides > Self::MAX_SIDES {
+            bail!("dice have between {} and {} sides", Self::MIN_SIDES, Self::MAX_SIDES);
+        }
+
+        Ok(dice)
+    }
+}
+
+pub mod event {
+    use cosmwasm_std::{Addr, Event};
+
+    use crate::msg::nois::NoisCallback;
+
+    use super::Dice;
+
+    /// Event emitted when a roll is settled, with everything needed to check it
+    #[derive(Debug)]
+    pub struct DiceRollEvent<'a> {
+        pub user: &'a Addr,
+        pub dice: Dice,
+        pub values: &'a [u8],
+        pub callback: &'a NoisCallback,
+    }
+
+    impl DiceRollEvent<'_> {
+        pub const KEY: &'static str = "dice-roll";
+    }
+
+    impl From<DiceRollEvent<'_>> for Event {
+        fn from(src: DiceRollEvent) -> Self {
+            Event::new(DiceRollEvent::KEY).add_attributes(vec![
+                ("user", src.user.to_string()),
+                ("dice", src.dice.to_string()),
+                ("values", src.values.iter().map(|value| value.to_string()).collect::<Vec<_>>().join(",")),
+                ("job-id", src.callback.job_id.clone()),
+                ("published", src.callback.published.nanos().to_string()),
+                ("randomness", src.callback.randomness.to_hex()),
+            ])
+        }
+    }
+}
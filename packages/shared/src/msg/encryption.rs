@@ -0,0 +1,52 @@
+//! End-to-end encrypted private rooms
+//!
+//! Each member publishes an x25519 public key in their profile. The room's creator makes a random
+//! room key and wraps it for every member, and members encrypt messages with it.
+//! The contracts only see the ciphertext, the wrapped keys and who the members are.
+//! When a member leaves, a new key is made for the rest, that's the next epoch
+use anyhow::{bail, Result};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{from_json, to_json_string, Addr, Binary};
+
+/// x25519 public keys and room keys alike
+pub const ENCRYPTION_KEY_LEN: usize = 32;
+
+/// What's in the message text of a private room
+#[cw_serde]
+pub struct EncryptedMessage {
+    /// The room key it's encrypted with
+    pub epoch: u32,
+    pub nonce: Binary,
+    pub ciphertext: Binary,
+}
+
+impl EncryptedMessage {
+    pub fn to_message_text(&self) -> Result<String> {
+        Ok(to_json_string(self)?)
+    }
+
+    pub fn from_message_text(text: &str) -> Result<Self> {
+        match from_json(text.as_bytes()) {
+            Ok(msg) => Ok(msg),
+            Err(_) => bail!("messages in a private room must be encrypted"),
+        }
+    }
+}
+
+/// A room key, encrypted for one member
+/// the wrapping key comes from an x25519 exchange between `ephemeral_pubkey` and the member's key
+#[cw_serde]
+pub struct WrappedRoomKey {
+    pub member: Addr,
+    pub ephemeral_pubkey: Binary,
+    pub nonce: Binary,
+    pub ciphertext: Binary,
+}
+
+pub fn validate_encryption_key(key: &[u8]) -> Result<()> {
+    if key.len() != ENCRYPTION_KEY_LEN {
+        bail!("an encryption key has {} bytes", ENCRYPTION_KEY_LEN);
+    }
+
+    Ok(())
+}
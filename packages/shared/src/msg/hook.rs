@@ -0,0 +1,57 @@
+use cosmwasm_schema::cw_serde;
+
+use super::{chat_message::{ChatMessage, ChatMessageWithIndex}, network::NetworkId, room::RoomId};
+
+/// What a hook contract receives when a new chat message is stored on the client
+/// subscribers need to accept this as one of their own execute variants, i.e. `{"chat_hook": {...}}`
+#[cw_serde]
+pub enum ChatHookMsg {
+    ChatHook {
+        message: ChatMessageWithIndex,
+    },
+}
+
+/// Which messages a hook gets, every set field has to match
+/// the default (nothing set) gets everything
+#[cw_serde]
+#[derive(Default)]
+pub struct HookFilter {
+    pub sender: Option<String>,
+    pub network_id: Option<NetworkId>,
+    /// the message has to start with this, e.g. `!bot`
+    pub prefix: Option<String>,
+    pub room: Option<RoomId>,
+}
+
+impl HookFilter {
+    pub fn matches(&self, message: &ChatMessage) -> bool {
+        self.sender.as_ref().map_or(true, |sender| message.user.as_str() == sender)
+            && self.network_id.map_or(true, |network_id| message.network_id == network_id)
+            && self.prefix.as_ref().map_or(true, |prefix| message.message.starts_with(prefix.as_str()))
+            && self.room.as_ref().map_or(true, |room| message.room.as_ref() == Some(room))
+    }
+}
+
+pub mod event {
+    use cosmwasm_std::{Addr, Event};
+
+    /// Event emitted when a hook contract failed, the message itself is still stored
+    #[derive(Debug)]
+    pub struct ChatHookFailedEvent {
+        pub contract: Addr,
+        pub error: String,
+    }
+
+    impl ChatHookFailedEvent {
+        pub const KEY: &'static str = "chat-hook-failed";
+    }
+
+    impl From<ChatHookFailedEvent> for Event {
+        fn from(src: ChatHookFailedEvent) -> Self {
+            Event::new(ChatHookFailedEvent::KEY).add_attributes(vec![
+                ("contract", src.contract.to_string()),
+                ("error", src.error),
+            ])
+        }
+    }
+}
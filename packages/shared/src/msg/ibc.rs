@@ -1,7 +1,17 @@
+use anyhow::Result;
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, IbcChannel, Uint128};
+use cosmwasm_std::{from_json, to_json_binary, Addr, Binary, IbcChannel, Timestamp, Uint128};
 
-use super::chat_message::{ChatMessage, ChatMessageWithIndex};
+use crate::ibc::{IbcAck, IbcAckError, IbcAckErrorCode, IbcProtocolVersion, MAX_BATCH_SIZE};
+
+mod proto;
+
+use super::chat_message::{ChatMessage, ChatMessageId, ChatMessageIndex, ChatMessageWithIndex};
+use super::poll::{Poll, PollId, PollTally, Voter};
+use super::room::Room;
+use super::identity::{IdentityLink, IdentityLinkSignature};
+use super::network::NetworkId;
+use super::pin::Pin;
 
 #[cw_serde]
 pub enum IbcExecuteMsg {
@@ -11,6 +21,168 @@ pub enum IbcExecuteMsg {
         message: ChatMessageWithIndex
     },
     SendMessageToClient {
-        message: ChatMessage
+        message: ChatMessage,
+        /// The server's id for it, the same on every chain
+        /// missing from older servers
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<ChatMessageId>,
+    },
+    /// A new poll, the server assigns the id and tells every client
+    CreatePoll {
+        question: String,
+        options: Vec<String>,
+        closes_at: Timestamp,
+        creator: Voter,
+    },
+    /// One vote per voter per poll, counted by the server
+    Vote {
+        poll_id: PollId,
+        option: u32,
+        voter: Voter,
+    },
+    /// Sent by the server to every client, including the one that created it
+    PollCreated {
+        poll: Poll,
+    },
+    /// Sent by the server to every client, with the final tally
+    PollClosed {
+        tally: PollTally,
+    },
+    /// A room created on a client, the server passes it on to every other client
+    /// the home client already has it
+    RoomCreated {
+        room: Room,
+    },
+    /// The home client changed who may post in a room, passed on like [IbcExecuteMsg::RoomCreated]
+    RoomUpdated {
+        room: Room,
+    },
+    /// A key joining an identity, checked by the client it was submitted to
+    /// the server checks the signature again, has the final say and sends [IbcExecuteMsg::IdentityLinked] to every client
+    LinkIdentity {
+        signature: IdentityLinkSignature,
+    },
+    /// Sent by the server to every client, including the one the link came from
+    IdentityLinked {
+        link: IdentityLink,
+    },
+    /// The first thing a client sends on a new channel, so the server knows which chain it is
+    /// and can tell the others it joined
+    ClientConnected {
+        network_id: NetworkId,
+    },
+    /// A message pinned or unpinned, see [crate::msg::pin]
+    /// from a room's home client, checked there, and passed on by the server to every client
+    UpdatePin {
+        pin: Pin,
+        pinned: bool,
+    },
+    /// Several messages in one packet, acked with [IbcAckSuccess::Batch]
+    /// batches can't be nested
+    Batch {
+        msgs: Vec<IbcExecuteMsg>
+    },
+}
+
+impl IbcExecuteMsg {
+    /// Combines the messages into one packet's worth
+    /// a single message is left as-is, so a batch always has at least 2 items
+    pub fn batch(msgs: Vec<IbcExecuteMsg>) -> Option<Self> {
+        let mut msgs = msgs.into_iter().flat_map(Self::into_items).collect::<Vec<_>>();
+
+        match msgs.len() {
+            0 => None,
+            1 => msgs.pop(),
+            _ => Some(IbcExecuteMsg::Batch { msgs }),
+        }
     }
+
+    /// Packs the messages into as few packets as possible, keeping their order
+    /// each packet has at most [MAX_BATCH_SIZE] messages
+    pub fn batches(msgs: Vec<IbcExecuteMsg>) -> Vec<Self> {
+        let msgs = msgs.into_iter().flat_map(Self::into_items).collect::<Vec<_>>();
+
+        msgs.chunks(MAX_BATCH_SIZE)
+            .filter_map(|chunk| Self::batch(chunk.to_vec()))
+            .collect()
+    }
+
+    /// The individual messages, i.e. a batch is unwrapped
+    pub fn into_items(self) -> Vec<Self> {
+        match self {
+            IbcExecuteMsg::Batch { msgs } => msgs,
+            msg => vec![msg],
+        }
+    }
+
+    /// Encode the message as packet data, for a channel speaking the given protocol version
+    pub fn encode(&self, version: IbcProtocolVersion) -> Result<Binary> {
+        match version {
+            IbcProtocolVersion::V1 => Ok(to_json_binary(self)?),
+            IbcProtocolVersion::V2 => Ok(proto::encode(self)?.into()),
+        }
+    }
+
+    /// Decode packet data received on a channel speaking the given protocol version
+    pub fn decode(version: IbcProtocolVersion, data: &Binary) -> Result<Self> {
+        let res = match version {
+            IbcProtocolVersion::V1 => from_json(data).map_err(anyhow::Error::from),
+            IbcProtocolVersion::V2 => proto::decode(data),
+        };
+
+        res.map_err(|err| IbcAckError::new(IbcAckErrorCode::DecodeFailed, err.to_string()).into())
+    }
+}
+
+/// The typed success payload of an [IbcAck](crate::ibc::IbcAck), one per [IbcExecuteMsg] kind
+#[cw_serde]
+pub enum IbcAckSuccess {
+    /// Reply to [IbcExecuteMsg::SendMessageToServer]
+    MessageBroadcast {
+        /// The id assigned by the server
+        id: ChatMessageId,
+        /// How many other clients the message was sent on to
+        recipients: u32,
+    },
+    /// Reply to [IbcExecuteMsg::SendMessageToClient]
+    MessageStored {
+        /// The local index on the receiving client
+        index: ChatMessageIndex,
+    },
+    /// Reply to [IbcExecuteMsg::CreatePoll]
+    PollCreated {
+        id: PollId,
+    },
+    /// Reply to [IbcExecuteMsg::Vote]
+    VoteCounted { },
+    /// Reply to [IbcExecuteMsg::PollCreated] and [IbcExecuteMsg::PollClosed]
+    PollStored { },
+    /// Reply to [IbcExecuteMsg::RoomCreated] and [IbcExecuteMsg::RoomUpdated] on the server
+    RoomBroadcast {
+        recipients: u32,
+    },
+    /// Reply to [IbcExecuteMsg::RoomCreated] and [IbcExecuteMsg::RoomUpdated] on a client
+    RoomStored { },
+    /// Reply to [IbcExecuteMsg::LinkIdentity]
+    IdentityLinked { },
+    /// Reply to [IbcExecuteMsg::IdentityLinked]
+    IdentityStored { },
+    /// Reply to [IbcExecuteMsg::ClientConnected]
+    ClientRegistered {
+        /// How many other clients were told
+        recipients: u32,
+    },
+    /// Reply to [IbcExecuteMsg::UpdatePin] on the server
+    PinBroadcast {
+        recipients: u32,
+    },
+    /// Reply to [IbcExecuteMsg::UpdatePin] on a client
+    PinStored { },
+    /// Reply to [IbcExecuteMsg::Batch], one result per item in the same order
+    /// items succeed or fail independently
+    Batch {
+        results: Vec<IbcAck>,
+    },
+    /// The counterparty acknowledged without any typed data
+    Unspecified,
 }
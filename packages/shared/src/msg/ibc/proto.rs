@@ -0,0 +1,461 @@
+//! The compact wire format for [IbcProtocolVersion::V2](crate::ibc::IbcProtocolVersion::V2)
+//!
+//! These mirror [IbcExecuteMsg] as protobuf messages, the tags must never be reused.
+//! A message without a compact form yet can still travel as JSON inside the envelope
+use anyhow::{anyhow, Result};
+use cosmwasm_std::{from_json, to_json_vec, Addr, Timestamp};
+use cw_utils::Expiration;
+use prost::Message;
+
+use crate::msg::{chat_message, content, network, room};
+
+use super::IbcExecuteMsg;
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ExecuteMsg {
+    #[prost(oneof = "execute_msg::Msg", tags = "1, 2, 3, 4, 15")]
+    pub msg: Option<execute_msg::Msg>,
+}
+
+pub mod execute_msg {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Msg {
+        #[prost(message, tag = "1")]
+        SendMessageToServer(super::ChatMessageWithIndex),
+        #[prost(message, tag = "2")]
+        SendMessageToClient(super::ChatMessage),
+        #[prost(message, tag = "3")]
+        Batch(super::Batch),
+        /// [SendMessageToClient](Msg::SendMessageToClient) with the server's id
+        #[prost(message, tag = "4")]
+        SendIdentifiedMessageToClient(super::ChatMessageWithId),
+        /// JSON-encoded [IbcExecuteMsg](super::IbcExecuteMsg)
+        #[prost(bytes, tag = "15")]
+        Json(Vec<u8>),
+    }
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ChatMessage {
+    #[prost(string, tag = "1")]
+    pub user: String,
+    #[prost(enumeration = "NetworkId", tag = "2")]
+    pub network_id: i32,
+    #[prost(string, tag = "3")]
+    pub message: String,
+    #[prost(enumeration = "ChatMessageKind", tag = "4")]
+    pub kind: i32,
+    #[prost(string, optional, tag = "5")]
+    pub room: Option<String>,
+    #[prost(message, optional, tag = "6")]
+    pub attestation: Option<RoomAttestation>,
+    /// nanoseconds, there's no height expiration on the wire
+    #[prost(uint64, optional, tag = "7")]
+    pub expires_at: Option<u64>,
+    /// absent for plain text
+    #[prost(message, optional, tag = "8")]
+    pub content: Option<MessageContent>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct MessageContent {
+    #[prost(oneof = "message_content::Kind", tags = "1, 2, 3, 4, 5")]
+    pub kind: Option<message_content::Kind>,
+}
+
+pub mod message_content {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Kind {
+        #[prost(message, tag = "1")]
+        Markdown(super::Empty),
+        #[prost(message, tag = "2")]
+        Link(super::LinkContent),
+        #[prost(message, tag = "3")]
+        Attachment(super::AttachmentContent),
+        #[prost(message, tag = "4")]
+        Token(super::TokenContent),
+        #[prost(message, tag = "5")]
+        Notice(super::Empty),
+    }
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Empty {}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct LinkContent {
+    #[prost(string, tag = "1")]
+    pub url: String,
+    #[prost(message, optional, tag = "2")]
+    pub preview: Option<LinkPreview>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct LinkPreview {
+    #[prost(string, tag = "1")]
+    pub title: String,
+    #[prost(string, optional, tag = "2")]
+    pub description: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub image: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct AttachmentContent {
+    #[prost(string, tag = "1")]
+    pub cid: String,
+    #[prost(string, tag = "2")]
+    pub mime: String,
+    #[prost(uint64, tag = "3")]
+    pub size: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct TokenContent {
+    #[prost(string, tag = "1")]
+    pub denom: String,
+    /// decimal string, like [Uint128](cosmwasm_std::Uint128) in JSON
+    #[prost(string, optional, tag = "2")]
+    pub amount: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct RoomAttestation {
+    #[prost(oneof = "room_attestation::Kind", tags = "1, 2")]
+    pub kind: Option<room_attestation::Kind>,
+}
+
+pub mod room_attestation {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Kind {
+        #[prost(message, tag = "1")]
+        Cw721(super::Cw721Attestation),
+        #[prost(message, tag = "2")]
+        Balance(super::BalanceAttestation),
+    }
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Cw721Attestation {
+    #[prost(string, tag = "1")]
+    pub collection: String,
+    #[prost(string, tag = "2")]
+    pub token_id: String,
+    #[prost(uint64, tag = "3")]
+    pub height: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct BalanceAttestation {
+    #[prost(oneof = "balance_attestation::Denom", tags = "1, 2")]
+    pub denom: Option<balance_attestation::Denom>,
+    /// decimal string, like [Uint128](cosmwasm_std::Uint128) in JSON
+    #[prost(string, tag = "3")]
+    pub amount: String,
+    #[prost(uint64, tag = "4")]
+    pub height: u64,
+}
+
+pub mod balance_attestation {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Denom {
+        #[prost(string, tag = "1")]
+        Native(String),
+        #[prost(string, tag = "2")]
+        Cw20(String),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum ChatMessageKind {
+    Text = 0,
+    Action = 1,
+    System = 2,
+    Network = 3,
+    Announcement = 4,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ChatMessageWithIndex {
+    #[prost(message, optional, tag = "1")]
+    pub msg: Option<ChatMessage>,
+    #[prost(uint64, tag = "2")]
+    pub index: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ChatMessageWithId {
+    #[prost(message, optional, tag = "1")]
+    pub msg: Option<ChatMessage>,
+    #[prost(uint64, tag = "2")]
+    pub id: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Batch {
+    #[prost(message, repeated, tag = "1")]
+    pub msgs: Vec<ExecuteMsg>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum NetworkId {
+    Unspecified = 0,
+    Neutron = 1,
+    Stargaze = 2,
+    Kujira = 3,
+    Nois = 4,
+}
+
+pub fn encode(msg: &IbcExecuteMsg) -> Result<Vec<u8>> {
+    Ok(ExecuteMsg::try_from(msg)?.encode_to_vec())
+}
+
+pub fn decode(data: &[u8]) -> Result<IbcExecuteMsg> {
+    ExecuteMsg::decode(data)?.try_into()
+}
+
+impl TryFrom<&IbcExecuteMsg> for ExecuteMsg {
+    type Error = anyhow::Error;
+
+    fn try_from(msg: &IbcExecuteMsg) -> Result<Self> {
+        let msg = match msg {
+            IbcExecuteMsg::SendMessageToServer { message } => execute_msg::Msg::SendMessageToServer(ChatMessageWithIndex {
+                msg: Some(message.msg.clone().try_into()?),
+                index: message.index.u64(),
+            }),
+            IbcExecuteMsg::SendMessageToClient { message, id: None } => execute_msg::Msg::SendMessageToClient(message.clone().try_into()?),
+            IbcExecuteMsg::SendMessageToClient { message, id: Some(id) } => execute_msg::Msg::SendIdentifiedMessageToClient(ChatMessageWithId {
+                msg: Some(message.clone().try_into()?),
+                id: id.u64(),
+            }),
+            IbcExecuteMsg::Batch { msgs } => execute_msg::Msg::Batch(Batch {
+                msgs: msgs.iter().map(ExecuteMsg::try_from).collect::<Result<_>>()?,
+            }),
+            #[allow(unreachable_patterns)]
+            msg => execute_msg::Msg::Json(to_json_vec(msg)?),
+        };
+
+        Ok(ExecuteMsg { msg: Some(msg) })
+    }
+}
+
+impl TryFrom<ExecuteMsg> for IbcExecuteMsg {
+    type Error = anyhow::Error;
+
+    fn try_from(msg: ExecuteMsg) -> Result<Self> {
+        Ok(match msg.msg.ok_or_else(|| anyhow!("empty message"))? {
+            execute_msg::Msg::SendMessageToServer(message) => IbcExecuteMsg::SendMessageToServer {
+                message: chat_message::ChatMessageWithIndex {
+                    msg: message.msg.ok_or_else(|| anyhow!("missing chat message"))?.try_into()?,
+                    index: message.index.into(),
+                },
+            },
+            execute_msg::Msg::SendMessageToClient(message) => IbcExecuteMsg::SendMessageToClient {
+                message: message.try_into()?,
+                id: None,
+            },
+            execute_msg::Msg::SendIdentifiedMessageToClient(message) => IbcExecuteMsg::SendMessageToClient {
+                message: message.msg.ok_or_else(|| anyhow!("missing chat message"))?.try_into()?,
+                id: Some(message.id.into()),
+            },
+            execute_msg::Msg::Batch(batch) => IbcExecuteMsg::Batch {
+                msgs: batch.msgs.into_iter().map(IbcExecuteMsg::try_from).collect::<Result<_>>()?,
+            },
+            execute_msg::Msg::Json(data) => from_json(data)?,
+        })
+    }
+}
+
+impl TryFrom<chat_message::ChatMessage> for ChatMessage {
+    type Error = anyhow::Error;
+
+    fn try_from(message: chat_message::ChatMessage) -> Result<Self> {
+        Ok(ChatMessage {
+            user: message.user.into_string(),
+            network_id: NetworkId::from(message.network_id).into(),
+            message: message.message,
+            kind: ChatMessageKind::from(message.kind).into(),
+            content: match message.content {
+                content::MessageContent::Text => None,
+                content => Some(content.into()),
+            },
+            room: message.room,
+            attestation: message.attestation.map(RoomAttestation::from),
+            expires_at: match message.expires {
+                Expiration::AtTime(time) => Some(time.nanos()),
+                Expiration::Never {} => None,
+                Expiration::AtHeight(_) => return Err(anyhow!("height expirations can't be sent")),
+            },
+        })
+    }
+}
+
+impl TryFrom<ChatMessage> for chat_message::ChatMessage {
+    type Error = anyhow::Error;
+
+    fn try_from(message: ChatMessage) -> Result<Self> {
+        Ok(chat_message::ChatMessage {
+            user: Addr::unchecked(message.user),
+            network_id: NetworkId::try_from(message.network_id)
+                .map_err(|_| anyhow!("unknown network id: {}", message.network_id))?
+                .try_into()?,
+            message: message.message,
+            kind: ChatMessageKind::try_from(message.kind)
+                .map_err(|_| anyhow!("unknown message kind: {}", message.kind))?
+                .into(),
+            content: message.content.map(content::MessageContent::try_from).transpose()?.unwrap_or_default(),
+            room: message.room,
+            attestation: message.attestation.map(room::RoomAttestation::try_from).transpose()?,
+            expires: message.expires_at.map_or(Expiration::Never {}, |nanos| Expiration::AtTime(Timestamp::from_nanos(nanos))),
+        })
+    }
+}
+
+impl From<chat_message::ChatMessageKind> for ChatMessageKind {
+    fn from(kind: chat_message::ChatMessageKind) -> Self {
+        match kind {
+            chat_message::ChatMessageKind::Text => ChatMessageKind::Text,
+            chat_message::ChatMessageKind::Action => ChatMessageKind::Action,
+            chat_message::ChatMessageKind::System => ChatMessageKind::System,
+            chat_message::ChatMessageKind::Network => ChatMessageKind::Network,
+            chat_message::ChatMessageKind::Announcement => ChatMessageKind::Announcement,
+        }
+    }
+}
+
+impl From<ChatMessageKind> for chat_message::ChatMessageKind {
+    fn from(kind: ChatMessageKind) -> Self {
+        match kind {
+            ChatMessageKind::Text => chat_message::ChatMessageKind::Text,
+            ChatMessageKind::Action => chat_message::ChatMessageKind::Action,
+            ChatMessageKind::System => chat_message::ChatMessageKind::System,
+            ChatMessageKind::Network => chat_message::ChatMessageKind::Network,
+            ChatMessageKind::Announcement => chat_message::ChatMessageKind::Announcement,
+        }
+    }
+}
+
+impl From<room::RoomAttestation> for RoomAttestation {
+    fn from(attestation: room::RoomAttestation) -> Self {
+        let kind = match attestation {
+            room::RoomAttestation::Cw721 { collection, token_id, height } => room_attestation::Kind::Cw721(Cw721Attestation {
+                collection: collection.into_string(),
+                token_id,
+                height,
+            }),
+            room::RoomAttestation::Balance { denom, amount, height } => room_attestation::Kind::Balance(BalanceAttestation {
+                denom: Some(match denom {
+                    room::RoomDenom::Native { denom } => balance_attestation::Denom::Native(denom),
+                    room::RoomDenom::Cw20 { contract } => balance_attestation::Denom::Cw20(contract.into_string()),
+                }),
+                amount: amount.to_string(),
+                height,
+            }),
+        };
+
+        RoomAttestation { kind: Some(kind) }
+    }
+}
+
+impl TryFrom<RoomAttestation> for room::RoomAttestation {
+    type Error = anyhow::Error;
+
+    fn try_from(attestation: RoomAttestation) -> Result<Self> {
+        Ok(match attestation.kind.ok_or_else(|| anyhow!("empty attestation"))? {
+            room_attestation::Kind::Cw721(attestation) => room::RoomAttestation::Cw721 {
+                collection: Addr::unchecked(attestation.collection),
+                token_id: attestation.token_id,
+                height: attestation.height,
+            },
+            room_attestation::Kind::Balance(attestation) => room::RoomAttestation::Balance {
+                denom: match attestation.denom.ok_or_else(|| anyhow!("missing attestation denom"))? {
+                    balance_attestation::Denom::Native(denom) => room::RoomDenom::Native { denom },
+                    balance_attestation::Denom::Cw20(contract) => room::RoomDenom::Cw20 { contract: Addr::unchecked(contract) },
+                },
+                amount: attestation.amount.parse()?,
+                height: attestation.height,
+            },
+        })
+    }
+}
+
+impl From<content::MessageContent> for MessageContent {
+    fn from(content: content::MessageContent) -> Self {
+        let kind = match content {
+            content::MessageContent::Text => None,
+            content::MessageContent::Markdown => Some(message_content::Kind::Markdown(Empty {})),
+            content::MessageContent::Link { url, preview } => Some(message_content::Kind::Link(LinkContent {
+                url,
+                preview: preview.map(|preview| LinkPreview {
+                    title: preview.title,
+                    description: preview.description,
+                    image: preview.image,
+                }),
+            })),
+            content::MessageContent::Attachment { cid, mime, size } => Some(message_content::Kind::Attachment(AttachmentContent { cid, mime, size })),
+            content::MessageContent::Token { denom, amount } => Some(message_content::Kind::Token(TokenContent {
+                denom,
+                amount: amount.map(|amount| amount.to_string()),
+            })),
+            content::MessageContent::Notice => Some(message_content::Kind::Notice(Empty {})),
+        };
+
+        MessageContent { kind }
+    }
+}
+
+impl TryFrom<MessageContent> for content::MessageContent {
+    type Error = anyhow::Error;
+
+    fn try_from(content: MessageContent) -> Result<Self> {
+        Ok(match content.kind {
+            None => content::MessageContent::Text,
+            Some(message_content::Kind::Markdown(_)) => content::MessageContent::Markdown,
+            Some(message_content::Kind::Link(link)) => content::MessageContent::Link {
+                url: link.url,
+                preview: link.preview.map(|preview| content::LinkPreview {
+                    title: preview.title,
+                    description: preview.description,
+                    image: preview.image,
+                }),
+            },
+            Some(message_content::Kind::Attachment(attachment)) => content::MessageContent::Attachment {
+                cid: attachment.cid,
+                mime: attachment.mime,
+                size: attachment.size,
+            },
+            Some(message_content::Kind::Token(token)) => content::MessageContent::Token {
+                denom: token.denom,
+                amount: token.amount.map(|amount| amount.parse()).transpose()?,
+            },
+            Some(message_content::Kind::Notice(_)) => content::MessageContent::Notice,
+        })
+    }
+}
+
+impl From<network::NetworkId> for NetworkId {
+    fn from(network_id: network::NetworkId) -> Self {
+        match network_id {
+            network::NetworkId::Neutron => NetworkId::Neutron,
+            network::NetworkId::Stargaze => NetworkId::Stargaze,
+            network::NetworkId::Kujira => NetworkId::Kujira,
+            network::NetworkId::Nois => NetworkId::Nois,
+        }
+    }
+}
+
+impl TryFrom<NetworkId> for network::NetworkId {
+    type Error = anyhow::Error;
+
+    fn try_from(network_id: NetworkId) -> Result<Self> {
+        match network_id {
+            NetworkId::Neutron => Ok(network::NetworkId::Neutron),
+            NetworkId::Stargaze => Ok(network::NetworkId::Stargaze),
+            NetworkId::Kujira => Ok(network::NetworkId::Kujira),
+            NetworkId::Nois => Ok(network::NetworkId::Nois),
+            NetworkId::Unspecified => Err(anyhow!("unspecified network id")),
+        }
+    }
+}
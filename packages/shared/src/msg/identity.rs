@@ -0,0 +1,95 @@
+//! One person behind addresses on several chains
+//!
+//! An identity is named by the canonical address of a key, i.e. the data of its bech32 address
+//! so the same key already has the same identity on every chain, whatever the prefix.
+//! A different key joins an identity by signing an [IdentityLinkStatement] for it,
+//! as an ADR-036 message (see [crate::msg::signed]), which the server checks again, stores and tells every client about
+use anyhow::{anyhow, bail, Result};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{to_json_vec, Api, Binary, HexBinary};
+
+use super::signed::{adr036_sign_hash, pubkey_to_canonical};
+
+/// Hex of a canonical address
+pub type IdentityId = String;
+
+/// The identity of a key by its address on any chain
+pub fn identity_of_address(address: &str) -> Result<IdentityId> {
+    let (_, data) = bech32::decode(address).map_err(|err| anyhow!("{} is not a bech32 address: {}", address, err))?;
+
+    Ok(HexBinary::from(data).to_hex())
+}
+
+/// The identity of a key by its secp256k1 public key
+pub fn identity_of_pubkey(pubkey: &[u8]) -> IdentityId {
+    HexBinary::from(pubkey_to_canonical(pubkey)).to_hex()
+}
+
+/// What the joining key signs
+#[cw_serde]
+pub struct IdentityLinkStatement {
+    /// The identity of the address that submits the link
+    pub link_identity: IdentityId,
+}
+
+/// A signed [IdentityLinkStatement], as submitted to a client and passed on to the server
+#[cw_serde]
+pub struct IdentityLinkSignature {
+    /// The joining key's address, the statement is signed as it
+    pub address: String,
+    pub pubkey: Binary,
+    pub signature: Binary,
+    /// What the statement says, the identity is resolved by whoever stores the link
+    pub link_identity: IdentityId,
+}
+
+impl IdentityLinkSignature {
+    /// Checks that the key behind the address signed the statement
+    /// returns the identity of the joining key
+    pub fn verify(&self, api: &dyn Api) -> Result<IdentityId> {
+        let member = identity_of_address(&self.address)?;
+        if identity_of_pubkey(&self.pubkey) != member {
+            bail!("the public key is not the one of {}", self.address);
+        }
+
+        let statement = to_json_vec(&IdentityLinkStatement { link_identity: self.link_identity.clone() })?;
+        if !api.secp256k1_verify(&adr036_sign_hash(&self.address, &statement), &self.signature, &self.pubkey)? {
+            bail!("invalid signature for {}", self.address);
+        }
+
+        Ok(member)
+    }
+}
+
+#[cw_serde]
+pub struct IdentityLink {
+    /// The key that joins, it has no members of its own
+    pub member: IdentityId,
+    /// The identity it joins, never a member of another
+    pub identity: IdentityId,
+}
+
+pub mod event {
+    use cosmwasm_std::Event;
+
+    use super::IdentityLink;
+
+    /// Emitted by the server and every client when a link is stored
+    #[derive(Debug)]
+    pub struct IdentityLinkedEvent<'a> {
+        pub link: &'a IdentityLink,
+    }
+
+    impl IdentityLinkedEvent<'_> {
+        pub const KEY: &'static str = "identity-linked";
+    }
+
+    impl From<IdentityLinkedEvent<'_>> for Event {
+        fn from(src: IdentityLinkedEvent) -> Self {
+            Event::new(IdentityLinkedEvent::KEY).add_attributes(vec![
+                ("member", src.link.member.clone()),
+                ("identity", src.link.identity.clone()),
+            ])
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! Posting a chat message with an ICS-20 transfer, for chains without a client contract
+//!
+//! The memo follows [ibc-hooks](https://github.com/osmosis-labs/osmosis/tree/main/x/ibc-hooks):
+//! `{"wasm": {"contract": "<server>", "msg": {"post_from_transfer": {"post": {...}}}}}`.
+//! The hooks module runs the server with the transferred funds, as an intermediary sender
+//! derived from the channel and the original sender, see [hook_intermediary_sender]
+//!
+//! Going the other way, a contract that sends a transfer with an `ibc_callback` memo
+//! hears back from ibc-hooks when it's acknowledged or times out, see [IbcLifecycleComplete]
+use cosmwasm_schema::cw_serde;
+use sha2::{Digest, Sha256};
+
+use super::contract::server::ExecuteMsg as ServerExecuteMsg;
+
+/// The prefix ibc-hooks derives its intermediary senders with
+pub const HOOK_SENDER_PREFIX: &str = "ibc-wasm-hook-intermediary";
+
+/// The whole memo of the transfer
+#[cw_serde]
+pub struct TransferMemo {
+    pub wasm: WasmHookMemo,
+}
+
+#[cw_serde]
+pub struct WasmHookMemo {
+    pub contract: String,
+    pub msg: ServerExecuteMsg,
+}
+
+/// The chat message inside [ServerExecuteMsg::PostFromTransfer]
+#[cw_serde]
+pub struct TransferChatPost {
+    /// The transfer channel on the server's chain the transfer arrives on
+    pub channel: String,
+    /// The author, i.e. the sender of the transfer on the source chain
+    pub sender: String,
+    pub message: String,
+}
+
+impl TransferMemo {
+    pub fn new(server: impl Into<String>, post: TransferChatPost) -> Self {
+        Self {
+            wasm: WasmHookMemo {
+                contract: server.into(),
+                msg: ServerExecuteMsg::PostFromTransfer { post },
+            },
+        }
+    }
+}
+
+/// The memo of an outgoing transfer, asking ibc-hooks to tell `ibc_callback` how it went
+#[cw_serde]
+pub struct IbcCallbackMemo {
+    pub ibc_callback: String,
+}
+
+/// What ibc-hooks calls the sending contract's `sudo` entry point with, once a transfer is done
+/// by then a failed transfer was already refunded to the contract
+#[cw_serde]
+pub enum IbcLifecycleComplete {
+    IbcAck {
+        /// Our end of the transfer channel
+        channel: String,
+        sequence: u64,
+        ack: String,
+        success: bool,
+    },
+    IbcTimeout {
+        channel: String,
+        sequence: u64,
+    },
+}
+
+/// The canonical address ibc-hooks executes the contract as, for a transfer from `sender` arriving on `channel`
+/// i.e. the SDK's `address.Hash(HOOK_SENDER_PREFIX, channel + "/" + sender)`
+/// nobody else can send from it, so it proves who the author is
+pub fn hook_intermediary_sender(channel: &str, sender: &str) -> Vec<u8> {
+    let typ = Sha256::digest(HOOK_SENDER_PREFIX.as_bytes());
+    Sha256::new()
+        .chain_update(typ)
+        .chain_update(format!("{}/{}", channel, sender))
+        .finalize()
+        .to_vec()
+}
@@ -0,0 +1,93 @@
+//! `@address` and `@nickname` mentions in chat messages
+//!
+//! Nicknames only mean something on the chain they were set on, so mentions aren't resolved by the sender.
+//! Every client parses the messages it stores, its own and the ones from the server,
+//! and keeps an inbox entry for each of its users that's mentioned
+use cosmwasm_schema::cw_serde;
+
+use super::chat_message::{ChatMessage, ChatMessageKind, ChatMessageWithIndex};
+
+/// Anything past that in one message is ignored
+pub const MAX_MENTIONS: usize = 10;
+
+/// The names after each `@`, in order and without repeats
+/// an `@` in the middle of a word, like an email address, isn't a mention
+pub fn parse_mentions(text: &str) -> Vec<String> {
+    let mut mentions: Vec<String> = Vec::new();
+
+    for word in text.split(char::is_whitespace) {
+        let Some(name) = word.trim_start_matches(['(', '[', '"', '\'']).strip_prefix('@') else {
+            continue;
+        };
+        let name = name.trim_end_matches(|c: char| c.is_ascii_punctuation() && c != '_' && c != '-');
+
+        if !name.is_empty() && !name.contains('@') && !mentions.iter().any(|x| x == name) {
+            mentions.push(name.to_string());
+            if mentions.len() == MAX_MENTIONS {
+                break;
+            }
+        }
+    }
+
+    mentions
+}
+
+/// Only what users write can mention someone, not the contracts' notices
+pub fn can_mention(message: &ChatMessage) -> bool {
+    matches!(message.kind, ChatMessageKind::Text | ChatMessageKind::Action) && !message.content.is_notice()
+}
+
+/// An entry in a user's inbox
+#[cw_serde]
+pub struct Mention {
+    pub message: ChatMessageWithIndex,
+    pub read: bool,
+}
+
+pub mod event {
+    use cosmwasm_std::{Addr, Event};
+    use anyhow::{Error, anyhow};
+    use crate::{event::CosmwasmEventExt, msg::{chat_message::ChatMessageIndex, network::NetworkId}};
+
+    /// Emitted by the mentioned user's home client, once per message
+    #[derive(Debug)]
+    pub struct MentionEvent {
+        /// The one who was mentioned
+        pub user: Addr,
+        pub index: ChatMessageIndex,
+        pub author: Addr,
+        pub author_network_id: NetworkId,
+    }
+
+    impl MentionEvent {
+        pub const KEY: &'static str = "mention";
+    }
+
+    impl From<MentionEvent> for Event {
+        fn from(src: MentionEvent) -> Self {
+            Event::new(MentionEvent::KEY).add_attributes(vec![
+                ("user", src.user.to_string()),
+                ("index", src.index.to_string()),
+                ("author", src.author.to_string()),
+                ("author-network-id", src.author_network_id.to_string()),
+            ])
+        }
+    }
+
+    impl TryFrom<Event> for MentionEvent {
+        type Error = Error;
+
+        fn try_from(evt: Event) -> anyhow::Result<Self> {
+            if evt.ty.as_str() != format!("wasm-{}", MentionEvent::KEY) {
+                return Err(anyhow!("unexpected event type: {}, should be {}", evt.ty, MentionEvent::KEY));
+            }
+
+            Ok(MentionEvent {
+                user: Addr::unchecked(evt.string_attr("user")?),
+                index: evt.u64_attr("index")?.into(),
+                author: Addr::unchecked(evt.string_attr("author")?),
+                author_network_id: evt.string_attr("author-network-id")?.parse()?,
+            })
+        }
+    }
+}
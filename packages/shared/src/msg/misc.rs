@@ -26,4 +26,54 @@ impl From<cosmwasm_std::Order> for Order {
             cosmwasm_std::Order::Descending => Self::Descending,
         }
     }
+}
+
+/// Like [cosmwasm_std::IbcOrder] but serialized as a simple string
+#[cw_serde]
+#[derive(Eq, Copy, Default)]
+pub enum ChannelOrder {
+    /// Packets may arrive in any order, a timeout only loses that one packet
+    #[default]
+    Unordered,
+    /// Packets arrive strictly in order, a timeout closes the channel
+    Ordered,
+}
+
+impl From<ChannelOrder> for cosmwasm_std::IbcOrder {
+    fn from(order: ChannelOrder) -> Self {
+        match order {
+            ChannelOrder::Unordered => Self::Unordered,
+            ChannelOrder::Ordered => Self::Ordered,
+        }
+    }
+}
+
+impl From<cosmwasm_std::IbcOrder> for ChannelOrder {
+    fn from(order: cosmwasm_std::IbcOrder) -> Self {
+        match order {
+            cosmwasm_std::IbcOrder::Unordered => Self::Unordered,
+            cosmwasm_std::IbcOrder::Ordered => Self::Ordered,
+        }
+    }
+}
+
+impl std::fmt::Display for ChannelOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChannelOrder::Unordered => write!(f, "unordered"),
+            ChannelOrder::Ordered => write!(f, "ordered"),
+        }
+    }
+}
+
+impl std::str::FromStr for ChannelOrder {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "unordered" => Ok(ChannelOrder::Unordered),
+            "ordered" => Ok(ChannelOrder::Ordered),
+            _ => Err(anyhow::anyhow!("Unknown channel order: {}", s)),
+        }
+    }
 }
\ No newline at end of file
@@ -2,4 +2,20 @@ pub mod contract;
 pub mod ibc;
 pub mod misc;
 pub mod network;
-pub mod chat_message;
\ No newline at end of file
+pub mod chat_message;
+pub mod hook;
+pub mod command;
+pub mod poll;
+pub mod nois;
+pub mod dice;
+pub mod room;
+pub mod cw721;
+pub mod cw20;
+pub mod tip;
+pub mod memo;
+pub mod signed;
+pub mod identity;
+pub mod encryption;
+pub mod content;
+pub mod mention;
+pub mod pin;
@@ -0,0 +1,23 @@
+//! The parts of the [Nois](https://nois.network) proxy interface we use
+//! kept here instead of depending on the `nois` crate, the JSON is the same
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{HexBinary, Timestamp};
+
+/// Sent to the Nois proxy on our own chain, with its fee as funds
+#[cw_serde]
+pub enum NoisProxyExecuteMsg {
+    GetNextRandomness {
+        job_id: String,
+    },
+}
+
+/// What the proxy sends back in [crate::msg::contract::client::ExecuteMsg::NoisReceive]
+/// once the beacon round after the request is published
+#[cw_serde]
+pub struct NoisCallback {
+    pub job_id: String,
+    /// When the beacon round was published, this identifies the round
+    pub published: Timestamp,
+    /// The round's randomness, anyone can check it against the Nois chain
+    pub randomness: HexBinary,
+}
@@ -0,0 +1,60 @@
+//! Messages pinned to the top of the main chat or a room, on every chain
+//!
+//! A pin names the message by the server's id, since the local index differs from chain to chain.
+//! Room owners pin in their rooms from the room's home chain, the server's admin pins anywhere,
+//! either way the server tells every client, including the one it came from
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::Addr;
+
+use super::{chat_message::{ChatMessageId, ChatMessageWithIndex}, room::RoomId};
+
+/// Per room, the main chat included
+pub const MAX_PINS: usize = 10;
+
+#[cw_serde]
+pub struct Pin {
+    /// `None` is the main chat
+    pub room: Option<RoomId>,
+    pub id: ChatMessageId,
+    /// The room's owner, or the server's admin
+    pub by: Addr,
+}
+
+#[cw_serde]
+pub struct PinnedMessage {
+    pub pin: Pin,
+    /// `None` if this chain doesn't have the message, e.g. it expired
+    pub message: Option<ChatMessageWithIndex>,
+}
+
+pub mod event {
+    use cosmwasm_std::Event;
+
+    use super::Pin;
+
+    /// Emitted by the server and every client when a message is pinned or unpinned
+    #[derive(Debug)]
+    pub struct PinUpdatedEvent<'a> {
+        pub pin: &'a Pin,
+        pub pinned: bool,
+    }
+
+    impl PinUpdatedEvent<'_> {
+        pub const KEY: &'static str = "pin-updated";
+    }
+
+    impl From<PinUpdatedEvent<'_>> for Event {
+        fn from(src: PinUpdatedEvent) -> Self {
+            let event = Event::new(PinUpdatedEvent::KEY).add_attributes(vec![
+                ("id", src.pin.id.to_string()),
+                ("by", src.pin.by.to_string()),
+                ("pinned", src.pinned.to_string()),
+            ]);
+
+            match &src.pin.room {
+                Some(room) => event.add_attribute("room", room.clone()),
+                None => event,
+            }
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use anyhow::{bail, Result};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Timestamp, Uint64};
+
+use super::network::NetworkId;
+
+/// Assigned by the server, which keeps the authoritative tally
+pub type PollId = Uint64;
+
+pub const MIN_POLL_OPTIONS: usize = 2;
+pub const MAX_POLL_OPTIONS: usize = 10;
+
+/// A user on some chain, each one gets one vote per poll
+#[cw_serde]
+#[derive(Eq)]
+pub struct Voter {
+    pub network_id: NetworkId,
+    pub user: Addr,
+}
+
+impl std::fmt::Display for Voter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.network_id, self.user)
+    }
+}
+
+#[cw_serde]
+pub struct Poll {
+    pub id: PollId,
+    pub question: String,
+    pub options: Vec<String>,
+    /// Votes arriving at the server at or after this time are rejected
+    pub closes_at: Timestamp,
+    pub creator: Voter,
+}
+
+/// Checks what the user asked for, before it's sent anywhere
+pub fn validate_poll(question: &str, options: &[String], closes_at: Timestamp, now: Timestamp) -> Result<()> {
+    if question.trim().is_empty() {
+        bail!("the question can't be empty");
+    }
+
+    if options.len() < MIN_POLL_OPTIONS || options.len() > MAX_POLL_OPTIONS {
+        bail!("a poll needs between {} and {} options", MIN_POLL_OPTIONS, MAX_POLL_OPTIONS);
+    }
+
+    if options.iter().any(|option| option.trim().is_empty()) {
+        bail!("options can't be empty");
+    }
+
+    if closes_at <= now {
+        bail!("the poll would already be closed");
+    }
+
+    Ok(())
+}
+
+#[cw_serde]
+pub struct PollTally {
+    pub poll: Poll,
+    /// One count per option, in the same order
+    pub votes: Vec<u64>,
+    /// Once closed, the tally is final
+    pub closed: bool,
+}
+
+impl PollTally {
+    pub fn new(poll: Poll) -> Self {
+        Self {
+            votes: vec![0; poll.options.len()],
+            poll,
+            closed: false,
+        }
+    }
+}
+
+pub mod event {
+    use cosmwasm_std::Event;
+
+    use super::{Poll, PollTally};
+
+    /// Event emitted when a poll is created, or a client hears about it
+    #[derive(Debug)]
+    pub struct PollCreatedEvent<'a> {
+        pub poll: &'a Poll,
+    }
+
+    impl PollCreatedEvent<'_> {
+        pub const KEY: &'static str = "poll-created";
+    }
+
+    impl From<PollCreatedEvent<'_>> for Event {
+        fn from(src: PollCreatedEvent) -> Self {
+            Event::new(PollCreatedEvent::KEY).add_attributes(vec![
+                ("poll-id", src.poll.id.to_string()),
+                ("question", src.poll.question.clone()),
+                ("creator", src.poll.creator.to_string()),
+                ("closes-at", src.poll.closes_at.to_string()),
+            ])
+        }
+    }
+
+    /// Event emitted when a poll is closed with its final tally
+    #[derive(Debug)]
+    pub struct PollClosedEvent<'a> {
+        pub tally: &'a PollTally,
+    }
+
+    impl PollClosedEvent<'_> {
+        pub const KEY: &'static str = "poll-closed";
+    }
+
+    impl From<PollClosedEvent<'_>> for Event {
+        fn from(src: PollClosedEvent) -> Self {
+            Event::new(PollClosedEvent::KEY).add_attributes(vec![
+                ("poll-id", src.tally.poll.id.to_string()),
+                ("votes", src.tally.votes.iter().map(|votes| votes.to_string()).collect::<Vec<_>>().join(",")),
+            ])
+        }
+    }
+}
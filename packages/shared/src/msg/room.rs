@@ -0,0 +1,197 @@
+use anyhow::{bail, Result};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Uint128};
+
+use super::{chat_message::ChatMessage, network::NetworkId};
+
+/// `<home network>/<name>`, e.g. `stargaze/punks`
+/// unique without any coordination, since each client only creates rooms for its own network
+pub type RoomId = String;
+
+pub const MAX_ROOM_NAME_LEN: usize = 32;
+
+/// Who may post in a room, everyone can read it like any other on-chain data
+#[cw_serde]
+pub enum RoomAccess {
+    Open,
+    /// The sender must own a token from this cw721 collection on the room's home chain
+    Cw721 {
+        collection: Addr,
+    },
+    /// The sender must hold at least `min` of the denom on the room's home chain
+    Balance {
+        denom: RoomDenom,
+        min: Uint128,
+    },
+    /// Only the members, with messages encrypted for them, see [crate::msg::encryption]
+    /// changed with its own messages, not like the other rules
+    Private {
+        members: Vec<Addr>,
+        /// Goes up with every new room key
+        epoch: u32,
+    },
+}
+
+#[cw_serde]
+pub enum RoomDenom {
+    /// A bank balance
+    Native {
+        denom: String,
+    },
+    Cw20 {
+        contract: Addr,
+    },
+}
+
+impl std::fmt::Display for RoomDenom {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RoomDenom::Native { denom } => write!(f, "{}", denom),
+            RoomDenom::Cw20 { contract } => write!(f, "cw20:{}", contract),
+        }
+    }
+}
+
+#[cw_serde]
+pub struct Room {
+    pub id: RoomId,
+    pub name: String,
+    /// The chain the room was created on, where its access rule is checked
+    pub home: NetworkId,
+    pub access: RoomAccess,
+    pub creator: Addr,
+}
+
+impl Room {
+    pub fn id_for(home: NetworkId, name: &str) -> RoomId {
+        format!("{}/{}", home, name)
+    }
+
+    /// The home chain, as named in the id
+    pub fn home_of(id: &str) -> Option<NetworkId> {
+        id.split_once('/')?.0.parse().ok()
+    }
+
+    /// Checks a message that was accepted on another chain
+    /// a gated room is only posted to from its home chain, with the attestation it gave there
+    pub fn honors(&self, message: &ChatMessage) -> Result<()> {
+        match &self.access {
+            RoomAccess::Open => Ok(()),
+            RoomAccess::Cw721 { collection } => {
+                self.check_home(message)?;
+
+                match &message.attestation {
+                    Some(RoomAttestation::Cw721 { collection: attested, .. }) if attested == collection => Ok(()),
+                    _ => bail!("message to {} is missing an attestation for {}", self.id, collection),
+                }
+            },
+            RoomAccess::Balance { denom, min } => {
+                self.check_home(message)?;
+
+                match &message.attestation {
+                    Some(RoomAttestation::Balance { denom: attested, amount, .. }) if attested == denom && amount >= min => Ok(()),
+                    _ => bail!("message to {} is missing an attestation for {} {}", self.id, min, denom),
+                }
+            },
+            // membership was checked at home, and there's nothing else we could check
+            RoomAccess::Private { .. } => self.check_home(message),
+        }
+    }
+
+    fn check_home(&self, message: &ChatMessage) -> Result<()> {
+        if message.network_id != self.home {
+            bail!("{} can only be posted to from {}", self.id, self.home);
+        }
+
+        Ok(())
+    }
+}
+
+/// A single word, so the id stays readable
+pub fn validate_room_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.chars().count() > MAX_ROOM_NAME_LEN {
+        bail!("a room name has between 1 and {} characters", MAX_ROOM_NAME_LEN);
+    }
+
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        bail!("a room name can only have letters, digits, - and _");
+    }
+
+    Ok(())
+}
+
+/// Given by the room's home client when it accepted the message
+/// anyone can check it against the home chain at that height
+#[cw_serde]
+pub enum RoomAttestation {
+    /// The user owned this token
+    Cw721 {
+        collection: Addr,
+        token_id: String,
+        height: u64,
+    },
+    /// The user held this much
+    Balance {
+        denom: RoomDenom,
+        amount: Uint128,
+        height: u64,
+    },
+}
+
+pub mod event {
+    use cosmwasm_std::Event;
+
+    use super::{Room, RoomAccess};
+
+    // the rule, if there is one
+    fn add_access_attributes(event: Event, access: &RoomAccess) -> Event {
+        match access {
+            RoomAccess::Open => event,
+            RoomAccess::Cw721 { collection } => event.add_attribute("collection", collection.to_string()),
+            RoomAccess::Balance { denom, min } => event.add_attribute("min-balance", format!("{} {}", min, denom)),
+            RoomAccess::Private { members, epoch } => event.add_attributes(vec![
+                ("members", members.len().to_string()),
+                ("epoch", epoch.to_string()),
+            ]),
+        }
+    }
+
+    /// Emitted by each client when it learns about a room, including the home client
+    #[derive(Debug)]
+    pub struct RoomCreatedEvent<'a> {
+        pub room: &'a Room,
+    }
+
+    impl RoomCreatedEvent<'_> {
+        pub const KEY: &'static str = "room-created";
+    }
+
+    impl From<RoomCreatedEvent<'_>> for Event {
+        fn from(src: RoomCreatedEvent) -> Self {
+            let event = Event::new(RoomCreatedEvent::KEY).add_attributes(vec![
+                ("room", src.room.id.clone()),
+                ("creator", src.room.creator.to_string()),
+            ]);
+
+            add_access_attributes(event, &src.room.access)
+        }
+    }
+
+    /// Emitted when a room's owner changes who may post, on every client
+    #[derive(Debug)]
+    pub struct RoomAccessSetEvent<'a> {
+        pub room: &'a Room,
+    }
+
+    impl RoomAccessSetEvent<'_> {
+        pub const KEY: &'static str = "room-access-set";
+    }
+
+    impl From<RoomAccessSetEvent<'_>> for Event {
+        fn from(src: RoomAccessSetEvent) -> Self {
+            let event = Event::new(RoomAccessSetEvent::KEY).add_attribute("room", src.room.id.clone());
+
+            add_access_attributes(event, &src.room.access)
+        }
+    }
+}
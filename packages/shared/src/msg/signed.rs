@@ -0,0 +1,55 @@
+//! Chat messages signed off-chain, so someone else can pay the gas to post them
+//!
+//! The user signs the JSON of a [SignedPayload] as an [ADR-036](https://github.com/cosmos/cosmos-sdk/blob/main/docs/architecture/adr-036-arbitrary-signature.md)
+//! `sign/MsgSignData`, the same thing Keplr's `signArbitrary` produces
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::Binary;
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+
+use super::{content::MessageContent, room::RoomId};
+
+/// What the user signs
+#[cw_serde]
+pub struct SignedPayload {
+    /// The client contract it's for, so it can't be replayed on another one
+    pub contract: String,
+    /// The signer's next nonce on that contract, see `QueryMsg::Nonce`
+    pub nonce: u64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room: Option<RoomId>,
+    #[serde(default, skip_serializing_if = "MessageContent::is_text")]
+    pub content: MessageContent,
+}
+
+/// Everything `SendSignedMessage` needs
+#[cw_serde]
+pub struct SignedMessage {
+    /// The exact bytes that were signed, the JSON of a [SignedPayload]
+    pub payload: Binary,
+    /// Compressed secp256k1 public key, 33 bytes
+    pub pubkey: Binary,
+    /// 64 bytes, `r || s`
+    pub signature: Binary,
+}
+
+/// The canonical address of a secp256k1 public key, the same on every Cosmos chain
+/// i.e. `ripemd160(sha256(pubkey))`
+pub fn pubkey_to_canonical(pubkey: &[u8]) -> Vec<u8> {
+    Ripemd160::digest(Sha256::digest(pubkey)).to_vec()
+}
+
+/// The amino JSON sign doc of an ADR-036 signature over `data`, keys sorted and without whitespace
+pub fn adr036_sign_doc(signer: &str, data: &[u8]) -> Vec<u8> {
+    format!(
+        r#"{{"account_number":"0","chain_id":"","fee":{{"amount":[],"gas":"0"}},"memo":"","msgs":[{{"type":"sign/MsgSignData","value":{{"data":"{}","signer":"{}"}}}}],"sequence":"0"}}"#,
+        Binary::from(data).to_base64(),
+        signer,
+    ).into_bytes()
+}
+
+/// The hash that is actually signed
+pub fn adr036_sign_hash(signer: &str, data: &[u8]) -> Vec<u8> {
+    Sha256::digest(adr036_sign_doc(signer, data)).to_vec()
+}
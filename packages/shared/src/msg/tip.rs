@@ -0,0 +1,91 @@
+use anyhow::Result;
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Coin, Coins};
+
+use super::{chat_message::ChatMessageIndex, network::NetworkId};
+
+/// A tip sent over ICS-20, until the transfer is acknowledged
+/// it only counts towards the totals once it arrived, and goes back to the tipper if it didn't
+#[cw_serde]
+pub struct PendingTip {
+    pub index: ChatMessageIndex,
+    pub tipper: Addr,
+    pub author: Addr,
+    pub network_id: NetworkId,
+    /// One transfer per denom
+    pub amount: Coin,
+}
+
+/// Adds the coins to a running total, keeping one entry per denom
+pub fn add_tip(total: Vec<Coin>, tip: &[Coin]) -> Result<Vec<Coin>> {
+    let mut total = Coins::try_from(total)?;
+    for coin in tip {
+        total.add(coin.clone())?;
+    }
+
+    Ok(total.into_vec())
+}
+
+/// e.g. `10ustars, 5ukuji`
+pub fn format_coins(coins: &[Coin]) -> String {
+    coins.iter().map(|coin| coin.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+pub mod event {
+    use cosmwasm_std::{Addr, Coin, Event};
+
+    use crate::msg::{chat_message::ChatMessageIndex, network::NetworkId};
+
+    use super::{format_coins, PendingTip};
+
+    /// Emitted by the tipper's client, the tip is on its way to the author
+    #[derive(Debug)]
+    pub struct TipEvent<'a> {
+        pub index: ChatMessageIndex,
+        pub tipper: &'a Addr,
+        pub author: &'a Addr,
+        pub network_id: NetworkId,
+        pub amount: &'a [Coin],
+    }
+
+    impl TipEvent<'_> {
+        pub const KEY: &'static str = "tip";
+    }
+
+    impl From<TipEvent<'_>> for Event {
+        fn from(src: TipEvent) -> Self {
+            Event::new(TipEvent::KEY).add_attributes(vec![
+                ("index", src.index.to_string()),
+                ("tipper", src.tipper.to_string()),
+                ("author", src.author.to_string()),
+                ("network-id", src.network_id.to_string()),
+                ("amount", format_coins(src.amount)),
+            ])
+        }
+    }
+
+    /// Emitted by the tipper's client when a tip's transfer is done, either way
+    #[derive(Debug)]
+    pub struct TipSettledEvent<'a> {
+        pub tip: &'a PendingTip,
+        /// Otherwise it was refunded to the tipper
+        pub delivered: bool,
+    }
+
+    impl TipSettledEvent<'_> {
+        pub const KEY: &'static str = "tip-settled";
+    }
+
+    impl From<TipSettledEvent<'_>> for Event {
+        fn from(src: TipSettledEvent) -> Self {
+            Event::new(TipSettledEvent::KEY).add_attributes(vec![
+                ("index", src.tip.index.to_string()),
+                ("tipper", src.tip.tipper.to_string()),
+                ("author", src.tip.author.to_string()),
+                ("network-id", src.tip.network_id.to_string()),
+                ("amount", src.tip.amount.to_string()),
+                ("delivered", src.delivered.to_string()),
+            ])
+        }
+    }
+}
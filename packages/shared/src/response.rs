@@ -18,6 +18,14 @@ pub struct ResponseBuilder {
     event_type_count: HashMap<String, u32>,
 }
 
+/// Where a [ResponseBuilder] was, to go back to with [ResponseBuilder::rollback]
+pub struct ResponseCheckpoint {
+    messages: usize,
+    attributes: usize,
+    events: usize,
+    event_type_count: HashMap<String, u32>,
+}
+
 enum EventType {
     MuteEvents,
     EmitEvents {
@@ -59,6 +67,24 @@ impl ResponseBuilder {
         self.resp
     }
 
+    /// Remember what's in the response so far
+    pub fn checkpoint(&self) -> ResponseCheckpoint {
+        ResponseCheckpoint {
+            messages: self.resp.messages.len(),
+            attributes: self.resp.attributes.len(),
+            events: self.resp.events.len(),
+            event_type_count: self.event_type_count.clone(),
+        }
+    }
+
+    /// Drop everything added since the checkpoint, e.g. when the handler that added it failed
+    pub fn rollback(&mut self, checkpoint: ResponseCheckpoint) {
+        self.resp.messages.truncate(checkpoint.messages);
+        self.resp.attributes.truncate(checkpoint.attributes);
+        self.resp.events.truncate(checkpoint.events);
+        self.event_type_count = checkpoint.event_type_count;
+    }
+
     /// Add a new [CosmosMsg] to the response.
     pub fn add_message(&mut self, msg: impl Into<CosmosMsg<Empty>>) {
         self.resp.messages.push(SubMsg::new(msg.into()));
@@ -203,6 +229,16 @@ impl ResponseBuilder {
         resp
     }
 
+    /// Turn the accumulated response into an IBC Receive response with a typed ack
+    pub fn into_ibc_recv_response_ack(self, ack: &IbcAck) -> Result<IbcReceiveResponse> {
+        let mut resp = IbcReceiveResponse::new(ack.to_std_ack()?);
+        resp.messages = self.resp.messages;
+        resp.attributes = self.resp.attributes;
+        resp.events = self.resp.events;
+
+        Ok(resp)
+    }
+
     /// Turn the accumulated response into an IBC Receive fail response
     pub fn into_ibc_recv_response_fail(self, error: anyhow::Error) -> IbcReceiveResponse {
         let mut resp = IbcReceiveResponse::new(StdAck::error(error.to_string()));
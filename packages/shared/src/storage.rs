@@ -0,0 +1,157 @@
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    iter::Peekable,
+    ops::Bound,
+};
+
+use anyhow::Result;
+use cosmwasm_std::{Order, Record, Storage};
+
+use crate::response::ResponseBuilder;
+
+/// Buffers writes on top of a store, so they can be dropped if what made them fails
+/// reads see the buffered writes, and nothing reaches the store until [StorageCache::into_writes] is applied
+pub struct StorageCache<'a> {
+    storage: &'a dyn Storage,
+    // `None` is a removal
+    local: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+}
+
+/// The writes buffered by a [StorageCache], in key order
+pub struct StorageWrites(BTreeMap<Vec<u8>, Option<Vec<u8>>>);
+
+impl<'a> StorageCache<'a> {
+    pub fn new(storage: &'a dyn Storage) -> Self {
+        Self {
+            storage,
+            local: BTreeMap::new(),
+        }
+    }
+
+    pub fn into_writes(self) -> StorageWrites {
+        StorageWrites(self.local)
+    }
+}
+
+impl StorageWrites {
+    pub fn apply(self, storage: &mut dyn Storage) {
+        for (key, value) in self.0 {
+            match value {
+                Some(value) => storage.set(&key, &value),
+                None => storage.remove(&key),
+            }
+        }
+    }
+}
+
+/// Runs `f` on a cache of the store, its writes are only kept if it succeeds
+/// the same goes for the events and messages it added to the response
+pub fn transactional<T>(
+    store: &mut dyn Storage,
+    response: &mut ResponseBuilder,
+    f: impl FnOnce(&mut dyn Storage, &mut ResponseBuilder) -> Result<T>,
+) -> Result<T> {
+    let checkpoint = response.checkpoint();
+    let mut cache = StorageCache::new(&*store);
+
+    match f(&mut cache, response) {
+        Ok(value) => {
+            cache.into_writes().apply(store);
+            Ok(value)
+        },
+        Err(err) => {
+            response.rollback(checkpoint);
+            Err(err)
+        }
+    }
+}
+
+impl Storage for StorageCache<'_> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        match self.local.get(key) {
+            Some(value) => value.clone(),
+            None => self.storage.get(key),
+        }
+    }
+
+    fn range<'b>(&'b self, start: Option<&[u8]>, end: Option<&[u8]>, order: Order) -> Box<dyn Iterator<Item = Record> + 'b> {
+        // same as the real stores, an empty range rather than BTreeMap's panic
+        if let (Some(start), Some(end)) = (start, end) {
+            if start > end {
+                return Box::new(std::iter::empty());
+            }
+        }
+
+        let bounds = (
+            start.map_or(Bound::Unbounded, |start| Bound::Included(start.to_vec())),
+            end.map_or(Bound::Unbounded, |end| Bound::Excluded(end.to_vec())),
+        );
+        let local: Box<dyn Iterator<Item = (&'b Vec<u8>, &'b Option<Vec<u8>>)> + 'b> = match order {
+            Order::Ascending => Box::new(self.local.range(bounds)),
+            Order::Descending => Box::new(self.local.range(bounds).rev()),
+        };
+
+        Box::new(MergedRange {
+            base: self.storage.range(start, end, order).peekable(),
+            local: local.peekable(),
+            order,
+        })
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.local.insert(key.to_vec(), Some(value.to_vec()));
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.local.insert(key.to_vec(), None);
+    }
+}
+
+// both sides are in the same order, the local side wins on the same key
+struct MergedRange<'a, B: Iterator<Item = Record>, L: Iterator<Item = (&'a Vec<u8>, &'a Option<Vec<u8>>)>> {
+    base: Peekable<B>,
+    local: Peekable<L>,
+    order: Order,
+}
+
+impl<'a, B, L> Iterator for MergedRange<'a, B, L>
+where
+    B: Iterator<Item = Record>,
+    L: Iterator<Item = (&'a Vec<u8>, &'a Option<Vec<u8>>)>,
+{
+    type Item = Record;
+
+    fn next(&mut self) -> Option<Record> {
+        loop {
+            let from_local = match (self.base.peek(), self.local.peek()) {
+                (None, None) => return None,
+                (Some(_), None) => false,
+                (None, Some(_)) => true,
+                (Some((base_key, _)), Some((local_key, _))) => {
+                    let ordering = match self.order {
+                        Order::Ascending => base_key.as_slice().cmp(local_key.as_slice()),
+                        Order::Descending => local_key.as_slice().cmp(base_key.as_slice()),
+                    };
+                    match ordering {
+                        Ordering::Less => false,
+                        Ordering::Equal => {
+                            self.base.next();
+                            true
+                        },
+                        Ordering::Greater => true,
+                    }
+                },
+            };
+
+            if !from_local {
+                return self.base.next();
+            }
+
+            // a removal hides the key, keep going
+            if let (key, Some(value)) = self.local.next()? {
+                return Some((key.clone(), value.clone()));
+            }
+        }
+    }
+}
@@ -155,6 +155,70 @@ export async function ffi_contract_exec_funds(wallet, contractAddress, msg, fund
     }
 } 
 
+function executeContractMsg(wallet, contractAddress, msg, funds) {
+    return {
+        typeUrl: "/cosmwasm.wasm.v1.MsgExecuteContract",
+        value: {
+            sender: wallet.address,
+            contract: contractAddress,
+            msg: new TextEncoder().encode(JSON.stringify(msg)),
+            funds: funds || [],
+        },
+    };
+}
+
+// the gas an execute would use, for a fee worked out on the rust side
+export async function ffi_contract_exec_simulate(wallet, contractAddress, msg, funds) {
+    return await wallet.client.simulate(wallet.address, [executeContractMsg(wallet, contractAddress, msg, funds)], "");
+}
+
+// like ffi_contract_exec_funds, with the given fee, e.g. one paid by a granter
+export async function ffi_contract_exec_fee(wallet, contractAddress, msg, funds, fee) {
+    const executeMsg = executeContractMsg(wallet, contractAddress, msg, funds);
+    return assertTxSuccess(await wallet.client.signAndBroadcast(wallet.address, [executeMsg], fee, ""));
+}
+
+// the allowance is already encoded, see wallet::feegrant
+export async function ffi_fee_grant(wallet, grantee, allowanceTypeUrl, allowanceValue) {
+    const grantMsg = {
+        typeUrl: "/cosmos.feegrant.v1beta1.MsgGrantAllowance",
+        value: {
+            granter: wallet.address,
+            grantee,
+            allowance: { typeUrl: allowanceTypeUrl, value: allowanceValue },
+        },
+    };
+
+    return assertTxSuccess(await wallet.client.signAndBroadcast(wallet.address, [grantMsg], "auto", ""));
+}
+
+export async function ffi_fee_revoke(wallet, grantee) {
+    const revokeMsg = {
+        typeUrl: "/cosmos.feegrant.v1beta1.MsgRevokeAllowance",
+        value: { granter: wallet.address, grantee },
+    };
+
+    return assertTxSuccess(await wallet.client.signAndBroadcast(wallet.address, [revokeMsg], "auto", ""));
+}
+
+// the query client of SigningCosmWasmClient has no feegrant extension, so this goes through REST
+export async function ffi_fee_grants(wallet) {
+    const resp = await fetch(`${wallet.rest_url}/cosmos/feegrant/v1beta1/issued/${wallet.address}`);
+    if (!resp.ok) {
+        throw new Error(`fee grants query failed: ${resp.status} ${await resp.text()}`);
+    }
+
+    const { allowances } = await resp.json();
+    return allowances || [];
+}
+
+function assertTxSuccess(result) {
+    if (result.code !== 0) {
+        throw new Error(`tx ${result.transactionHash} failed with code ${result.code}: ${result.rawLog}`);
+    }
+    return result;
+}
+
 export async function ffi_wallet_balance(wallet) {
     const coin = await wallet.client.getBalance(wallet.address, wallet.denom);
     return Number(coin.amount)
@@ -43,6 +43,9 @@ extern "C" {
     #[wasm_bindgen(method, getter)]
     pub fn denom(this: &WalletSigning) -> String;
 
+    #[wasm_bindgen(method, getter)]
+    pub fn gas_price(this: &WalletSigning) -> String;
+
     #[wasm_bindgen(method, getter)]
     pub fn cosmjs(this: &WalletSigning) -> CosmJs;
 }
@@ -121,6 +124,42 @@ extern "C" {
         funds: JsValue,
     ) -> Result<JsValue, JsValue>;
 
+    #[wasm_bindgen(catch)]
+    pub async fn ffi_contract_exec_simulate(
+        wallet: &JsValue,
+        addr: &str,
+        msg: JsValue,
+        funds: JsValue,
+    ) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(catch)]
+    pub async fn ffi_contract_exec_fee(
+        wallet: &JsValue,
+        addr: &str,
+        msg: JsValue,
+        funds: JsValue,
+        fee: JsValue,
+    ) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(catch)]
+    pub async fn ffi_fee_grant(
+        wallet: &JsValue,
+        grantee: &str,
+        allowance_type_url: &str,
+        allowance_value: &[u8],
+    ) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(catch)]
+    pub async fn ffi_fee_revoke(
+        wallet: &JsValue,
+        grantee: &str,
+    ) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(catch)]
+    pub async fn ffi_fee_grants(
+        wallet: &JsValue,
+    ) -> Result<JsValue, JsValue>;
+
     #[wasm_bindgen(catch)]
     pub async fn ffi_wallet_balance(
         wallet: &JsValue,
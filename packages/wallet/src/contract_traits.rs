@@ -1,13 +1,15 @@
 use std::marker::PhantomData;
 use anyhow::Result;
-use cosmwasm_std::{Coin, Order, Uint64};
+use cosmwasm_std::{Binary, Coin, Order, Timestamp, Uint64};
+use cw_utils::Expiration;
 use serde::{de::DeserializeOwned, Serialize};
 
-use crate::{config::{DeployContractConfig, DEPLOY_CONFIG}, prelude::WalletSigning, response_types::*};
+use crate::{config::{DeployContractConfig, DEPLOY_CONFIG}, encryption::{encrypt_message, RoomKey}, prelude::WalletSigning, response_types::*, signed::IdentityLinkProof};
 use shared::msg::contract::{
-    client::{ChatMessagesResp, ExecuteMsg as ClientExecuteMsg, InfoResp as ClientInfoResp, QueryMsg as ClientQueryMsg},
-    server::{InfoResp as ServerInfoResp, QueryMsg as ServerQueryMsg},
+    client::{BlockedResp, ChatMessagesResp, ExecuteMsg as ClientExecuteMsg, HooksResp, IdentityResp as ClientIdentityResp, InfoResp as ClientInfoResp, MentionsResp, NonceResp, OutboxResp, PinsResp, PollsResp as ClientPollsResp, ProfileResp, QueryMsg as ClientQueryMsg, RoomAccessResp, RoomKeysResp, RoomsResp, TipsResp, UnreadCountsResp, UnreadMentionsResp},
+    server::{ExecuteMsg as ServerExecuteMsg, IdentityResp as ServerIdentityResp, InfoResp as ServerInfoResp, PollResp, PollsResp as ServerPollsResp, QueryMsg as ServerQueryMsg},
 };
+use shared::msg::{chat_message::ChatMessageId, content::MessageContent, encryption::WrappedRoomKey, hook::HookFilter, memo::TransferChatPost, network::NetworkId, poll::PollId, room::RoomAccess, signed::SignedMessage};
 
 // These are just generic traits that can be implemented for any specific contract trait
 // More to the point, they are the *only* traits that need to be implemented for a contract, everything else is derived
@@ -33,19 +35,252 @@ pub trait ContractClient<ExecResponse>: ContractQuery<ClientQueryMsg> + Contract
     }
 
     async fn query_chat_messages(&mut self, after_index: Option<Uint64>, order: Option<Order>) -> Result<ChatMessagesResp> {
-        self.query(&ClientQueryMsg::ChatMessages { after_index, order: order.map(|order| order.into()) }).await
+        self.query(&ClientQueryMsg::ChatMessages { after_index, order: order.map(|order| order.into()), limit: None, viewer: None }).await
+    }
+
+    /// A page of messages as the viewer sees them, i.e. without the senders they blocked
+    async fn query_chat_messages_for(&mut self, viewer: impl Into<String>, after_index: Option<Uint64>, order: Option<Order>, limit: Option<u32>) -> Result<ChatMessagesResp> {
+        self.query(&ClientQueryMsg::ChatMessages { after_index, order: order.map(|order| order.into()), limit, viewer: Some(viewer.into()) }).await
+    }
+
+    async fn query_blocked(&mut self, viewer: impl Into<String>) -> Result<BlockedResp> {
+        self.query(&ClientQueryMsg::Blocked { viewer: viewer.into() }).await
+    }
+
+    /// Oldest first
+    async fn query_mentions(&mut self, user: impl Into<String>, unread_only: bool) -> Result<MentionsResp> {
+        self.query(&ClientQueryMsg::Mentions { user: user.into(), after_index: None, order: None, limit: None, unread_only: Some(unread_only) }).await
+    }
+
+    async fn query_unread_mentions(&mut self, user: impl Into<String>) -> Result<UnreadMentionsResp> {
+        self.query(&ClientQueryMsg::UnreadMentions { user: user.into() }).await
+    }
+
+    async fn query_unread_counts(&mut self, user: impl Into<String>) -> Result<UnreadCountsResp> {
+        self.query(&ClientQueryMsg::UnreadCounts { user: user.into() }).await
+    }
+
+    async fn query_pins(&mut self, room: Option<String>) -> Result<PinsResp> {
+        self.query(&ClientQueryMsg::Pins { room }).await
+    }
+
+    async fn query_identity(&mut self, address: impl Into<String>) -> Result<ClientIdentityResp> {
+        self.query(&ClientQueryMsg::Identity { address: address.into() }).await
+    }
+
+    async fn query_nonce(&mut self, user: impl Into<String>) -> Result<NonceResp> {
+        self.query(&ClientQueryMsg::Nonce { user: user.into() }).await
+    }
+
+    async fn query_outbox(&mut self) -> Result<OutboxResp> {
+        self.query(&ClientQueryMsg::Outbox {}).await
+    }
+
+    async fn query_profile(&mut self, user: impl Into<String>) -> Result<ProfileResp> {
+        self.query(&ClientQueryMsg::Profile { user: user.into() }).await
+    }
+
+    async fn query_polls(&mut self) -> Result<ClientPollsResp> {
+        self.query(&ClientQueryMsg::Polls {}).await
+    }
+
+    async fn query_message_tips(&mut self, index: Uint64) -> Result<TipsResp> {
+        self.query(&ClientQueryMsg::MessageTips { index }).await
+    }
+
+    async fn query_user_tips(&mut self, network_id: NetworkId, address: impl Into<String>) -> Result<TipsResp> {
+        self.query(&ClientQueryMsg::UserTips { network_id, address: address.into() }).await
+    }
+
+    async fn query_rooms(&mut self) -> Result<RoomsResp> {
+        self.query(&ClientQueryMsg::Rooms {}).await
+    }
+
+    async fn query_room_keys(&mut self, room: impl Into<String>, member: impl Into<String>) -> Result<RoomKeysResp> {
+        self.query(&ClientQueryMsg::RoomKeys { room: room.into(), member: member.into() }).await
+    }
+
+    async fn query_room_access(&mut self, room: impl Into<String>, user: impl Into<String>) -> Result<RoomAccessResp> {
+        self.query(&ClientQueryMsg::RoomAccess { room: room.into(), user: user.into() }).await
+    }
+
+    async fn query_hooks(&mut self) -> Result<HooksResp> {
+        self.query(&ClientQueryMsg::Hooks {}).await
     }
 
     async fn exec_send_message(&mut self, msg: impl Into<String>) -> Result<ExecResponse> {
-        self.exec(&ClientExecuteMsg::SendMessage { message: msg.into() }).await
+        self.exec(&ClientExecuteMsg::SendMessage { message: msg.into(), room: None, expires: None, content: None }).await
+    }
+
+    async fn exec_send_room_message(&mut self, room: impl Into<String>, msg: impl Into<String>) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::SendMessage { message: msg.into(), room: Some(room.into()), expires: None, content: None }).await
+    }
+
+    /// Anything but plain text, the text is the markdown, the caption or whatever goes with the content
+    async fn exec_send_content_message(&mut self, room: Option<String>, msg: impl Into<String>, content: MessageContent) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::SendMessage { message: msg.into(), room, expires: None, content: Some(content) }).await
+    }
+
+    async fn exec_send_expiring_message(&mut self, msg: impl Into<String>, expires: Expiration) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::SendMessage { message: msg.into(), room: None, expires: Some(expires), content: None }).await
+    }
+
+    async fn exec_send_signed_message(&mut self, signed: SignedMessage) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::SendSignedMessage { payload: signed.payload, pubkey: signed.pubkey, signature: signed.signature }).await
+    }
+
+    async fn exec_link_identity(&mut self, proof: IdentityLinkProof) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::LinkIdentity { address: proof.address, pubkey: proof.pubkey, signature: proof.signature }).await
+    }
+
+    async fn exec_buffer_message(&mut self, msg: impl Into<String>) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::BufferMessage { message: msg.into(), room: None, expires: None, content: None }).await
+    }
+
+    async fn exec_flush_buffer(&mut self) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::FlushBuffer {}).await
+    }
+
+    async fn exec_purge_outbox(&mut self) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::PurgeOutbox {}).await
+    }
+
+    async fn exec_block(&mut self, network_id: NetworkId, address: impl Into<String>) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::Block { network_id, address: address.into() }).await
+    }
+
+    async fn exec_unblock(&mut self, network_id: NetworkId, address: impl Into<String>) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::Unblock { network_id, address: address.into() }).await
+    }
+
+    async fn exec_set_read_cursor(&mut self, room: Option<String>, index: Uint64) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::SetReadCursor { room, index }).await
+    }
+
+    async fn exec_pin(&mut self, index: Uint64) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::Pin { index }).await
+    }
+
+    async fn exec_unpin(&mut self, room: Option<String>, id: ChatMessageId) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::Unpin { room, id }).await
+    }
+
+    async fn exec_mark_read(&mut self, up_to: Option<Uint64>) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::MarkRead { up_to }).await
+    }
+
+    async fn exec_tip(&mut self, index: Uint64, funds: &[Coin]) -> Result<ExecResponse> {
+        self.exec_funds(&ClientExecuteMsg::Tip { index }, funds).await
+    }
+
+    async fn exec_set_transfer_channel(&mut self, network_id: NetworkId, channel_id: Option<String>) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::SetTransferChannel { network_id, channel_id }).await
+    }
+
+    async fn exec_create_room(&mut self, name: impl Into<String>, access: Option<RoomAccess>) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::CreateRoom { name: name.into(), access }).await
+    }
+
+    async fn exec_set_encryption_key(&mut self, key: Option<Binary>) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::SetEncryptionKey { key }).await
+    }
+
+    async fn exec_create_private_room(&mut self, name: impl Into<String>, keys: Vec<WrappedRoomKey>) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::CreatePrivateRoom { name: name.into(), keys }).await
+    }
+
+    async fn exec_add_room_member(&mut self, room: impl Into<String>, key: WrappedRoomKey) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::AddRoomMember { room: room.into(), key }).await
+    }
+
+    async fn exec_remove_room_member(&mut self, room: impl Into<String>, member: impl Into<String>, keys: Vec<WrappedRoomKey>) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::RemoveRoomMember { room: room.into(), member: member.into(), keys }).await
+    }
+
+    async fn exec_leave_room(&mut self, room: impl Into<String>) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::LeaveRoom { room: room.into() }).await
+    }
+
+    async fn exec_rotate_room_key(&mut self, room: impl Into<String>, keys: Vec<WrappedRoomKey>) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::RotateRoomKey { room: room.into(), keys }).await
+    }
+
+    /// Encrypts the text with the room key of `epoch` and posts it to the private room
+    async fn exec_send_encrypted_message(&mut self, room: impl Into<String>, room_key: &RoomKey, epoch: u32, msg: &str) -> Result<ExecResponse> {
+        let text = encrypt_message(room_key, epoch, msg)?.to_message_text()?;
+        self.exec_send_room_message(room, text).await
+    }
+
+    async fn exec_set_room_access(&mut self, room: impl Into<String>, access: RoomAccess) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::SetRoomAccess { room: room.into(), access }).await
+    }
+
+    async fn exec_create_poll(&mut self, question: impl Into<String>, options: Vec<String>, closes_at: Timestamp) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::CreatePoll { question: question.into(), options, closes_at }).await
+    }
+
+    async fn exec_vote(&mut self, poll_id: PollId, option: u32) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::Vote { poll_id, option }).await
+    }
+
+    async fn exec_set_hook(&mut self, contract: impl Into<String>, filter: Option<HookFilter>) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::SetHook { contract: contract.into(), filter }).await
+    }
+
+    async fn exec_remove_hook(&mut self, contract: impl Into<String>) -> Result<ExecResponse> {
+        self.exec(&ClientExecuteMsg::RemoveHook { contract: contract.into() }).await
     }
 }
 
 // The specific "server" contract trait - all methods are automatically implemented on top of the generic traits
 // it's still a trait, since it can be implemented for different wallet types (on-chain, multitest, etc.)
 
-pub trait ContractServer: ContractQuery<ServerQueryMsg> {
+pub trait ContractServer<ExecResponse>: ContractQuery<ServerQueryMsg> + ContractExec<ServerExecuteMsg, ExecResponse> {
     async fn query_info(&mut self) -> Result<ServerInfoResp> {
         self.query(&ServerQueryMsg::Info {}).await
     }
+
+    async fn query_poll(&mut self, poll_id: PollId) -> Result<PollResp> {
+        self.query(&ServerQueryMsg::Poll { poll_id }).await
+    }
+
+    async fn query_polls(&mut self) -> Result<ServerPollsResp> {
+        self.query(&ServerQueryMsg::Polls {}).await
+    }
+
+    async fn query_identity(&mut self, identity: impl Into<String>) -> Result<ServerIdentityResp> {
+        self.query(&ServerQueryMsg::Identity { identity: identity.into() }).await
+    }
+
+    async fn exec_close_polls(&mut self) -> Result<ExecResponse> {
+        self.exec(&ServerExecuteMsg::ClosePolls {}).await
+    }
+
+    async fn exec_post_from_transfer(&mut self, post: TransferChatPost, funds: &[Coin]) -> Result<ExecResponse> {
+        self.exec_funds(&ServerExecuteMsg::PostFromTransfer { post }, funds).await
+    }
+
+    async fn exec_set_memo_channel(&mut self, channel_id: impl Into<String>, network_id: Option<NetworkId>) -> Result<ExecResponse> {
+        self.exec(&ServerExecuteMsg::SetMemoChannel { channel_id: channel_id.into(), network_id }).await
+    }
+
+    async fn exec_set_client_connection(&mut self, connection_id: impl Into<String>, network_id: Option<NetworkId>) -> Result<ExecResponse> {
+        self.exec(&ServerExecuteMsg::SetClientConnection { connection_id: connection_id.into(), network_id }).await
+    }
+
+    async fn exec_set_network_notices(&mut self, enabled: bool) -> Result<ExecResponse> {
+        self.exec(&ServerExecuteMsg::SetNetworkNotices { enabled }).await
+    }
+
+    async fn exec_announce(&mut self, message: impl Into<String>, content: Option<MessageContent>) -> Result<ExecResponse> {
+        self.exec(&ServerExecuteMsg::Announce { message: message.into(), content }).await
+    }
+
+    async fn exec_pin(&mut self, room: Option<String>, id: ChatMessageId) -> Result<ExecResponse> {
+        self.exec(&ServerExecuteMsg::Pin { room, id }).await
+    }
+
+    async fn exec_unpin(&mut self, room: Option<String>, id: ChatMessageId) -> Result<ExecResponse> {
+        self.exec(&ServerExecuteMsg::Unpin { room, id }).await
+    }
 }
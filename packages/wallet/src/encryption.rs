@@ -0,0 +1,91 @@
+/// Encrypting and decrypting private room messages, see [shared::msg::encryption]
+use anyhow::{anyhow, bail, Result};
+use chacha20poly1305::{aead::Aead, ChaCha20Poly1305, KeyInit, Nonce};
+use cosmwasm_std::{Addr, Binary};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use shared::msg::encryption::{EncryptedMessage, WrappedRoomKey, ENCRYPTION_KEY_LEN};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+pub type RoomKey = [u8; ENCRYPTION_KEY_LEN];
+
+const WRAP_INFO: &[u8] = b"chat-room-key-wrap";
+const NONCE_LEN: usize = 12;
+
+/// The public key to publish with `SetEncryptionKey`, for a 32 byte secret
+pub fn encryption_pubkey(secret_key: &[u8]) -> Result<Binary> {
+    let secret = static_secret(secret_key)?;
+    Ok(Binary::from(PublicKey::from(&secret).as_bytes().as_slice()))
+}
+
+/// A fresh random room key, made when a room is created and on every rotation
+pub fn new_room_key() -> RoomKey {
+    rand::random()
+}
+
+/// Encrypts the room key for one member, with their published encryption key
+pub fn wrap_room_key(room_key: &RoomKey, member: impl Into<String>, member_pubkey: &[u8]) -> Result<WrappedRoomKey> {
+    let member_pubkey: [u8; ENCRYPTION_KEY_LEN] = member_pubkey.try_into().map_err(|_| anyhow!("an encryption key has {} bytes", ENCRYPTION_KEY_LEN))?;
+    let ephemeral = StaticSecret::random_from_rng(rand::thread_rng());
+    let ephemeral_pubkey = PublicKey::from(&ephemeral);
+    let shared = ephemeral.diffie_hellman(&PublicKey::from(member_pubkey));
+
+    let (nonce, ciphertext) = seal(&wrapping_key(shared.as_bytes(), ephemeral_pubkey.as_bytes())?, room_key)?;
+
+    Ok(WrappedRoomKey {
+        member: Addr::unchecked(member),
+        ephemeral_pubkey: Binary::from(ephemeral_pubkey.as_bytes().as_slice()),
+        nonce,
+        ciphertext,
+    })
+}
+
+/// The room key in a [WrappedRoomKey] made for the holder of `secret_key`
+pub fn unwrap_room_key(secret_key: &[u8], wrapped: &WrappedRoomKey) -> Result<RoomKey> {
+    let secret = static_secret(secret_key)?;
+    let ephemeral_pubkey: [u8; ENCRYPTION_KEY_LEN] = wrapped.ephemeral_pubkey.as_slice().try_into().map_err(|_| anyhow!("bad ephemeral key"))?;
+    let shared = secret.diffie_hellman(&PublicKey::from(ephemeral_pubkey));
+
+    let key = open(&wrapping_key(shared.as_bytes(), &ephemeral_pubkey)?, &wrapped.nonce, &wrapped.ciphertext)?;
+    key.try_into().map_err(|_| anyhow!("room keys have {} bytes", ENCRYPTION_KEY_LEN))
+}
+
+/// Encrypts the text with the room key of `epoch`, the result goes in the message text
+pub fn encrypt_message(room_key: &RoomKey, epoch: u32, text: &str) -> Result<EncryptedMessage> {
+    let (nonce, ciphertext) = seal(room_key, text.as_bytes())?;
+    Ok(EncryptedMessage { epoch, nonce, ciphertext })
+}
+
+pub fn decrypt_message(room_key: &RoomKey, msg: &EncryptedMessage) -> Result<String> {
+    Ok(String::from_utf8(open(room_key, &msg.nonce, &msg.ciphertext)?)?)
+}
+
+fn static_secret(secret_key: &[u8]) -> Result<StaticSecret> {
+    let bytes: [u8; ENCRYPTION_KEY_LEN] = secret_key.try_into().map_err(|_| anyhow!("an encryption secret has {} bytes", ENCRYPTION_KEY_LEN))?;
+    Ok(StaticSecret::from(bytes))
+}
+
+fn wrapping_key(shared: &[u8], ephemeral_pubkey: &[u8]) -> Result<RoomKey> {
+    let mut key = [0u8; ENCRYPTION_KEY_LEN];
+    Hkdf::<Sha256>::new(Some(ephemeral_pubkey), shared)
+        .expand(WRAP_INFO, &mut key)
+        .map_err(|_| anyhow!("couldn't derive the wrapping key"))?;
+    Ok(key)
+}
+
+fn seal(key: &RoomKey, plaintext: &[u8]) -> Result<(Binary, Binary)> {
+    let nonce: [u8; NONCE_LEN] = rand::random();
+    let ciphertext = ChaCha20Poly1305::new(key.into())
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| anyhow!("encryption failed"))?;
+    Ok((Binary::from(nonce.as_slice()), Binary::from(ciphertext)))
+}
+
+fn open(key: &RoomKey, nonce: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+    if nonce.len() != NONCE_LEN {
+        bail!("nonces have {} bytes", NONCE_LEN);
+    }
+    ChaCha20Poly1305::new(key.into())
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow!("couldn't decrypt, wrong key?"))
+}
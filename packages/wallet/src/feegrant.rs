@@ -0,0 +1,119 @@
+/// Fee grants: the sponsor's `MsgGrantAllowance`, and the fee of a grantee's tx that the sponsor pays
+/// built here rather than in wallet.js, cosmjs only signs and broadcasts them
+use anyhow::Result;
+use cosmwasm_std::{Coin, Uint128};
+use serde::{Deserialize, Serialize};
+
+pub const MSG_GRANT_ALLOWANCE: &str = "/cosmos.feegrant.v1beta1.MsgGrantAllowance";
+pub const BASIC_ALLOWANCE: &str = "/cosmos.feegrant.v1beta1.BasicAllowance";
+pub const ALLOWED_MSG_ALLOWANCE: &str = "/cosmos.feegrant.v1beta1.AllowedMsgAllowance";
+
+// same as cosmjs' "auto" fee
+const GAS_MULTIPLIER: f64 = 1.4;
+
+/// cosmos.feegrant.v1beta1.MsgGrantAllowance
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct MsgGrantAllowance {
+    #[prost(string, tag = "1")]
+    pub granter: String,
+    #[prost(string, tag = "2")]
+    pub grantee: String,
+    #[prost(message, optional, tag = "3")]
+    pub allowance: Option<ProtoAny>,
+}
+
+/// cosmos.feegrant.v1beta1.AllowedMsgAllowance
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct AllowedMsgAllowance {
+    #[prost(message, optional, tag = "1")]
+    pub allowance: Option<ProtoAny>,
+    #[prost(string, repeated, tag = "2")]
+    pub allowed_messages: Vec<String>,
+}
+
+/// cosmos.feegrant.v1beta1.BasicAllowance
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct BasicAllowance {
+    #[prost(message, repeated, tag = "1")]
+    pub spend_limit: Vec<ProtoCoin>,
+    #[prost(message, optional, tag = "2")]
+    pub expiration: Option<ProtoTimestamp>,
+}
+
+/// google.protobuf.Any
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoAny {
+    #[prost(string, tag = "1")]
+    pub type_url: String,
+    #[prost(bytes = "vec", tag = "2")]
+    pub value: Vec<u8>,
+}
+
+/// cosmos.base.v1beta1.Coin
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoCoin {
+    #[prost(string, tag = "1")]
+    pub denom: String,
+    #[prost(string, tag = "2")]
+    pub amount: String,
+}
+
+/// google.protobuf.Timestamp
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoTimestamp {
+    #[prost(int64, tag = "1")]
+    pub seconds: i64,
+    #[prost(int32, tag = "2")]
+    pub nanos: i32,
+}
+
+/// A basic allowance of `spend_limit` until `expiration_seconds`, only for the `allowed_messages` type urls
+pub fn grant_allowance_msg(
+    granter: &str,
+    grantee: &str,
+    spend_limit: &[Coin],
+    expiration_seconds: Option<u64>,
+    allowed_messages: Vec<String>,
+) -> Result<MsgGrantAllowance> {
+    let basic = BasicAllowance {
+        spend_limit: spend_limit
+            .iter()
+            .map(|coin| ProtoCoin { denom: coin.denom.clone(), amount: coin.amount.to_string() })
+            .collect(),
+        expiration: expiration_seconds
+            .map(|seconds| Ok::<_, anyhow::Error>(ProtoTimestamp { seconds: seconds.try_into()?, nanos: 0 }))
+            .transpose()?,
+    };
+
+    let allowed = AllowedMsgAllowance {
+        allowance: Some(ProtoAny { type_url: BASIC_ALLOWANCE.to_string(), value: prost::Message::encode_to_vec(&basic) }),
+        allowed_messages,
+    };
+
+    Ok(MsgGrantAllowance {
+        granter: granter.to_string(),
+        grantee: grantee.to_string(),
+        allowance: Some(ProtoAny { type_url: ALLOWED_MSG_ALLOWANCE.to_string(), value: prost::Message::encode_to_vec(&allowed) }),
+    })
+}
+
+/// The fee of a tx, as cosmjs' StdFee, paid from the granter's allowance
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GrantedFee {
+    pub amount: Vec<Coin>,
+    pub gas: String,
+    pub granter: String,
+}
+
+/// The fee for a tx that simulated to `gas_used`, at the chain's `gas_price` in `denom`
+/// cosmjs' "auto" fee can't name a granter, so this works it out the same way
+pub fn granted_fee(gas_used: u64, gas_price: &str, denom: &str, granter: &str) -> Result<GrantedFee> {
+    let gas = (gas_used as f64 * GAS_MULTIPLIER).round();
+    let amount = (gas * gas_price.parse::<f64>()?).ceil();
+
+    Ok(GrantedFee {
+        amount: vec![Coin { denom: denom.to_string(), amount: Uint128::new(amount as u128) }],
+        gas: (gas as u64).to_string(),
+        granter: granter.to_string(),
+    })
+}
@@ -5,6 +5,9 @@ pub mod response_types;
 pub mod bindings;
 pub mod contract_traits;
 pub mod wallet_contract_impls;
+pub mod signed;
+pub mod encryption;
+pub mod feegrant;
 pub mod prelude;
 
 #[cfg(feature = "node")]
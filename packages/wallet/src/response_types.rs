@@ -173,4 +173,12 @@ pub struct IndexedTx {
     pub gas_used: u64,
     #[serde(rename = "gasWanted")]
     pub gas_wanted: u64,
-}
\ No newline at end of file
+}
+/// A fee allowance the wallet has granted, as the feegrant REST endpoint returns it
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FeeGrant {
+    pub granter: String,
+    pub grantee: String,
+    // the nested allowance, kept as JSON since its shape depends on the type
+    pub allowance: serde_json::Value,
+}
@@ -0,0 +1,62 @@
+/// Signing chat messages for someone else to submit, see [shared::msg::signed]
+/// and statements linking a key to an identity, see [shared::msg::identity]
+use anyhow::Result;
+use bech32::{Bech32, Hrp};
+use cosmwasm_std::{to_json_vec, Binary};
+use k256::ecdsa::{signature::hazmat::PrehashSigner, Signature, SigningKey};
+use shared::msg::{
+    identity::IdentityLinkStatement,
+    signed::{adr036_sign_hash, pubkey_to_canonical, SignedMessage, SignedPayload},
+};
+
+/// The address a secp256k1 secret key signs as, with the chain's bech32 prefix
+pub fn signer_address(secret_key: &[u8], prefix: &str) -> Result<String> {
+    let key = SigningKey::from_slice(secret_key)?;
+    let pubkey = key.verifying_key().to_encoded_point(true);
+
+    Ok(bech32::encode::<Bech32>(Hrp::parse(prefix)?, &pubkey_to_canonical(pubkey.as_bytes()))?)
+}
+
+/// Signs the payload as an ADR-036 message from [signer_address], ready for `SendSignedMessage`
+pub fn sign_chat_payload(secret_key: &[u8], prefix: &str, payload: &SignedPayload) -> Result<SignedMessage> {
+    let key = SigningKey::from_slice(secret_key)?;
+    let pubkey = key.verifying_key().to_encoded_point(true);
+    let signer = signer_address(secret_key, prefix)?;
+
+    let data = to_json_vec(payload)?;
+    let signature: Signature = key.sign_prehash(&adr036_sign_hash(&signer, &data))?;
+    // the SDK only takes the low-s form
+    let signature = signature.normalize_s().unwrap_or(signature);
+
+    Ok(SignedMessage {
+        payload: Binary::from(data),
+        pubkey: Binary::from(pubkey.as_bytes()),
+        signature: Binary::from(signature.to_bytes().as_slice()),
+    })
+}
+
+/// Everything `LinkIdentity` needs from the joining key
+#[derive(Debug, Clone)]
+pub struct IdentityLinkProof {
+    pub address: String,
+    pub pubkey: Binary,
+    pub signature: Binary,
+}
+
+/// Signs, as the key's address with `prefix`, that it joins `identity`
+/// the address submitting the link must be of that identity
+pub fn sign_identity_link(secret_key: &[u8], prefix: &str, identity: &str) -> Result<IdentityLinkProof> {
+    let key = SigningKey::from_slice(secret_key)?;
+    let pubkey = key.verifying_key().to_encoded_point(true);
+    let address = signer_address(secret_key, prefix)?;
+
+    let statement = to_json_vec(&IdentityLinkStatement { link_identity: identity.to_string() })?;
+    let signature: Signature = key.sign_prehash(&adr036_sign_hash(&address, &statement))?;
+    let signature = signature.normalize_s().unwrap_or(signature);
+
+    Ok(IdentityLinkProof {
+        address,
+        pubkey: Binary::from(pubkey.as_bytes()),
+        signature: Binary::from(signature.to_bytes().as_slice()),
+    })
+}
@@ -5,14 +5,14 @@
 
 use std::cell::RefCell;
 
-use crate::{bindings::{cosmjs::CosmJs, wallet_ffi::*}, config::*, contract_traits::*, response_types::*, wallet_contract_impls::{WalletSigningContractClient, WalletSigningContractServer}};
+use crate::{bindings::{cosmjs::CosmJs, wallet_ffi::*}, config::*, contract_traits::*, feegrant::{grant_allowance_msg, granted_fee}, response_types::*, wallet_contract_impls::{WalletSigningContractClient, WalletSigningContractServer}};
 use base64::{prelude::BASE64_STANDARD, Engine};
 use cosmwasm_std::{Addr, Coin};
 use js_sys::Uint8Array;
 use serde::{de::DeserializeOwned, Serialize};
 use shared::{contract_kind::ContractKind, msg::network::NetworkId};
 use wasm_bindgen::prelude::*;
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 
 thread_local! {
     static WALLET: Wallet = {
@@ -192,6 +192,42 @@ impl WalletSigning {
         Ok(resp)
     }
 
+    /// Like [Self::contract_exec_funds], with the fee paid from the granter's fee allowance
+    pub async fn contract_exec_granted<MSG: Serialize>(
+        &self,
+        addr: &str,
+        msg: &MSG,
+        funds: &[Coin],
+        granter: &str,
+    ) -> Result<TxResp> {
+        let gas_used: u64 = json_deserialize_result(ffi_contract_exec_simulate(self, addr, json_serialize(msg)?, json_serialize(funds)?).await)?;
+        let fee = granted_fee(gas_used, &self.gas_price(), &self.denom(), granter)?;
+
+        json_deserialize_result(ffi_contract_exec_fee(self, addr, json_serialize(msg)?, json_serialize(funds)?, json_serialize(&fee)?).await)
+    }
+
+    /// Grants `grantee` a fee allowance from this wallet, only for the given message types
+    pub async fn fee_grant(
+        &self,
+        grantee: &str,
+        spend_limit: &[Coin],
+        expiration_seconds: Option<u64>,
+        allowed_messages: Vec<String>,
+    ) -> Result<TxResp> {
+        let msg = grant_allowance_msg(&self.address(), grantee, spend_limit, expiration_seconds, allowed_messages)?;
+        let allowance = msg.allowance.context("no allowance in the grant")?;
+        json_deserialize_result(ffi_fee_grant(self, grantee, &allowance.type_url, &allowance.value).await)
+    }
+
+    pub async fn fee_revoke(&self, grantee: &str) -> Result<TxResp> {
+        json_deserialize_result(ffi_fee_revoke(self, grantee).await)
+    }
+
+    /// Every fee allowance this wallet has granted
+    pub async fn fee_grants(&self) -> Result<Vec<FeeGrant>> {
+        json_deserialize_result(ffi_fee_grants(self).await)
+    }
+
     pub async fn contract_code_details(
         &self,
         code_id: u32,
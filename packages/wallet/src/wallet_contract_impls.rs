@@ -6,7 +6,7 @@ use cosmwasm_std::Coin;
 use serde::de::DeserializeOwned;
 use shared::{contract_kind::ContractKind, msg::contract::{
     client::{ChatMessagesResp, ExecuteMsg as ClientExecuteMsg, InfoResp as ClientInfoResp, QueryMsg as ClientQueryMsg},
-    server::{InfoResp as ServerInfoResp, QueryMsg as ServerQueryMsg},
+    server::{ExecuteMsg as ServerExecuteMsg, InfoResp as ServerInfoResp, QueryMsg as ServerQueryMsg},
 }};
 use anyhow::Result;
 
@@ -15,6 +15,8 @@ use anyhow::Result;
 #[derive(Clone)]
 pub struct WalletSigningContractClient {
     pub wallet: WalletSigning,
+    /// If set, fees for executes are paid from this account's fee allowance
+    pub fee_granter: Option<String>,
 }
 
 // where it all gets tied together :)
@@ -22,7 +24,12 @@ impl ContractClient<TxResp> for WalletSigningContractClient {}
 
 impl WalletSigningContractClient {
     pub fn new(wallet: WalletSigning) -> Self {
-        Self { wallet }
+        Self { wallet, fee_granter: None }
+    }
+
+    pub fn with_fee_granter(mut self, granter: impl Into<String>) -> Self {
+        self.fee_granter = Some(granter.into());
+        self
     }
 }
 
@@ -40,11 +47,17 @@ impl ContractQuery<ClientQueryMsg> for WalletSigningContractClient {
 
 impl ContractExec<ClientExecuteMsg, TxResp> for WalletSigningContractClient {
     async fn exec(&mut self, msg: &ClientExecuteMsg) -> Result<TxResp> {
-        self.wallet.contract_exec(&self.address(), msg).await
+        match &self.fee_granter {
+            Some(granter) => self.wallet.contract_exec_granted(&self.address(), msg, &[], granter).await,
+            None => self.wallet.contract_exec(&self.address(), msg).await,
+        }
     }
 
     async fn exec_funds(&mut self, msg: &ClientExecuteMsg, funds: &[Coin]) -> Result<TxResp> {
-        self.wallet.contract_exec_funds(&self.address(), msg, funds).await
+        match &self.fee_granter {
+            Some(granter) => self.wallet.contract_exec_granted(&self.address(), msg, funds, granter).await,
+            None => self.wallet.contract_exec_funds(&self.address(), msg, funds).await,
+        }
     }
 }
 
@@ -56,7 +69,7 @@ pub struct WalletSigningContractServer {
 }
 
 // where it all gets tied together :)
-impl ContractServer for WalletSigningContractServer {}
+impl ContractServer<TxResp> for WalletSigningContractServer {}
 
 impl WalletSigningContractServer {
     pub fn new(wallet: WalletSigning) -> Self {
@@ -73,4 +86,14 @@ impl ContractQuery<ServerQueryMsg> for WalletSigningContractServer {
     async fn query<RESP: DeserializeOwned>(&mut self, msg: &ServerQueryMsg) -> Result<RESP> {
         self.wallet.contract_query(&self.address(), msg).await
     }
-}
\ No newline at end of file
+}
+
+impl ContractExec<ServerExecuteMsg, TxResp> for WalletSigningContractServer {
+    async fn exec(&mut self, msg: &ServerExecuteMsg) -> Result<TxResp> {
+        self.wallet.contract_exec(&self.address(), msg).await
+    }
+
+    async fn exec_funds(&mut self, msg: &ServerExecuteMsg, funds: &[Coin]) -> Result<TxResp> {
+        self.wallet.contract_exec_funds(&self.address(), msg, funds).await
+    }
+}